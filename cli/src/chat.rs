@@ -0,0 +1,352 @@
+// Storm node providing distributed storage & messaging for lightning network.
+//
+// Written in 2022 by
+//     Dr. Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2022 by LNP/BP Standards Association, Switzerland.
+//
+// You should have received a copy of the MIT License along with this software.
+// If not, see <https://opensource.org/licenses/MIT>.
+
+//! Interactive chat session, sending typed-in messages and printing the received ones in a single
+//! terminal.
+//!
+//! Typed-in keys are read by a separate thread, while the main thread owns the RPC client and
+//! alternates between waiting for chat messages with a short timeout and sending the typed-in
+//! ones. In a terminal the input line is kept at the bottom of the screen and messages are printed
+//! above it, remaining in the terminal scrollback. If the input is not a terminal, the output
+//! format is not text or the system is not a unix one, lines are read from the input and messages
+//! are printed one per line.
+
+use std::io::{self, BufRead, Read, Write};
+#[cfg(unix)]
+use std::os::unix::io::RawFd;
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use internet2::addr::NodeId;
+#[cfg(unix)]
+use nix::sys::termios::{self, InputFlags, LocalFlags, SetArg, SpecialCharacterIndices, Termios};
+#[cfg(unix)]
+use nix::unistd::isatty;
+use storm_rpc::{ChatDirection, ChatRecord, Client, Timeouts};
+
+use crate::command::Error;
+use crate::output::{ChatLine, DeliveryStatus};
+use crate::Format;
+
+/// Interval of checking for typed-in messages while waiting for the incoming ones
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Time to wait for the node to accept a sent message, if the user has not set timeouts
+const SEND_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Typed-in line ending the session
+const QUIT_COMMAND: &str = "/quit";
+
+const PROMPT: &str = "> ";
+
+#[cfg(unix)]
+const STDIN: RawFd = 0;
+#[cfg(unix)]
+const STDOUT: RawFd = 1;
+
+/// Input read by the input thread
+enum Input {
+    /// Key typed in the terminal
+    Key(Key),
+    /// Line read from the input which is not a terminal
+    Line(String),
+    /// Input is closed
+    Eof,
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+enum Key {
+    Char(char),
+    Enter,
+    Backspace,
+    /// Ctrl-U
+    ClearLine,
+    /// Ctrl-C
+    Interrupt,
+    /// Ctrl-D
+    EndOfText,
+}
+
+/// Runs chat session with the remote peer until the user quits or the input is closed.
+pub fn run(
+    client: &mut Client,
+    format: Format,
+    remote_id: NodeId,
+    history: usize,
+) -> Result<(), Error> {
+    // Subscribing before loading the history, so no messages are lost in between
+    client.chat_subscribe(remote_id, None)?;
+    let records = client.chat_history(remote_id)?;
+
+    let raw_mode = match format {
+        Format::Text => RawMode::enable(),
+        _ => None,
+    };
+    let mut view = View {
+        format,
+        interactive: raw_mode.is_some(),
+        input: s!(""),
+    };
+
+    format
+        .notice(format!("Chat with {}; type {} or press Ctrl-D to exit", remote_id, QUIT_COMMAND));
+    for record in &records[records.len().saturating_sub(history)..] {
+        view.print(ChatLine::from(record));
+    }
+    view.prompt();
+
+    let inputs = spawn_input(view.interactive);
+    let timeouts = client.timeouts();
+    let send_timeouts =
+        if timeouts == default!() { Timeouts::idle(SEND_TIMEOUT) } else { timeouts };
+    loop {
+        loop {
+            let text = match inputs.try_recv() {
+                Ok(Input::Key(key)) => match view.key(key) {
+                    Some(Action::Send(text)) => text,
+                    Some(Action::Quit) => return Ok(()),
+                    None => continue,
+                },
+                Ok(Input::Line(text)) => text,
+                Ok(Input::Eof) | Err(TryRecvError::Disconnected) => return Ok(()),
+                Err(TryRecvError::Empty) => break,
+            };
+            if text.trim() == QUIT_COMMAND {
+                return Ok(());
+            }
+            if !text.trim().is_empty() {
+                client.set_timeouts(send_timeouts);
+                let status = match client.chat_tell(remote_id, text.clone()) {
+                    Ok(()) => DeliveryStatus::Sent,
+                    Err(err) => DeliveryStatus::Failed(err.to_string()),
+                };
+                view.print(ChatLine {
+                    timestamp: unix_time(),
+                    direction: ChatDirection::Outgoing,
+                    text,
+                    protection: None,
+                    status: Some(status),
+                });
+            }
+        }
+
+        client.set_timeouts(Timeouts::idle(POLL_INTERVAL));
+        let received = client.chat_recv(remote_id);
+        match received {
+            Ok(msg) => view.print(ChatLine::from(&ChatRecord {
+                timestamp: unix_time(),
+                direction: ChatDirection::Incoming,
+                msg,
+            })),
+            // The peer is silent
+            Err(storm_rpc::Error::Timeout(_)) => {}
+            Err(err) => return Err(err.into()),
+        }
+    }
+}
+
+enum Action {
+    Send(String),
+    Quit,
+}
+
+/// Output of the chat session
+struct View {
+    format: Format,
+    /// Whether the input line is edited in the terminal by the view
+    interactive: bool,
+    /// Text typed in the input line
+    input: String,
+}
+
+impl View {
+    /// Prints message; in interactive mode the input line is redrawn below it.
+    fn print(&mut self, line: ChatLine) {
+        match self.format {
+            Format::Text if self.interactive => {
+                print!("\r\x1b[K{}\n", line);
+                self.prompt();
+            }
+            Format::Text => println!("{}", line),
+            format => format.result(&line, |_| {}),
+        }
+    }
+
+    /// Redraws the input line.
+    fn prompt(&self) {
+        if self.interactive {
+            print!("\r\x1b[K{}{}", PROMPT, self.input);
+            let _ = io::stdout().flush();
+        }
+    }
+
+    /// Edits the input line.
+    fn key(&mut self, key: Key) -> Option<Action> {
+        match key {
+            Key::Char(c) => self.input.push(c),
+            Key::Backspace => {
+                self.input.pop();
+            }
+            Key::ClearLine => self.input.clear(),
+            Key::Enter => {
+                let text = std::mem::take(&mut self.input);
+                self.prompt();
+                return Some(Action::Send(text));
+            }
+            Key::EndOfText if !self.input.is_empty() => {}
+            Key::EndOfText | Key::Interrupt => {
+                print!("\r\x1b[K");
+                let _ = io::stdout().flush();
+                return Some(Action::Quit);
+            }
+        }
+        self.prompt();
+        None
+    }
+}
+
+/// Terminal mode in which typed-in keys are passed to the tool as they are, without echo and
+/// line editing. Original mode is restored on drop.
+///
+/// Raw mode is supported on unix systems only; on other systems the session uses line mode.
+#[cfg_attr(not(unix), allow(dead_code))]
+struct RawMode {
+    #[cfg(unix)]
+    original: Termios,
+}
+
+impl RawMode {
+    /// Switches the terminal to raw mode. Returns `None` if the input or the output is not a
+    /// terminal.
+    #[cfg(unix)]
+    fn enable() -> Option<RawMode> {
+        if !isatty(STDIN).unwrap_or_default() || !isatty(STDOUT).unwrap_or_default() {
+            return None;
+        }
+        let original = termios::tcgetattr(STDIN).ok()?;
+        let mut raw = original.clone();
+        raw.local_flags
+            .remove(LocalFlags::ICANON | LocalFlags::ECHO | LocalFlags::ISIG | LocalFlags::IEXTEN);
+        raw.input_flags.remove(InputFlags::IXON | InputFlags::ICRNL);
+        raw.control_chars[SpecialCharacterIndices::VMIN as usize] = 1;
+        raw.control_chars[SpecialCharacterIndices::VTIME as usize] = 0;
+        termios::tcsetattr(STDIN, SetArg::TCSANOW, &raw).ok()?;
+        Some(RawMode { original })
+    }
+
+    #[cfg(not(unix))]
+    fn enable() -> Option<RawMode> { None }
+}
+
+#[cfg(unix)]
+impl Drop for RawMode {
+    fn drop(&mut self) {
+        if let Err(err) = termios::tcsetattr(STDIN, SetArg::TCSANOW, &self.original) {
+            eprintln!("Unable to restore terminal mode: {}", err);
+        }
+    }
+}
+
+/// Starts thread reading the standard input, either key by key in a terminal or line by line.
+fn spawn_input(keys: bool) -> Receiver<Input> {
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || {
+        if keys {
+            read_keys(|key| tx.send(Input::Key(key)).is_ok());
+        } else {
+            for line in io::stdin().lock().lines() {
+                let line = match line {
+                    Ok(line) => line,
+                    Err(_) => break,
+                };
+                if tx.send(Input::Line(line)).is_err() {
+                    break;
+                }
+            }
+        }
+        let _ = tx.send(Input::Eof);
+    });
+    rx
+}
+
+/// State of parsing escape sequence sent by the terminal
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+enum Escape {
+    None,
+    /// `ESC` is received
+    Started,
+    /// Sequence parameters are being received
+    Sequence,
+}
+
+/// Decodes keys from the terminal input until it is closed or `on_key` returns `false`. Escape
+/// sequences of the special keys, like arrows, are skipped.
+fn read_keys(mut on_key: impl FnMut(Key) -> bool) {
+    let mut utf8 = Vec::with_capacity(4);
+    let mut escape = Escape::None;
+    for byte in io::stdin().lock().bytes() {
+        let byte = match byte {
+            Ok(byte) => byte,
+            Err(_) => return,
+        };
+        match (escape, byte) {
+            (Escape::None, _) => {}
+            (Escape::Started, b'[' | b'O') => {
+                escape = Escape::Sequence;
+                continue;
+            }
+            // Sequence ends with a byte in 0x40..=0x7E range
+            (Escape::Sequence, 0x20..=0x3F) => continue,
+            (Escape::Started | Escape::Sequence, _) => {
+                escape = Escape::None;
+                continue;
+            }
+        }
+        let key = match byte {
+            0x1B => {
+                escape = Escape::Started;
+                continue;
+            }
+            b'\r' | b'\n' => Key::Enter,
+            0x7F | 0x08 => Key::Backspace,
+            0x15 => Key::ClearLine,
+            0x03 => Key::Interrupt,
+            0x04 => Key::EndOfText,
+            0x00..=0x1F => continue,
+            _ => {
+                utf8.push(byte);
+                match std::str::from_utf8(&utf8) {
+                    Ok(s) => {
+                        let c = s.chars().next().expect("non-empty string");
+                        utf8.clear();
+                        Key::Char(c)
+                    }
+                    // Incomplete multi-byte character
+                    Err(err) if err.error_len().is_none() => continue,
+                    Err(_) => {
+                        utf8.clear();
+                        continue;
+                    }
+                }
+            }
+        };
+        if !on_key(key) {
+            return;
+        }
+    }
+}
+
+fn unix_time() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or_default()
+}
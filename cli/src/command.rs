@@ -8,18 +8,16 @@
 // You should have received a copy of the MIT License along with this software.
 // If not, see <https://opensource.org/licenses/MIT>.
 
-use std::io::{BufRead, Write};
-use std::{fs, io};
+use std::io::{self, BufRead};
 
-use amplify::num::u24;
 use amplify::IoError;
 use internet2::addr::PartialNodeAddr;
 use lnp::addr::LnpAddr;
-use microservices::rpc::ServerError;
-use storm::{Chunk, Container, ContainerHeader};
-use strict_encoding::{MediumVec, StrictDecode, StrictEncode};
+use microservices::rpc::{self, ServerError};
+use storm_rpc::{CatalogFilter, ContainerFilter, ContainerizeOptions, FailureCode};
 
-use crate::{Command, Opts};
+use crate::output::{Assembled, Containerized};
+use crate::{chat, Command, Opts};
 
 #[derive(Debug, Display, Error, From)]
 #[display(inner)]
@@ -35,10 +33,59 @@ pub enum Error {
     Store(ServerError<store_rpc::FailureCode>),
 
     #[from]
-    Lnp(lnp_rpc::Error),
+    StrictEncoding(strict_encoding::Error),
 
     #[from]
-    StrictEncoding(strict_encoding::Error),
+    Containerize(storm_rpc::ContainerizeError),
+
+    #[from]
+    Assemble(storm_rpc::AssembleError),
+
+    #[from]
+    Encryption(storm_rpc::EncryptionError),
+}
+
+impl Error {
+    /// Returns process exit code for the error, derived from the node failure code. The codes are
+    /// listed in the `EXIT STATUS` section of the tool help.
+    pub fn exit_code(&self) -> i32 {
+        let storm = match self {
+            Error::Storm(err) => err,
+            Error::Store(_) => return 11,
+            _ => return 1,
+        };
+        match storm {
+            storm_rpc::Error::Esb(_) | storm_rpc::Error::ClientTerminated => return 3,
+            storm_rpc::Error::UnexpectedServerResponse => return 14,
+            _ => {}
+        }
+        match storm.failure_code() {
+            Some(rpc::FailureCode::Other(code)) => match code {
+                FailureCode::Timeout => 4,
+                FailureCode::UnknownContainer => 5,
+                FailureCode::PinnedContainer => 6,
+                FailureCode::QuotaExceeded => 7,
+                FailureCode::PolicyRejected => 8,
+                FailureCode::UnknownPeer => 9,
+                FailureCode::PeerUnreachable => 10,
+                FailureCode::Store => 11,
+                FailureCode::TransferAutomation => 12,
+                FailureCode::Lnp => 13,
+                FailureCode::Encoding | FailureCode::UnexpectedRequest => 14,
+                FailureCode::Unauthenticated => 15,
+                FailureCode::PermissionDenied => 16,
+                FailureCode::TransfersInProgress => 17,
+                FailureCode::Esb | FailureCode::Launch | FailureCode::Unknown => 1,
+            },
+            Some(rpc::FailureCode::Transport) => 10,
+            Some(
+                rpc::FailureCode::Presentation
+                | rpc::FailureCode::Framing
+                | rpc::FailureCode::UnexpectedRequest,
+            ) => 14,
+            Some(rpc::FailureCode::Runtime) | None => 1,
+        }
+    }
 }
 
 impl Opts {
@@ -46,18 +93,52 @@ impl Opts {
         self,
         storm_client: &mut storm_rpc::Client,
         store_client: &mut store_rpc::Client,
-        lnp_client: &mut lnp_rpc::Client,
     ) -> Result<(), Error> {
-        let progress = |info| {
-            println!("{}", info);
-        };
+        let format = self.format;
+        let progress = |event| format.progress(event);
 
         debug!("Performing {:?}", self.command);
         match self.command {
+            Command::Peers => {
+                let peers = storm_client.peers()?;
+                format.result(&peers, |peers| {
+                    if peers.is_empty() {
+                        println!("No connected peers");
+                    }
+                    for peer in peers {
+                        match peer.addr {
+                            Some(addr) => {
+                                println!("{}@{}\t{}", peer.remote_id, addr, peer.storm)
+                            }
+                            None => println!("{}\t{}", peer.remote_id, peer.storm),
+                        }
+                    }
+                });
+            }
+            Command::Connect { peer } => {
+                let node_addr = LnpAddr::bifrost(peer).node_addr;
+                storm_client.connect(node_addr)?;
+                format.result(&node_addr, |_| println!("Connected to {}", peer));
+            }
+            Command::Disconnect { peer } => {
+                storm_client.disconnect(peer)?;
+                format.result(&peer, |peer| println!("Disconnected from {}", peer));
+            }
+            Command::Chat {
+                connect,
+                history,
+                peer,
+            } => {
+                if let Some(addr) = connect {
+                    let remote_node = PartialNodeAddr { id: peer, addr };
+                    storm_client.connect(LnpAddr::bifrost(remote_node).node_addr)?;
+                }
+                chat::run(storm_client, format, peer, history)?;
+            }
             Command::ChatSend { connect, peer } => {
                 if let Some(addr) = connect {
                     let remote_node = PartialNodeAddr { id: peer, addr };
-                    lnp_client.connect(LnpAddr::bifrost(remote_node))?;
+                    storm_client.connect(LnpAddr::bifrost(remote_node).node_addr)?;
                 }
                 let stdin = io::stdin();
                 for line in stdin.lock().lines() {
@@ -67,62 +148,81 @@ impl Opts {
             Command::ChatListen { connect, peer } => {
                 if let Some(addr) = connect {
                     let remote_node = PartialNodeAddr { id: peer, addr };
-                    lnp_client.connect(LnpAddr::bifrost(remote_node))?;
+                    storm_client.connect(LnpAddr::bifrost(remote_node).node_addr)?;
                 }
+                storm_client.chat_subscribe(peer, None)?;
                 loop {
-                    let line = storm_client.chat_recv(peer)?;
-                    println!("> {}", line);
+                    match storm_client.chat_recv(peer) {
+                        Ok(msg) => format.message(&msg),
+                        // Timeout here just means that the peer is silent
+                        Err(storm_rpc::Error::Timeout(_)) => continue,
+                        Err(err) => return Err(err.into()),
+                    }
                 }
             }
-            Command::Containerize { mime, path, info } => {
-                // TODO: Make this procedure part of Storm Core (containerization of arbitrary vec)
-                let data = fs::read(path)?;
-                let mut chunk_ids = MediumVec::new();
-                let size = data.len() as u64;
-                for piece in data.chunks(u24::MAX.into_usize()) {
-                    let chunk = Chunk::try_from(piece)?;
-                    let chunk_id = chunk.chunk_id();
-                    store_client.store(storm_rpc::DB_TABLE_CHUNKS, chunk_id, &chunk)?;
-                    chunk_ids.push(chunk_id)?;
+            Command::Containerize {
+                mime,
+                path,
+                info,
+                chunking,
+                compression,
+                encrypt_to,
+                seed,
+            } => {
+                let log = |info| format.notice(info);
+                let info = info.unwrap_or_default();
+                let options = ContainerizeOptions {
+                    chunking,
+                    compression,
+                    encrypt_to,
+                };
+                let id = if path.is_dir() {
+                    storm_rpc::containerize_dir(store_client, path, info, &options, log)?
+                } else {
+                    storm_rpc::containerize_file(store_client, path, mime, info, &options, log)?
+                };
+                format.result(&Containerized { container_id: id }, |res| {
+                    println!("{}", res.container_id)
+                });
+                if seed {
+                    storm_client.seed(id, progress)?;
                 }
-
-                let total_chunks = chunk_ids.len();
-                let header = ContainerHeader {
-                    version: 0,
-                    mime,
-                    info: info.unwrap_or_default(),
-                    size,
+            }
+            Command::Assemble {
+                container_id,
+                path,
+                node_key,
+            } => {
+                let log = |info| format.notice(info);
+                let node = node_key.map(storm_rpc::read_node_key).transpose()?;
+                let node = node.as_ref();
+                let header = if path.as_os_str() == "-" {
+                    let stdout = io::stdout().lock();
+                    storm_rpc::assemble(store_client, container_id, node, stdout, log)?
+                } else {
+                    match storm_rpc::assemble_file(store_client, container_id, node, &path, log) {
+                        Err(storm_rpc::AssembleError::DirectoryContainer(_)) => {
+                            storm_rpc::assemble_dir(store_client, container_id, node, &path, log)?
+                        }
+                        res => res?,
+                    }
                 };
-                let header_chunk = Chunk::try_from(header.strict_serialize()?)?;
-                let container = Container {
+                let assembled = Assembled {
                     header,
-                    chunks: chunk_ids,
+                    path: Some(path).filter(|path| path.as_os_str() != "-"),
                 };
-                let container_chunk = Chunk::try_from(container.strict_serialize()?)?;
-
-                let id = container.container_id();
-                store_client.store(storm_rpc::DB_TABLE_CONTAINER_HEADERS, id, &header_chunk)?;
-                store_client.store(storm_rpc::DB_TABLE_CONTAINERS, id, &container_chunk)?;
-                eprintln!("Containerized ({} chunks in total)", total_chunks);
-                println!("{}", id);
-            }
-            Command::Assemble { container_id, path } => {
-                // TODO: Make this procedure part of Storm Core (assembling data from a container)
-                let container_chunk = store_client
-                    .retrieve_chunk(storm_rpc::DB_TABLE_CONTAINERS, container_id)?
-                    .expect("No container with the provided id");
-                let container = Container::strict_deserialize(container_chunk)?;
-                println!("Size: {} bytes", container.header.size);
-                println!("MIME: {}", container.header.mime);
-                println!("Info: {}", container.header.info);
-                let mut file = fs::File::create(&path)?;
-                for chunk_id in container.chunks {
-                    let chunk = store_client
-                        .retrieve_chunk(storm_rpc::DB_TABLE_CHUNKS, chunk_id)?
-                        .expect(&format!("Chunk {} is absent", chunk_id));
-                    file.write_all(chunk.as_slice())?;
+                let text = |res: &Assembled| {
+                    eprintln!("Size: {} bytes", res.header.size);
+                    eprintln!("MIME: {}", res.header.mime);
+                    eprintln!("Info: {}", res.header.info);
+                    if let Some(path) = &res.path {
+                        eprintln!("Saved to {}", path.display());
+                    }
+                };
+                match assembled.path {
+                    Some(_) => format.result(&assembled, text),
+                    None => format.result_stderr(&assembled, text),
                 }
-                eprintln!("Saved to {}", path.display());
             }
             Command::Upload {
                 connect,
@@ -131,7 +231,7 @@ impl Opts {
             } => {
                 if let Some(addr) = connect {
                     let remote_node = PartialNodeAddr { id: peer, addr };
-                    lnp_client.connect(LnpAddr::bifrost(remote_node))?;
+                    storm_client.connect(LnpAddr::bifrost(remote_node).node_addr)?;
                 }
                 storm_client.upload(peer, container_id, progress)?;
             }
@@ -142,10 +242,158 @@ impl Opts {
             } => {
                 if let Some(addr) = connect {
                     let remote_node = PartialNodeAddr { id: peer, addr };
-                    lnp_client.connect(LnpAddr::bifrost(remote_node))?;
+                    storm_client.connect(LnpAddr::bifrost(remote_node).node_addr)?;
                 }
                 storm_client.download(peer, container_id, progress)?;
             }
+            Command::Ls {
+                mime,
+                min_size,
+                max_size,
+                since,
+                until,
+            } => {
+                let filter = ContainerFilter {
+                    mime,
+                    min_size,
+                    max_size,
+                    since,
+                    until,
+                };
+                let containers = storm_client.containers(filter)?;
+                format.result(&containers, |containers| {
+                    for summary in containers {
+                        let status = if summary.is_complete() { "complete" } else { "partial" };
+                        println!(
+                            "{}\t{}\t{} bytes\t{}",
+                            summary.container_id, status, summary.header.size, summary.header.mime
+                        );
+                    }
+                });
+            }
+            Command::Inspect { container_id } => {
+                let summary = storm_client.container_info(container_id)?;
+                format.result(&summary, |summary| {
+                    let header = &summary.header;
+                    println!("{}", summary.container_id);
+                    println!("  Version: {}", header.version);
+                    println!("  Size: {} bytes", header.size);
+                    println!("  MIME: {}", header.mime);
+                    println!("  Info: {}", header.info);
+                    println!("  Chunks: {} ({} present)", summary.chunks, summary.present_chunks);
+                    println!(
+                        "  Status: {}",
+                        if summary.is_complete() { "complete" } else { "partial" }
+                    );
+                    match summary.origin {
+                        Some(origin) => {
+                            println!("  Origin: {}", origin);
+                            println!("  Stored: {} (UNIX time)", origin.stored_at);
+                        }
+                        None => println!("  Origin: unknown"),
+                    }
+                });
+            }
+            Command::Quotas => {
+                let quotas = storm_client.quotas()?;
+                format.result(&quotas, |quotas| {
+                    if quotas.is_empty() {
+                        println!("No containers received from remote peers and no quotas set");
+                    }
+                    for quota in quotas {
+                        println!("{}", quota);
+                    }
+                });
+            }
+            Command::Delete { container_id } => {
+                storm_client.delete_container(container_id, progress)?;
+            }
+            Command::Pin { container_id } => {
+                storm_client.pin_container(container_id, true, progress)?;
+            }
+            Command::Unpin { container_id } => {
+                storm_client.pin_container(container_id, false, progress)?;
+            }
+            Command::Gc { dry_run } => {
+                let report = storm_client.collect_garbage(dry_run, progress)?;
+                format.result(&report, |report| {
+                    if report.dry_run {
+                        println!("Would reclaim {}", report);
+                    } else {
+                        println!("Reclaimed {}", report);
+                    }
+                    println!("Containers in use: {}", report.containers);
+                });
+            }
+            Command::Fsck { repair_from } => {
+                let report = storm_client.fsck(repair_from, progress)?;
+                format.result(&report, |report| {
+                    for container in &report.damaged {
+                        println!("{}:", container.container_id);
+                        for issue in &container.issues {
+                            println!("  {}", issue);
+                        }
+                    }
+                    println!("Checked {}", report);
+                    println!(
+                        "Orphaned chunks: {} ({} bytes)",
+                        report.orphaned_chunks, report.orphaned_bytes
+                    );
+                    if report.refetching > 0 {
+                        println!(
+                            "Re-fetching {} containers from the remote peer",
+                            report.refetching
+                        );
+                    }
+                });
+            }
+            Command::Seed { container_id } => {
+                storm_client.seed(container_id, progress)?;
+            }
+            Command::Catalog { status, peer, mime } => {
+                let filter = CatalogFilter {
+                    status,
+                    remote_id: peer,
+                    mime,
+                };
+                let catalog = storm_client.catalog(filter)?;
+                format.result(&catalog, |catalog| {
+                    for entry in catalog {
+                        let header = &entry.info.header;
+                        println!("{}", entry.info.id.container_id);
+                        println!("  Status: {}", entry.status);
+                        println!("  Size: {} bytes", header.size);
+                        println!("  MIME: {}", header.mime);
+                        println!("  Info: {}", header.info);
+                        println!("  First seen: {} (UNIX time)", entry.first_seen);
+                        for remote_id in &entry.remote_ids {
+                            println!("  Peer: {}", remote_id);
+                        }
+                    }
+                });
+            }
+            Command::Pending => {
+                let pending = storm_client.pending_containers()?;
+                format.result(&pending, |pending| {
+                    for pending in pending {
+                        let header = &pending.info.header;
+                        println!("{}", pending.info.id.container_id);
+                        println!("  Size: {} bytes", header.size);
+                        println!("  MIME: {}", header.mime);
+                        println!("  Info: {}", header.info);
+                        for remote_id in &pending.remote_ids {
+                            println!("  Peer: {}", remote_id);
+                        }
+                        println!("  Reason: {}", pending.violation);
+                    }
+                });
+            }
+            Command::Accept { container_id } => {
+                storm_client.accept_container(container_id, progress)?;
+            }
+            Command::Decline { container_id } => {
+                storm_client.decline_container(container_id, progress)?;
+            }
         }
         Ok(())
     }
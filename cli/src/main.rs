@@ -18,19 +18,26 @@ extern crate amplify;
 extern crate log;
 #[macro_use]
 extern crate clap;
+extern crate serde_crate as serde;
 
+mod chat;
 mod command;
 mod opts;
+mod output;
+
+use std::path::PathBuf;
+use std::time::Duration;
 
 use clap::Parser;
 use internet2::addr::ServiceAddr;
 use microservices::cli::LogStyle;
 use microservices::shell::LogLevel;
+use storm_rpc::{AuthToken, Timeouts, RPC_COOKIE_FILE};
 
-pub use crate::opts::{Command, Opts};
+pub use crate::opts::{Command, Format, Opts};
 
 fn main() {
-    println!("storm-cli: command-line tool for working with Storm node");
+    eprintln!("storm-cli: command-line tool for working with Storm node");
 
     let mut opts = Opts::parse();
     LogLevel::from_verbosity_flag_count(opts.verbose).apply();
@@ -48,14 +55,21 @@ fn main() {
     let mut storm_client =
         storm_rpc::Client::with(storm_endpoint.clone(), radio_endpoint.clone(), s!("storm-cli"))
             .expect("Error initializing Storm client");
-
-    let lnp_endpoint = &mut opts.lnp_endpoint;
-    if let ServiceAddr::Ipc(ref mut path) = lnp_endpoint {
-        *path = shellexpand::tilde(path).to_string();
+    if let Some(timeout) = opts.timeout {
+        storm_client.set_timeouts(Timeouts::idle(Duration::from_secs(timeout)));
+    }
+    let token = opts.token.clone().or_else(|| {
+        let data_dir = shellexpand::tilde(&opts.data_dir.display().to_string()).to_string();
+        let cookie_file = PathBuf::from(data_dir).join(RPC_COOKIE_FILE);
+        AuthToken::read(&cookie_file)
+            .map_err(|err| {
+                warn!("Unable to read RPC access cookie {}: {}", cookie_file.display(), err)
+            })
+            .ok()
+    });
+    if let Some(token) = token {
+        storm_client.set_auth_token(token);
     }
-    debug!("LNP RPC socket {}", lnp_endpoint);
-    let mut lnp_client =
-        lnp_rpc::Client::with(lnp_endpoint.clone()).expect("Error initializing LNP client");
 
     let store_endpoint = &mut opts.store_endpoint;
     if let ServiceAddr::Ipc(ref mut path) = store_endpoint {
@@ -66,6 +80,10 @@ fn main() {
         store_rpc::Client::with(&store_endpoint.clone()).expect("Error initializing store client");
 
     trace!("Executing command: {}", opts.command);
-    opts.exec(&mut storm_client, &mut store_client, &mut lnp_client)
-        .unwrap_or_else(|err| eprintln!("{} {}\n", "Error:".err(), err.err_details()));
+    let format = opts.format;
+    if let Err(err) = opts.exec(&mut storm_client, &mut store_client) {
+        eprintln!("{} {}\n", "Error:".err(), err.err_details());
+        format.error(err.to_string(), err.exit_code());
+        std::process::exit(err.exit_code());
+    }
 }
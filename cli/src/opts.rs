@@ -10,16 +10,46 @@
 
 use std::path::PathBuf;
 
-use internet2::addr::{NodeId, PartialSocketAddr, ServiceAddr};
-use lnp_rpc::LNP_NODE_RPC_ENDPOINT;
+use clap::ValueHint;
+use internet2::addr::{NodeId, PartialNodeAddr, PartialSocketAddr, ServiceAddr};
 use stens::AsciiString;
 use store_rpc::STORED_RPC_ENDPOINT;
 use storm::ContainerId;
-use storm_rpc::{CHATD_RPC_ENDPOINT, STORM_NODE_RPC_ENDPOINT};
+use storm_ext::STORM_NODE_DATA_DIR;
+use storm_rpc::{
+    AuthToken, CatalogStatus, Chunking, Compression, CHATD_RPC_ENDPOINT, STORM_NODE_RPC_ENDPOINT,
+};
+
+/// Exit codes of the tool, derived from the failure codes reported by the node
+const EXIT_STATUS: &str = "EXIT STATUS:
+    0     success
+    1     general failure
+    2     invalid command-line arguments
+    3     node is not reachable over RPC
+    4     request timed out
+    5     unknown container
+    6     container is pinned
+    7     storage quota exceeded
+    8     rejected by downpour policy
+    9     peer is not connected
+    10    peer is unreachable
+    11    store failure
+    12    container transfer failure
+    13    LNP node failure
+    14    unexpected request or reply, node version mismatch
+    15    missing or invalid RPC access token
+    16    request is not permitted for the client role
+    17    container transfers are in progress";
 
 /// Command-line tool for working with store daemon
 #[derive(Parser, Clone, PartialEq, Eq, Debug)]
-#[clap(name = "storm-cli", bin_name = "storm-cli", author, version)]
+#[clap(
+    name = "storm-cli",
+    bin_name = "storm-cli",
+    author,
+    version,
+    after_help = EXIT_STATUS
+)]
 pub struct Opts {
     /// ZMQ socket for connecting Storm node RPC interface.
     ///
@@ -34,6 +64,27 @@ pub struct Opts {
     )]
     pub storm_endpoint: ServiceAddr,
 
+    /// Node data directory path.
+    ///
+    /// Unless `--token` is given, RPC requests are authenticated with the access cookie from
+    /// this directory, which grants full access to the node.
+    #[clap(
+        short,
+        long,
+        global = true,
+        default_value = STORM_NODE_DATA_DIR,
+        env = "STORM_NODE_DATA_DIR",
+        value_hint = ValueHint::DirPath
+    )]
+    pub data_dir: PathBuf,
+
+    /// Access token for the node RPC interface.
+    ///
+    /// Tokens with limited permissions are listed by the node operator in `rpc.tokens` file in
+    /// the node data directory.
+    #[clap(long, global = true, env = "STORM_NODE_RPC_TOKEN", hide_env_values = true)]
+    pub token: Option<AuthToken>,
+
     /// ZMQ socket for connecting storage daemon.
     ///
     /// Socket can be either TCP address in form of `<ipv4 | ipv6>:<port>` – or a path
@@ -59,18 +110,20 @@ pub struct Opts {
     )]
     pub radio_endpoint: ServiceAddr,
 
-    /// ZMQ socket for connecting LNP node RPC interface.
+    /// Maximal time (in seconds) to wait for a reply or a progress report from the node.
     ///
-    /// Socket can be either TCP address in form of `<ipv4 | ipv6>:<port>` – or a path
-    /// to an IPC file.
-    #[clap(
-        short = 'L',
-        long = "lnp",
-        global = true,
-        default_value = LNP_NODE_RPC_ENDPOINT,
-        env = "LNP_NODE_RPC_ENDPOINT"
-    )]
-    pub lnp_endpoint: ServiceAddr,
+    /// Long operations do not time out as long as they keep reporting progress. If not
+    /// provided, the tool waits for replies forever.
+    #[clap(long, global = true, env = "STORM_CLI_TIMEOUT", value_name = "SECS")]
+    pub timeout: Option<u64>,
+
+    /// Format of the command output: human-readable `text`, or machine-readable `json` or
+    /// `yaml`.
+    ///
+    /// In machine-readable formats each result, progress event, chat message and error is
+    /// printed as a separate record: a single-line JSON object or a YAML document.
+    #[clap(long, global = true, value_enum, env = "STORM_CLI_FORMAT", default_value = "text")]
+    pub format: Format,
 
     /// Set verbosity level.
     ///
@@ -83,9 +136,62 @@ pub struct Opts {
     pub command: Command,
 }
 
+/// Format of the tool output
+#[derive(ValueEnum, Copy, Clone, PartialEq, Eq, Hash, Debug, Display)]
+pub enum Format {
+    /// Human-readable text
+    #[display("text")]
+    Text,
+
+    /// JSON object per line
+    #[display("json")]
+    Json,
+
+    /// YAML documents
+    #[display("yaml")]
+    Yaml,
+}
+
 /// Command-line commands:
 #[derive(Subcommand, Clone, PartialEq, Eq, Debug, Display)]
 pub enum Command {
+    /// List remote peers connected to the node.
+    #[display("peers")]
+    Peers,
+
+    /// Connect to a remote peer over Bifrost protocol.
+    #[display("connect")]
+    Connect {
+        /// Remote node address in form of `<node_id>@<host>[:<port>]`.
+        peer: PartialNodeAddr,
+    },
+
+    /// Disconnect from a remote peer.
+    #[display("disconnect")]
+    Disconnect {
+        /// Remote node id (public key).
+        peer: NodeId,
+    },
+
+    /// Interactive chat session with a remote peer.
+    ///
+    /// Shows the latest messages from the chat history kept by the node, followed by the new
+    /// messages as they arrive; typed-in lines are sent to the peer. Type `/quit` or press
+    /// Ctrl-D to exit.
+    #[display("chat")]
+    Chat {
+        /// Remote node address to force connection (re)establishment
+        #[clap(long)]
+        connect: Option<PartialSocketAddr>,
+
+        /// Number of the latest messages from the chat history to show.
+        #[clap(long, default_value = "50", value_name = "COUNT")]
+        history: usize,
+
+        /// Remote node id (public key).
+        peer: NodeId,
+    },
+
     /// Listen for the incoming chat messages from a remote peer.
     #[display("chat-listen")]
     ChatListen {
@@ -108,29 +214,59 @@ pub enum Command {
         peer: NodeId,
     },
 
-    /// Convert on-disk file into a container in the Store database.
+    /// Convert on-disk file or directory into a container in the Store database.
     #[display("containerize")]
     Containerize {
-        /// MIME file type
+        /// MIME file type. Ignored for directories.
         #[clap(short, long, default_value = "application/octet-stream")]
         mime: AsciiString,
 
-        /// Local file for containerization.
+        /// Local file or directory for containerization.
         path: PathBuf,
 
         /// Information about the container
         #[clap()]
         info: Option<String>,
+
+        /// Chunking strategy: `fixed`, or content-defined `cdc` with default chunk sizes, or
+        /// `cdc-MIN-AVG-MAX` with sizes given in bytes.
+        ///
+        /// Content-defined chunking allows re-uploads of slightly modified files to transfer only
+        /// the changed chunks.
+        #[clap(short, long, default_value = "fixed")]
+        chunking: Chunking,
+
+        /// Compression of the chunk data: `none` or `deflate`.
+        ///
+        /// Compressed chunks take less space in the Store database and less bandwidth when
+        /// transferred; assembly decompresses them transparently.
+        #[clap(short = 'z', long, default_value = "none")]
+        compression: Compression,
+
+        /// Encrypt the container to the given node; may be repeated to encrypt to multiple nodes.
+        ///
+        /// Only the listed nodes are able to assemble the encrypted container; add the local node
+        /// id to keep the ability to assemble it locally.
+        #[clap(long = "encrypt-to", value_name = "NODE_ID")]
+        encrypt_to: Vec<NodeId>,
+
+        /// Announce the container to the downpour seed peers once containerized.
+        #[clap(long)]
+        seed: bool,
     },
 
-    /// Assemble a file from a Store database-present container and save as a file.
+    /// Assemble a file or a directory from a Store database-present container and save it.
     #[display("assemble")]
     Assemble {
         /// ID of the container to assemble into a file.
         container_id: ContainerId,
 
-        /// Path and filename to save the file.
+        /// Path and filename to save the file; use `-` to write the data to the standard output.
         path: PathBuf,
+
+        /// File with the node private key, required for assembling encrypted containers.
+        #[clap(short = 'k', long, value_name = "FILE")]
+        node_key: Option<PathBuf>,
     },
 
     /// Upload given container to the remote peer
@@ -160,4 +296,121 @@ pub enum Command {
         /// Container to send
         container_id: ContainerId,
     },
+
+    /// List containers kept in the Store database.
+    #[display("ls")]
+    Ls {
+        /// Show only containers of the given MIME type.
+        #[clap(short, long)]
+        mime: Option<String>,
+
+        /// Show only containers of at least the given size, in bytes.
+        #[clap(long)]
+        min_size: Option<u64>,
+
+        /// Show only containers of at most the given size, in bytes.
+        #[clap(long)]
+        max_size: Option<u64>,
+
+        /// Show only containers stored at or after the given UNIX time.
+        #[clap(long)]
+        since: Option<u64>,
+
+        /// Show only containers stored before the given UNIX time.
+        #[clap(long)]
+        until: Option<u64>,
+    },
+
+    /// Show information about a container kept in the Store database.
+    #[display("inspect")]
+    Inspect {
+        /// Container to inspect
+        container_id: ContainerId,
+    },
+
+    /// Show storage used by the containers received from remote peers, per app and per peer,
+    /// together with the configured quotas.
+    #[display("quotas")]
+    Quotas,
+
+    /// Delete a container from the Store database. Its chunks are reclaimed by the next garbage
+    /// collection.
+    #[display("delete")]
+    Delete {
+        /// Container to delete
+        container_id: ContainerId,
+    },
+
+    /// Protect a container from deletion.
+    #[display("pin")]
+    Pin {
+        /// Container to pin
+        container_id: ContainerId,
+    },
+
+    /// Remove protection from deletion from a container.
+    #[display("unpin")]
+    Unpin {
+        /// Container to unpin
+        container_id: ContainerId,
+    },
+
+    /// Reclaim space taken by chunks which are not used by any of containers.
+    #[display("gc")]
+    Gc {
+        /// Only show what would be reclaimed, without reclaiming anything.
+        #[clap(long)]
+        dry_run: bool,
+    },
+
+    /// Check integrity of the containers and chunks kept in the Store database.
+    #[display("fsck")]
+    Fsck {
+        /// Re-fetch damaged containers from the given peer.
+        #[clap(short, long)]
+        repair_from: Option<NodeId>,
+    },
+
+    /// Announce a container from the Store database to the downpour seed peers.
+    #[display("seed")]
+    Seed {
+        /// Container to announce
+        container_id: ContainerId,
+    },
+
+    /// Browse catalog of containers announced by remote peers.
+    #[display("catalog")]
+    Catalog {
+        /// Show only containers with the given status (pending, downloading, complete or
+        /// declined).
+        #[clap(short, long)]
+        status: Option<CatalogStatus>,
+
+        /// Show only containers announced by the given peer.
+        #[clap(short, long)]
+        peer: Option<NodeId>,
+
+        /// Show only containers of the given MIME type.
+        #[clap(short, long)]
+        mime: Option<String>,
+    },
+
+    /// List container announcements which were not accepted automatically by the downpour
+    /// policy and are waiting for a decision.
+    #[display("pending")]
+    Pending,
+
+    /// Accept pending container announcement and download the container.
+    #[display("accept")]
+    Accept {
+        /// Container to download
+        container_id: ContainerId,
+    },
+
+    /// Decline pending container announcement.
+    #[display("decline")]
+    Decline {
+        /// Container to decline
+        container_id: ContainerId,
+    },
 }
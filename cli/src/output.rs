@@ -0,0 +1,209 @@
+// Storm node providing distributed storage & messaging for lightning network.
+//
+// Written in 2022 by
+//     Dr. Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2022 by LNP/BP Standards Association, Switzerland.
+//
+// You should have received a copy of the MIT License along with this software.
+// If not, see <https://opensource.org/licenses/MIT>.
+
+//! Printing of the command results in the format selected by the user.
+//!
+//! In machine-readable formats the output is a stream of records, each being either a command
+//! result, a progress event, a chat message or an error. JSON records are printed one per line;
+//! YAML records are printed as separate documents.
+
+use std::fmt::{self, Display, Formatter};
+use std::io::{self, Write};
+use std::path::PathBuf;
+
+use chrono::{Local, TimeZone};
+use colored::Colorize;
+use serde::Serialize;
+use serde_yaml::with::singleton_map_recursive;
+use storm::{ContainerHeader, ContainerId};
+use storm_rpc::{ChatDirection, ChatMsg, ChatProtection, ChatRecord, ProgressEvent};
+
+use crate::Format;
+
+/// Width of the progress bar, in characters
+const PROGRESS_BAR_WIDTH: usize = 40;
+
+/// Record of the machine-readable output
+#[derive(Serialize)]
+#[serde(crate = "serde_crate", rename_all = "snake_case")]
+enum Record<'a, T: Serialize> {
+    Result(&'a T),
+    Progress(&'a ProgressEvent),
+    Message(&'a ChatMsg),
+    Error { message: String, exit_code: i32 },
+}
+
+/// Result of the containerize command
+#[derive(Serialize)]
+#[serde(crate = "serde_crate")]
+pub struct Containerized {
+    pub container_id: ContainerId,
+}
+
+/// Result of the assemble command
+#[derive(Serialize)]
+#[serde(crate = "serde_crate")]
+pub struct Assembled {
+    pub header: ContainerHeader,
+    /// File or directory the container was saved to; `None` if it was written to the standard
+    /// output.
+    pub path: Option<PathBuf>,
+}
+
+/// Message of the interactive chat session
+#[derive(Serialize)]
+#[serde(crate = "serde_crate")]
+pub struct ChatLine {
+    /// Time when the message was sent or received, as a UNIX timestamp.
+    pub timestamp: u64,
+    pub direction: ChatDirection,
+    pub text: String,
+    /// End-to-end protection of the message; unknown for the messages sent during the session.
+    pub protection: Option<ChatProtection>,
+    /// Delivery status of the messages sent during the session.
+    pub status: Option<DeliveryStatus>,
+}
+
+/// Delivery status of a message sent during the interactive chat session
+#[derive(Serialize)]
+#[serde(crate = "serde_crate", rename_all = "snake_case")]
+pub enum DeliveryStatus {
+    /// Message is accepted by the node for delivery to the peer. Bifrost protocol does not
+    /// acknowledge message receipt, so this is the final status of delivered messages.
+    Sent,
+
+    /// Node has failed to accept the message.
+    Failed(String),
+}
+
+impl From<&ChatRecord> for ChatLine {
+    fn from(record: &ChatRecord) -> Self {
+        ChatLine {
+            timestamp: record.timestamp,
+            direction: record.direction,
+            text: record.msg.text.clone(),
+            protection: Some(record.msg.protection),
+            status: None,
+        }
+    }
+}
+
+impl Display for ChatLine {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let time = match Local.timestamp_opt(self.timestamp as i64, 0).single() {
+            Some(time) if time.date_naive() == Local::now().date_naive() => {
+                time.format("%H:%M:%S").to_string()
+            }
+            Some(time) => time.format("%Y-%m-%d %H:%M:%S").to_string(),
+            None => s!("--:--:--"),
+        };
+        write!(f, "{} {}", format!("[{}]", time).dimmed(), self.direction)?;
+        if let Some(protection) = self.protection {
+            write!(f, " {}", protection.to_string().dimmed())?;
+        }
+        write!(f, " {}", self.text)?;
+        match &self.status {
+            Some(DeliveryStatus::Sent) => write!(f, " {}", "(sent)".dimmed()),
+            Some(DeliveryStatus::Failed(err)) => {
+                write!(f, " {}", format!("(failed: {})", err).red())
+            }
+            None => Ok(()),
+        }
+    }
+}
+
+impl Format {
+    /// Prints command result. In text format, the result is printed by the `text` function.
+    pub fn result<T: Serialize>(self, value: &T, text: impl FnOnce(&T)) {
+        match self {
+            Format::Text => text(value),
+            _ => self.emit(&mut io::stdout(), &Record::Result(value)),
+        }
+    }
+
+    /// Prints command result to the standard error output, which is used when the standard output
+    /// is occupied by the data.
+    pub fn result_stderr<T: Serialize>(self, value: &T, text: impl FnOnce(&T)) {
+        match self {
+            Format::Text => text(value),
+            _ => self.emit(&mut io::stderr(), &Record::Result(value)),
+        }
+    }
+
+    /// Prints progress event; in text format chunk transfers are shown as a progress bar updated
+    /// in place.
+    pub fn progress(self, event: ProgressEvent) {
+        if self != Format::Text {
+            return self.emit(&mut io::stdout(), &Record::<()>::Progress(&event));
+        }
+        match (&event, event.fraction()) {
+            (ProgressEvent::ChunkReceived { done, total, .. }, Some(fraction)) => {
+                let filled = (fraction * PROGRESS_BAR_WIDTH as f64) as usize;
+                print!(
+                    "\r[{}{}] {}",
+                    "#".repeat(filled),
+                    " ".repeat(PROGRESS_BAR_WIDTH - filled),
+                    event
+                );
+                if done == total {
+                    println!();
+                }
+                let _ = io::stdout().flush();
+            }
+            _ => println!("{}", event),
+        }
+    }
+
+    /// Prints information about the progress of a local operation to the standard error output.
+    pub fn notice(self, info: impl ToString) {
+        match self {
+            Format::Text => eprintln!("{}", info.to_string()),
+            _ => self.emit(
+                &mut io::stderr(),
+                &Record::<()>::Progress(&ProgressEvent::Notice(info.to_string())),
+            ),
+        }
+    }
+
+    /// Prints chat message received from a remote peer.
+    pub fn message(self, msg: &ChatMsg) {
+        match self {
+            Format::Text => println!("> {}", msg),
+            _ => self.emit(&mut io::stdout(), &Record::<()>::Message(msg)),
+        }
+    }
+
+    /// Prints error record. Does nothing in text format, where errors are reported to the standard
+    /// error output only.
+    pub fn error(self, message: String, exit_code: i32) {
+        if self != Format::Text {
+            let record: Record<()> = Record::Error { message, exit_code };
+            self.emit(&mut io::stdout(), &record);
+        }
+    }
+
+    fn emit<T: Serialize>(self, out: &mut impl Write, record: &Record<T>) {
+        let res = match self {
+            Format::Text => unreachable!("text output is not made of records"),
+            Format::Json => serde_json::to_writer(&mut *out, record)
+                .map_err(|err| err.to_string())
+                .and_then(|_| writeln!(out).map_err(|err| err.to_string())),
+            // Enums are written as single-key maps, like in JSON, instead of YAML tags
+            Format::Yaml => writeln!(out, "---").map_err(|err| err.to_string()).and_then(|_| {
+                let mut serializer = serde_yaml::Serializer::new(&mut *out);
+                singleton_map_recursive::serialize(record, &mut serializer)
+                    .map_err(|err| err.to_string())
+            }),
+        };
+        if let Err(err) = res.and_then(|_| out.flush().map_err(|err| err.to_string())) {
+            eprintln!("Unable to print {} record: {}", self, err);
+        }
+    }
+}
@@ -88,6 +88,11 @@ pub enum ExtMsg {
     #[display("container_retrieved({0})")]
     ContainerRetrieved(ContainerId),
 
+    /// Notification about failed container download
+    #[api(type = 0x0015)]
+    #[display("container_retrieval_failed({0})")]
+    ContainerRetrievalFailed(ContainerId),
+
     /// Command from an extension to the main daemon to retrieve container from the remote peer
     #[api(type = 0x0012)]
     #[display("retrieve_container({0})")]
@@ -158,8 +163,8 @@ impl ExtMsg {
             ExtMsg::RegisterApp(_) => {
                 unreachable!("ExtMsg::remote_id must not be called on ExtMsg::RegisterApp")
             }
-            ExtMsg::ContainerRetrieved(_) => {
-                unreachable!("ExtMsg::remote_id must not be called on ExtMsg::ContainerRetrieved")
+            ExtMsg::ContainerRetrieved(_) | ExtMsg::ContainerRetrievalFailed(_) => {
+                unreachable!("ExtMsg::remote_id must not be called on container retrieval reports")
             }
             ExtMsg::ListTopics(AddressedMsg { remote_id, .. })
             | ExtMsg::Topics(AddressedMsg { remote_id, .. })
@@ -201,7 +206,8 @@ impl ExtMsg {
             }
             ExtMsg::SendContainer(_)
             | ExtMsg::RetrieveContainer(_)
-            | ExtMsg::ContainerRetrieved(_) => {
+            | ExtMsg::ContainerRetrieved(_)
+            | ExtMsg::ContainerRetrievalFailed(_) => {
                 unreachable!("the task is handled by a dedicated daemon")
             }
         }
@@ -223,7 +229,8 @@ impl ExtMsg {
             ExtMsg::ContainerAnnouncement(AddressedMsg { data, .. }) => data.strict_serialize(),
             ExtMsg::SendContainer(AddressedMsg { data, .. }) => data.strict_serialize(),
             ExtMsg::RetrieveContainer(AddressedMsg { data, .. }) => data.strict_serialize(),
-            ExtMsg::ContainerRetrieved(container_id) => container_id.strict_serialize(),
+            ExtMsg::ContainerRetrieved(container_id)
+            | ExtMsg::ContainerRetrievalFailed(container_id) => container_id.strict_serialize(),
         }
         .expect("extension-generated message can't be serialized as a bifrost message payload")
     }
@@ -0,0 +1,224 @@
+// Storm node providing distributed storage & messaging for lightning network.
+//
+// Written in 2022 by
+//     Dr. Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2022 by LNP/BP Standards Association, Switzerland.
+//
+// You should have received a copy of the MIT License along with this software.
+// If not, see <https://opensource.org/licenses/MIT>.
+
+//! JSON-RPC 2.0 API translating method calls into Storm node RPC requests.
+//!
+//! | Method           | Parameters                            | Result                              |
+//! |------------------|---------------------------------------|-------------------------------------|
+//! | `status`         | none                                  | gateway version and connected peers |
+//! | `peers`          | none                                  | list of connected peers             |
+//! | `connect`        | `peer`: `<node_id>@<host>[:<port>]`   | none                                |
+//! | `disconnect`     | `peer`: node id                       | none                                |
+//! | `chat_send`      | `peer`, `text`                        | none                                |
+//! | `containers`     | container filter fields, all optional | list of containers                  |
+//! | `container_info` | `container_id`                        | container summary                   |
+//! | `upload`         | `peer`, `container_id`                | none; reports progress              |
+//! | `download`       | `peer`, `container_id`                | none; reports progress              |
+
+use internet2::addr::{NodeId, PartialNodeAddr};
+use lnp::addr::LnpAddr;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::Value;
+use storm::ContainerId;
+use storm_rpc::{Client, ContainerFilter, PeerInfo, ProgressEvent, FAILURE_CODES_VERSION};
+
+/// Error codes defined by JSON-RPC 2.0 specification
+pub const PARSE_ERROR: i32 = -32700;
+pub const INVALID_REQUEST: i32 = -32600;
+pub const METHOD_NOT_FOUND: i32 = -32601;
+pub const INVALID_PARAMS: i32 = -32602;
+/// Error code for failures reported by the node; the node failure code is provided in the error
+/// data.
+pub const NODE_ERROR: i32 = -32000;
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(crate = "serde_crate")]
+pub struct RpcRequest {
+    pub jsonrpc: String,
+    #[serde(default)]
+    pub id: Value,
+    pub method: String,
+    #[serde(default)]
+    pub params: Value,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(crate = "serde_crate")]
+pub struct RpcResponse {
+    pub jsonrpc: &'static str,
+    pub id: Value,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub result: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<RpcError>,
+}
+
+impl RpcResponse {
+    pub fn with(id: Value, result: Result<Value, RpcError>) -> Self {
+        let (result, error) = match result {
+            Ok(result) => (Some(result), None),
+            Err(error) => (None, Some(error)),
+        };
+        RpcResponse {
+            jsonrpc: "2.0",
+            id,
+            result,
+            error,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(crate = "serde_crate")]
+pub struct RpcError {
+    pub code: i32,
+    pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub data: Option<ErrorData>,
+}
+
+#[derive(Copy, Clone, Debug, Serialize)]
+#[serde(crate = "serde_crate")]
+pub struct ErrorData {
+    /// Failure code reported by the node, as listed in [`storm_rpc::FailureCode`]
+    pub failure_code: u16,
+}
+
+impl RpcError {
+    pub fn with(code: i32, message: impl ToString) -> Self {
+        RpcError {
+            code,
+            message: message.to_string(),
+            data: None,
+        }
+    }
+}
+
+impl From<storm_rpc::Error> for RpcError {
+    fn from(err: storm_rpc::Error) -> Self {
+        RpcError {
+            code: NODE_ERROR,
+            message: err.to_string(),
+            data: err.failure_code().map(|code| ErrorData {
+                failure_code: code.into(),
+            }),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(crate = "serde_crate")]
+struct Status {
+    version: &'static str,
+    failure_codes_version: u16,
+    peers: Vec<PeerInfo>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(crate = "serde_crate")]
+struct PeerParams {
+    peer: NodeId,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(crate = "serde_crate")]
+struct ConnectParams {
+    peer: String,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(crate = "serde_crate")]
+struct ChatParams {
+    peer: NodeId,
+    text: String,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(crate = "serde_crate")]
+struct ContainerParams {
+    container_id: ContainerId,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(crate = "serde_crate")]
+struct TransferParams {
+    peer: NodeId,
+    container_id: ContainerId,
+}
+
+/// Parses method parameters; absent parameters are treated as an empty object.
+fn params<T: DeserializeOwned>(params: Value) -> Result<T, RpcError> {
+    let params = if params.is_null() { Value::Object(empty!()) } else { params };
+    serde_json::from_value(params).map_err(|err| RpcError::with(INVALID_PARAMS, err))
+}
+
+fn to_value(value: impl Serialize) -> Result<Value, RpcError> {
+    serde_json::to_value(value)
+        .map_err(|err| RpcError::with(NODE_ERROR, format!("unable to encode result: {}", err)))
+}
+
+/// Checks whether the method reports progress while running.
+pub fn is_progressive(method: &str) -> bool { matches!(method, "upload" | "download") }
+
+/// Executes JSON-RPC method using the node client.
+pub fn call(
+    client: &mut Client,
+    method: &str,
+    params_value: Value,
+    progress: &dyn Fn(ProgressEvent),
+) -> Result<Value, RpcError> {
+    debug!("Executing JSON-RPC method {}", method);
+    match method {
+        "status" => to_value(Status {
+            version: env!("CARGO_PKG_VERSION"),
+            failure_codes_version: FAILURE_CODES_VERSION,
+            peers: client.peers()?,
+        }),
+        "peers" => to_value(client.peers()?),
+        "connect" => {
+            let ConnectParams { peer } = params(params_value)?;
+            let peer = peer
+                .parse::<PartialNodeAddr>()
+                .map_err(|err| RpcError::with(INVALID_PARAMS, err))?;
+            client.connect(LnpAddr::bifrost(peer).node_addr)?;
+            Ok(Value::Null)
+        }
+        "disconnect" => {
+            let PeerParams { peer } = params(params_value)?;
+            client.disconnect(peer)?;
+            Ok(Value::Null)
+        }
+        "chat_send" => {
+            let ChatParams { peer, text } = params(params_value)?;
+            client.chat_tell(peer, text)?;
+            Ok(Value::Null)
+        }
+        "containers" => {
+            let filter: ContainerFilter = params(params_value)?;
+            to_value(client.containers(filter)?)
+        }
+        "container_info" => {
+            let ContainerParams { container_id } = params(params_value)?;
+            to_value(client.container_info(container_id)?)
+        }
+        "upload" => {
+            let TransferParams { peer, container_id } = params(params_value)?;
+            client.upload(peer, container_id, progress)?;
+            Ok(Value::Null)
+        }
+        "download" => {
+            let TransferParams { peer, container_id } = params(params_value)?;
+            client.download(peer, container_id, progress)?;
+            Ok(Value::Null)
+        }
+        _ => Err(RpcError::with(METHOD_NOT_FOUND, format!("unknown method `{}`", method))),
+    }
+}
@@ -0,0 +1,247 @@
+// Storm node providing distributed storage & messaging for lightning network.
+//
+// Written in 2022 by
+//     Dr. Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2022 by LNP/BP Standards Association, Switzerland.
+//
+// You should have received a copy of the MIT License along with this software.
+// If not, see <https://opensource.org/licenses/MIT>.
+
+//! Minimal HTTP/1.1 support sufficient for the gateway API.
+//!
+//! Each connection carries a single request: responses are always sent with `Connection: close`.
+//! Request bodies must have `Content-Length` header; chunked transfer encoding is not supported.
+
+use std::collections::BTreeMap;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::TcpStream;
+
+use serde::Serialize;
+
+/// Maximal size of the request line and headers
+const MAX_HEAD_SIZE: usize = 16 * 1024;
+
+/// Maximal size of the request body
+const MAX_BODY_SIZE: usize = 1024 * 1024;
+
+#[derive(Debug, Display, Error, From)]
+#[display(doc_comments)]
+pub enum HttpError {
+    /// I/O error: {0}
+    #[from]
+    Io(io::Error),
+
+    /// malformed HTTP request
+    Malformed,
+
+    /// request headers exceed size limit
+    HeadTooLarge,
+
+    /// request body exceeds size limit
+    BodyTooLarge,
+
+    /// request body must have Content-Length header
+    LengthRequired,
+}
+
+impl HttpError {
+    pub fn status(&self) -> Status {
+        match self {
+            HttpError::Io(_) | HttpError::Malformed => Status::BadRequest,
+            HttpError::HeadTooLarge => Status::HeadTooLarge,
+            HttpError::BodyTooLarge => Status::PayloadTooLarge,
+            HttpError::LengthRequired => Status::LengthRequired,
+        }
+    }
+}
+
+/// HTTP status codes used by the gateway
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Display)]
+pub enum Status {
+    #[display("200 OK")]
+    Ok,
+
+    #[display("400 Bad Request")]
+    BadRequest,
+
+    #[display("401 Unauthorized")]
+    Unauthorized,
+
+    #[display("404 Not Found")]
+    NotFound,
+
+    #[display("405 Method Not Allowed")]
+    MethodNotAllowed,
+
+    #[display("411 Length Required")]
+    LengthRequired,
+
+    #[display("413 Payload Too Large")]
+    PayloadTooLarge,
+
+    #[display("431 Request Header Fields Too Large")]
+    HeadTooLarge,
+
+    #[display("502 Bad Gateway")]
+    BadGateway,
+}
+
+/// HTTP request
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct Request {
+    pub method: String,
+    pub path: String,
+    pub query: BTreeMap<String, String>,
+    /// Headers with lowercase names
+    pub headers: BTreeMap<String, String>,
+    pub body: Vec<u8>,
+}
+
+impl Request {
+    /// Reads request from the connection.
+    pub fn read(stream: &TcpStream) -> Result<Request, HttpError> {
+        let mut reader = BufReader::new(stream).take(MAX_HEAD_SIZE as u64);
+
+        let mut line = String::new();
+        reader.read_line(&mut line)?;
+        let mut parts = line.split_whitespace();
+        let (method, target) = match (parts.next(), parts.next(), parts.next()) {
+            (Some(method), Some(target), Some(version)) if version.starts_with("HTTP/1.") => {
+                (method.to_owned(), target.to_owned())
+            }
+            _ => return Err(HttpError::Malformed),
+        };
+
+        let mut headers = BTreeMap::new();
+        loop {
+            line.clear();
+            if reader.read_line(&mut line)? == 0 {
+                return Err(if reader.limit() == 0 {
+                    HttpError::HeadTooLarge
+                } else {
+                    HttpError::Malformed
+                });
+            }
+            let header = line.trim_end_matches(&['\r', '\n'][..]);
+            if header.is_empty() {
+                break;
+            }
+            let (name, value) = header.split_once(':').ok_or(HttpError::Malformed)?;
+            headers.insert(name.trim().to_lowercase(), value.trim().to_owned());
+        }
+
+        let mut body = vec![];
+        if headers.contains_key("transfer-encoding") {
+            return Err(HttpError::LengthRequired);
+        }
+        if let Some(len) = headers.get("content-length") {
+            let len = len.parse::<usize>().map_err(|_| HttpError::Malformed)?;
+            if len > MAX_BODY_SIZE {
+                return Err(HttpError::BodyTooLarge);
+            }
+            body.resize(len, 0);
+            // Body may be already partially buffered by the reader, so it must be used further
+            reader.into_inner().read_exact(&mut body)?;
+        }
+
+        let (path, query) = match target.split_once('?') {
+            Some((path, query)) => (path.to_owned(), parse_query(query)),
+            None => (target, empty!()),
+        };
+
+        Ok(Request {
+            method,
+            path,
+            query,
+            headers,
+            body,
+        })
+    }
+
+    pub fn header(&self, name: &str) -> Option<&str> { self.headers.get(name).map(String::as_str) }
+
+    /// Returns path segments, skipping empty ones.
+    pub fn segments(&self) -> Vec<&str> {
+        self.path.split('/').filter(|segment| !segment.is_empty()).collect()
+    }
+
+    /// Checks whether the client accepts server-sent event stream as a response.
+    pub fn accepts_events(&self) -> bool {
+        self.header("accept").map(|accept| accept.contains("text/event-stream")).unwrap_or_default()
+    }
+}
+
+/// Parses query string. Values are not percent-decoded, since none of the API parameters may
+/// contain characters requiring encoding.
+fn parse_query(query: &str) -> BTreeMap<String, String> {
+    query
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| match pair.split_once('=') {
+            Some((key, value)) => (key.to_owned(), value.to_owned()),
+            None => (pair.to_owned(), s!("")),
+        })
+        .collect()
+}
+
+/// Writes complete response with a JSON body.
+pub fn respond_json(
+    mut stream: &TcpStream,
+    status: Status,
+    body: &impl Serialize,
+) -> Result<(), io::Error> {
+    let body = serde_json::to_vec(body)?;
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: \
+         close\r\n\r\n",
+        status,
+        body.len()
+    )?;
+    stream.write_all(&body)?;
+    stream.flush()
+}
+
+/// Writes response with an error message.
+pub fn respond_error(stream: &TcpStream, status: Status, message: &str) -> Result<(), io::Error> {
+    #[derive(Serialize)]
+    #[serde(crate = "serde_crate")]
+    struct ErrorBody<'a> {
+        error: &'a str,
+    }
+    respond_json(stream, status, &ErrorBody { error: message })
+}
+
+/// Response streaming server-sent events
+pub struct EventStream<'stream> {
+    stream: &'stream TcpStream,
+}
+
+impl<'stream> EventStream<'stream> {
+    /// Writes response head starting the event stream.
+    pub fn start(mut stream: &'stream TcpStream) -> Result<Self, io::Error> {
+        write!(
+            stream,
+            "HTTP/1.1 {}\r\nContent-Type: text/event-stream\r\nCache-Control: \
+             no-cache\r\nConnection: close\r\n\r\n",
+            Status::Ok
+        )?;
+        stream.flush()?;
+        Ok(EventStream { stream })
+    }
+
+    /// Sends event with JSON data.
+    pub fn send(&mut self, event: &str, data: &impl Serialize) -> Result<(), io::Error> {
+        let data = serde_json::to_string(data)?;
+        write!(self.stream, "event: {}\ndata: {}\n\n", event, data)?;
+        self.stream.flush()
+    }
+
+    /// Sends comment, which is ignored by the clients. Used to detect closed connections while
+    /// there are no events.
+    pub fn keep_alive(&mut self) -> Result<(), io::Error> {
+        self.stream.write_all(b": keep-alive\n\n")?;
+        self.stream.flush()
+    }
+}
@@ -0,0 +1,84 @@
+// Storm node providing distributed storage & messaging for lightning network.
+//
+// Written in 2022 by
+//     Dr. Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2022 by LNP/BP Standards Association, Switzerland.
+//
+// You should have received a copy of the MIT License along with this software.
+// If not, see <https://opensource.org/licenses/MIT>.
+
+#![recursion_limit = "256"]
+
+//! HTTP/JSON-RPC gateway to Storm node, allowing clients which can't use ZMQ and strict
+//! encoding, like web dashboards, to work with the node.
+
+#[macro_use]
+extern crate amplify;
+#[macro_use]
+extern crate log;
+#[macro_use]
+extern crate clap;
+#[macro_use]
+extern crate serde_crate as serde;
+
+mod api;
+mod http;
+mod opts;
+mod server;
+
+use std::net::TcpListener;
+use std::process;
+use std::time::Duration;
+
+use clap::Parser;
+use storm_rpc::{AuthToken, RPC_COOKIE_FILE};
+
+pub use crate::opts::Opts;
+
+fn main() {
+    eprintln!("storm-gateway: HTTP/JSON-RPC gateway to Storm node");
+
+    let mut opts = Opts::parse();
+    opts.process();
+    trace!("Command-line arguments: {:#?}", &opts);
+
+    let token = AuthToken::read_or_create(&opts.token_file).unwrap_or_else(|err| {
+        error!("Unable to load access token from {}: {}", opts.token_file, err);
+        process::exit(1);
+    });
+
+    let rpc_token = opts.rpc_token.clone().or_else(|| {
+        let cookie_file = opts.data_dir.join(RPC_COOKIE_FILE);
+        AuthToken::read(&cookie_file)
+            .map_err(|err| {
+                warn!("Unable to read node RPC access cookie {}: {}", cookie_file.display(), err)
+            })
+            .ok()
+    });
+
+    let listener = TcpListener::bind(opts.listen).unwrap_or_else(|err| {
+        error!("Unable to listen on {}: {}", opts.listen, err);
+        process::exit(1);
+    });
+    if !opts.listen.ip().is_loopback() {
+        warn!(
+            "Gateway listens on non-local address {}; use TLS-terminating proxy to protect the \
+             access token",
+            opts.listen
+        );
+    }
+    info!("Listening for HTTP connections on {}", opts.listen);
+
+    let config = server::Config {
+        token,
+        storm_endpoint: opts.storm_endpoint,
+        radio_endpoint: opts.radio_endpoint,
+        rpc_token,
+        timeout: Duration::from_secs(opts.timeout),
+    };
+    if let Err(err) = server::run(listener, config) {
+        error!("Gateway has terminated: {}", err);
+        process::exit(1);
+    }
+}
@@ -0,0 +1,119 @@
+// Storm node providing distributed storage & messaging for lightning network.
+//
+// Written in 2022 by
+//     Dr. Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2022 by LNP/BP Standards Association, Switzerland.
+//
+// You should have received a copy of the MIT License along with this software.
+// If not, see <https://opensource.org/licenses/MIT>.
+
+use std::net::SocketAddr;
+use std::path::PathBuf;
+
+use clap::ValueHint;
+use internet2::addr::ServiceAddr;
+use storm_ext::STORM_NODE_DATA_DIR;
+use storm_rpc::{AuthToken, CHATD_RPC_ENDPOINT, STORM_NODE_RPC_ENDPOINT};
+
+/// Default address the gateway listens on. Only local connections are accepted by default.
+pub const STORM_GATEWAY_ENDPOINT: &str = "127.0.0.1:64980";
+
+/// Default file with the gateway access token
+pub const STORM_GATEWAY_TOKEN_FILE: &str = "{data_dir}/gateway.token";
+
+/// HTTP/JSON-RPC gateway to Storm node
+#[derive(Parser, Clone, PartialEq, Eq, Debug)]
+#[clap(name = "storm-gateway", bin_name = "storm-gateway", author, version)]
+pub struct Opts {
+    /// Address to listen for HTTP connections on.
+    ///
+    /// Exposing the gateway on a non-local interface gives everyone knowing the access token
+    /// full control over the node; a TLS-terminating proxy should be used in such case.
+    #[clap(short, long, default_value = STORM_GATEWAY_ENDPOINT, env = "STORM_GATEWAY_ENDPOINT")]
+    pub listen: SocketAddr,
+
+    /// Data directory path.
+    ///
+    /// Path to the directory that contains the gateway access token file and the node RPC access
+    /// cookie.
+    #[clap(
+        short,
+        long,
+        default_value = STORM_NODE_DATA_DIR,
+        env = "STORM_NODE_DATA_DIR",
+        value_hint = ValueHint::DirPath
+    )]
+    pub data_dir: PathBuf,
+
+    /// File with the access token which HTTP clients must provide.
+    ///
+    /// If the file does not exist, a new random token is generated and saved into it.
+    #[clap(
+        long,
+        default_value = STORM_GATEWAY_TOKEN_FILE,
+        env = "STORM_GATEWAY_TOKEN_FILE",
+        value_hint = ValueHint::FilePath
+    )]
+    pub token_file: String,
+
+    /// ZMQ socket for connecting Storm node RPC interface.
+    ///
+    /// Socket can be either TCP address in form of `<ipv4 | ipv6>:<port>` – or a path
+    /// to an IPC file.
+    #[clap(
+        short = 'S',
+        long = "storm",
+        default_value = STORM_NODE_RPC_ENDPOINT,
+        env = "STORM_NODE_RPC_ENDPOINT"
+    )]
+    pub storm_endpoint: ServiceAddr,
+
+    /// ZMQ socket for chat daemon PUB/SUB API.
+    ///
+    /// Socket can be either TCP address in form of `<ipv4 | ipv6>:<port>` – or a path
+    /// to an IPC file.
+    #[clap(
+        short = 'C',
+        long = "chat",
+        env = "CHATD_RPC_ENDPOINT",
+        default_value = CHATD_RPC_ENDPOINT,
+    )]
+    pub radio_endpoint: ServiceAddr,
+
+    /// Access token for the node RPC interface.
+    ///
+    /// Defaults to the node access cookie from the data directory, granting full access to the
+    /// node. A token with a limited role restricts JSON-RPC methods available to the gateway
+    /// clients.
+    #[clap(long, env = "STORM_NODE_RPC_TOKEN", hide_env_values = true)]
+    pub rpc_token: Option<AuthToken>,
+
+    /// Maximal time (in seconds) to wait for a reply or a progress report from the node.
+    ///
+    /// Long operations do not time out as long as they keep reporting progress.
+    #[clap(long, default_value = "60", env = "STORM_GATEWAY_TIMEOUT", value_name = "SECS")]
+    pub timeout: u64,
+
+    /// Set verbosity level.
+    ///
+    /// Can be used multiple times to increase verbosity.
+    #[clap(short, long, parse(from_occurrences))]
+    pub verbose: u8,
+}
+
+impl Opts {
+    pub fn process(&mut self) {
+        microservices::shell::shell_setup(
+            self.verbose,
+            [&mut self.storm_endpoint, &mut self.radio_endpoint],
+            &mut self.data_dir,
+            &[],
+        );
+        microservices::shell::shell_expand_dir(
+            &mut self.token_file,
+            &self.data_dir.display().to_string(),
+            &[],
+        );
+    }
+}
@@ -0,0 +1,192 @@
+// Storm node providing distributed storage & messaging for lightning network.
+//
+// Written in 2022 by
+//     Dr. Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2022 by LNP/BP Standards Association, Switzerland.
+//
+// You should have received a copy of the MIT License along with this software.
+// If not, see <https://opensource.org/licenses/MIT>.
+
+//! HTTP server routing requests to the API handlers.
+//!
+//! | Request              | Description                                              |
+//! |----------------------|----------------------------------------------------------|
+//! | `POST /rpc`          | JSON-RPC 2.0 call, see [`crate::api`]                    |
+//! | `GET /chat/<node_id>`| server-sent event stream of chat messages from the peer  |
+//!
+//! JSON-RPC calls of the methods reporting progress return server-sent event stream with
+//! `progress` events followed by the `result` event if the request has `Accept:
+//! text/event-stream` header.
+//!
+//! All requests must be authenticated with the access token, provided either in
+//! `Authorization: Bearer <token>` header or, for the clients unable to set headers (like browser
+//! `EventSource`), in `token` query parameter.
+//!
+//! Each connection is served by a separate thread with its own node RPC client, so long-running
+//! requests and chat streams do not block other clients.
+
+use std::cell::RefCell;
+use std::net::{TcpListener, TcpStream};
+use std::sync::Arc;
+use std::time::Duration;
+use std::{io, thread};
+
+use internet2::addr::{NodeId, ServiceAddr};
+use serde_json::Value;
+use storm_rpc::{AuthToken, Client, ProgressEvent, Timeouts};
+
+use crate::api::{self, RpcError, RpcRequest, RpcResponse, INVALID_REQUEST, PARSE_ERROR};
+use crate::http::{respond_error, respond_json, EventStream, Request, Status};
+
+/// Time after which a connection not sending a complete request is dropped
+const READ_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Interval of keep-alive comments sent to the idle chat streams
+const KEEP_ALIVE_INTERVAL: Duration = Duration::from_secs(15);
+
+/// Settings shared by all connections
+#[derive(Clone, Debug)]
+pub struct Config {
+    /// Token which HTTP clients must provide
+    pub token: AuthToken,
+    pub storm_endpoint: ServiceAddr,
+    pub radio_endpoint: ServiceAddr,
+    /// Token authenticating the gateway to the node
+    pub rpc_token: Option<AuthToken>,
+    pub timeout: Duration,
+}
+
+/// Accepts connections and serves each of them in a separate thread.
+pub fn run(listener: TcpListener, config: Config) -> Result<(), io::Error> {
+    let config = Arc::new(config);
+    for stream in listener.incoming() {
+        let stream = match stream {
+            Ok(stream) => stream,
+            Err(err) => {
+                warn!("Unable to accept connection: {}", err);
+                continue;
+            }
+        };
+        let config = config.clone();
+        thread::Builder::new().name(s!("gateway-connection")).spawn(move || {
+            let peer = stream.peer_addr().map(|addr| addr.to_string()).unwrap_or_default();
+            if let Err(err) = serve(&stream, &config) {
+                debug!("Connection with {} terminated: {}", peer, err);
+            }
+        })?;
+    }
+    Ok(())
+}
+
+fn serve(stream: &TcpStream, config: &Config) -> Result<(), io::Error> {
+    stream.set_read_timeout(Some(READ_TIMEOUT))?;
+    let request = match Request::read(stream) {
+        Ok(request) => request,
+        Err(err) => return respond_error(stream, err.status(), &err.to_string()),
+    };
+    trace!("HTTP request {} {}", request.method, request.path);
+
+    if !is_authorized(&request, &config.token) {
+        return respond_error(stream, Status::Unauthorized, "missing or invalid access token");
+    }
+
+    match (request.method.as_str(), request.segments().as_slice()) {
+        ("POST", ["rpc"]) => {
+            let mut client = match connect(config) {
+                Ok(client) => client,
+                Err(err) => return respond_error(stream, Status::BadGateway, &err.to_string()),
+            };
+            serve_rpc(stream, &request, &mut client)
+        }
+        ("GET", ["chat", remote_id]) => {
+            let remote_id = match remote_id.parse::<NodeId>() {
+                Ok(remote_id) => remote_id,
+                Err(_) => return respond_error(stream, Status::BadRequest, "invalid node id"),
+            };
+            let mut client = match connect(config) {
+                Ok(client) => client,
+                Err(err) => return respond_error(stream, Status::BadGateway, &err.to_string()),
+            };
+            serve_chat(stream, remote_id, &mut client)
+        }
+        (_, ["rpc"]) | (_, ["chat", _]) => {
+            respond_error(stream, Status::MethodNotAllowed, "method not allowed")
+        }
+        _ => respond_error(stream, Status::NotFound, "not found"),
+    }
+}
+
+fn connect(config: &Config) -> Result<Client, storm_rpc::Error> {
+    let mut client = Client::with(
+        config.storm_endpoint.clone(),
+        config.radio_endpoint.clone(),
+        s!("storm-gateway"),
+    )?;
+    client.set_timeouts(Timeouts::idle(config.timeout));
+    if let Some(token) = &config.rpc_token {
+        client.set_auth_token(token.clone());
+    }
+    Ok(client)
+}
+
+fn serve_rpc(stream: &TcpStream, request: &Request, client: &mut Client) -> Result<(), io::Error> {
+    let rpc_request = match serde_json::from_slice::<Value>(&request.body) {
+        Err(err) => Err(RpcError::with(PARSE_ERROR, err)),
+        Ok(value) => serde_json::from_value::<RpcRequest>(value)
+            .map_err(|err| RpcError::with(INVALID_REQUEST, err))
+            .and_then(|rpc_request| {
+                if rpc_request.jsonrpc == "2.0" {
+                    Ok(rpc_request)
+                } else {
+                    Err(RpcError::with(INVALID_REQUEST, "unsupported JSON-RPC version"))
+                }
+            }),
+    };
+    let rpc_request = match rpc_request {
+        Ok(rpc_request) => rpc_request,
+        Err(err) => {
+            return respond_json(stream, Status::Ok, &RpcResponse::with(Value::Null, Err(err)))
+        }
+    };
+
+    if !(request.accepts_events() && api::is_progressive(&rpc_request.method)) {
+        let result = api::call(client, &rpc_request.method, rpc_request.params, &|_| {});
+        return respond_json(stream, Status::Ok, &RpcResponse::with(rpc_request.id, result));
+    }
+
+    let events = RefCell::new(EventStream::start(stream)?);
+    let progress = |event: ProgressEvent| {
+        // The client may have gone; the request is completed anyway
+        let _ = events.borrow_mut().send("progress", &event);
+    };
+    let result = api::call(client, &rpc_request.method, rpc_request.params, &progress);
+    let response = RpcResponse::with(rpc_request.id, result);
+    events.into_inner().send("result", &response)
+}
+
+fn serve_chat(stream: &TcpStream, remote_id: NodeId, client: &mut Client) -> Result<(), io::Error> {
+    client.set_timeouts(Timeouts::idle(KEEP_ALIVE_INTERVAL));
+    if let Err(err) = client.chat_subscribe(remote_id, None) {
+        return respond_error(stream, Status::BadGateway, &err.to_string());
+    }
+    let mut events = EventStream::start(stream)?;
+    loop {
+        match client.chat_recv(remote_id) {
+            Ok(msg) => events.send("message", &msg)?,
+            // Timeout here just means that the peer is silent
+            Err(storm_rpc::Error::Timeout(_)) => events.keep_alive()?,
+            Err(err) => return events.send("error", &RpcError::from(err)),
+        }
+    }
+}
+
+fn is_authorized(request: &Request, token: &AuthToken) -> bool {
+    request
+        .header("authorization")
+        .and_then(|auth| auth.strip_prefix("Bearer "))
+        .or_else(|| request.query.get("token").map(String::as_str))
+        .and_then(|provided| provided.parse::<AuthToken>().ok())
+        .map(|provided| provided == *token)
+        .unwrap_or_default()
+}
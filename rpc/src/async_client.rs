@@ -0,0 +1,358 @@
+// Storm node providing distributed storage & messaging for lightning network.
+//
+// Written in 2022 by
+//     Dr. Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2022 by LNP/BP Standards Association, Switzerland.
+//
+// You should have received a copy of the MIT License along with this software.
+// If not, see <https://opensource.org/licenses/MIT>.
+
+//! Asynchronous RPC client for use from tokio-based services.
+//!
+//! ZMQ sockets of the [`Client`] are blocking, so [`AsyncClient`] keeps them in a dedicated worker
+//! thread and executes requests there one by one. Futures returned by the client do not block
+//! the runtime and may be used with any executor.
+//!
+//! [`ProgressStream`] and [`ChatStream`] implement [`Stream`], so they can be used with stream
+//! combinators of any async library.
+//!
+//! Requests are cancelled by dropping their future, [`ProgressStream`] or [`ChatStream`].
+//! Requests which were not started yet are not sent to the node at all; requests which are
+//! already sent run in the worker until the node replies, but their progress and results are
+//! discarded.
+
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::mpsc as std_mpsc;
+use std::task::{Context, Poll};
+use std::thread;
+use std::time::Duration;
+
+use futures_core::Stream;
+use internet2::addr::{NodeAddr, NodeId, ServiceAddr};
+use microservices::esb::ClientId;
+use storm::{ContainerId, MesgId};
+use tokio::sync::{mpsc, oneshot};
+
+use crate::{
+    AuthToken, CatalogEntry, CatalogFilter, ChatMsg, ChatRecord, Client, ContainerFilter,
+    ContainerSummary, Error, FsckReport, GcReport, PeerInfo, PendingContainer, ProgressEvent,
+    QuotaUsage, Timeouts,
+};
+
+/// Interval in which the worker receiving chat messages checks whether the [`ChatStream`] is
+/// still alive.
+const CHAT_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+type Job = Box<dyn FnOnce(&mut Client) + Send>;
+
+/// Asynchronous version of [`Client`].
+///
+/// Cloning the client is cheap; all clones share the same connection and worker thread, which
+/// terminates once the last clone is dropped.
+#[derive(Clone, Debug)]
+pub struct AsyncClient {
+    client_id: ClientId,
+    jobs: std_mpsc::Sender<Job>,
+}
+
+impl AsyncClient {
+    /// Connects to the node. Unlike [`Client::with`], does not block while ZMQ bootstraps.
+    pub async fn with(
+        rpc_endpoint: ServiceAddr,
+        chat_endpoint: ServiceAddr,
+        user_agent: String,
+    ) -> Result<Self, Error> {
+        let (jobs, queue) = std_mpsc::channel::<Job>();
+        let (ready, connected) = oneshot::channel();
+        thread::Builder::new()
+            .name(s!("storm-rpc-client"))
+            .spawn(move || {
+                let mut client = match Client::with(rpc_endpoint, chat_endpoint, user_agent) {
+                    Ok(client) => {
+                        let _ = ready.send(Ok(client.client_id()));
+                        client
+                    }
+                    Err(err) => {
+                        let _ = ready.send(Err(err));
+                        return;
+                    }
+                };
+                for job in queue {
+                    job(&mut client);
+                }
+                debug!("RPC client worker terminated");
+            })
+            .map_err(|_| Error::ClientTerminated)?;
+        let client_id = connected.await.map_err(|_| Error::ClientTerminated)??;
+        Ok(AsyncClient { client_id, jobs })
+    }
+
+    pub fn client_id(&self) -> ClientId { self.client_id }
+
+    /// Executes a blocking client operation in the worker thread. Allows to use operations of
+    /// [`Client`] which do not have async counterparts.
+    pub async fn call<T>(
+        &self,
+        f: impl FnOnce(&mut Client) -> Result<T, Error> + Send + 'static,
+    ) -> Result<T, Error>
+    where
+        T: Send + 'static,
+    {
+        let (tx, rx) = oneshot::channel();
+        self.submit(move |client| {
+            if !tx.is_closed() {
+                let _ = tx.send(f(client));
+            }
+        })?;
+        rx.await.map_err(|_| Error::ClientTerminated)?
+    }
+
+    /// Executes a blocking client operation reporting progress in the worker thread.
+    pub fn call_with_progress<T>(
+        &self,
+        f: impl FnOnce(&mut Client, &dyn Fn(ProgressEvent)) -> Result<T, Error> + Send + 'static,
+    ) -> ProgressStream<T>
+    where
+        T: Send + 'static,
+    {
+        let (progress_tx, progress) = mpsc::unbounded_channel();
+        let (tx, result) = oneshot::channel();
+        let submitted = self.submit(move |client| {
+            if tx.is_closed() {
+                return;
+            }
+            let report = |event: ProgressEvent| {
+                let _ = progress_tx.send(event);
+            };
+            let _ = tx.send(f(client, &report));
+        });
+        ProgressStream {
+            progress: submitted.ok().map(|_| progress),
+            result,
+        }
+    }
+
+    fn submit(&self, job: impl FnOnce(&mut Client) + Send + 'static) -> Result<(), Error> {
+        self.jobs.send(Box::new(job)).map_err(|_| Error::ClientTerminated)
+    }
+}
+
+impl AsyncClient {
+    /// Sets access token put into all further requests, see [`Client::set_auth_token`].
+    pub async fn set_auth_token(&self, token: AuthToken) -> Result<(), Error> {
+        self.call(move |client| {
+            client.set_auth_token(token);
+            Ok(())
+        })
+        .await
+    }
+
+    pub async fn chat_tell(&self, remote_id: NodeId, text: String) -> Result<(), Error> {
+        self.call(move |client| client.chat_tell(remote_id, text)).await
+    }
+
+    /// Returns chat messages sent to and received from the remote peer, oldest first.
+    pub async fn chat_history(&self, remote_id: NodeId) -> Result<Vec<ChatRecord>, Error> {
+        self.call(move |client| client.chat_history(remote_id)).await
+    }
+
+    /// Returns stream of chat messages from a remote peer and, optionally, a specific room within
+    /// the conversation with that peer.
+    ///
+    /// The worker thread is occupied by receiving chat messages while the stream is alive, so a
+    /// separate client should be used for other requests. Dropping the stream stops receiving and
+    /// releases the worker within a second. The stream waits for messages regardless of the
+    /// client timeouts.
+    pub fn chat_messages(&self, remote_id: NodeId, room: Option<MesgId>) -> ChatStream {
+        let (tx, messages) = mpsc::unbounded_channel();
+        let _ = self.submit(move |client| {
+            if let Err(err) = client.chat_subscribe(remote_id, room) {
+                let _ = tx.send(Err(err));
+                return;
+            }
+            let timeouts = client.timeouts();
+            client.set_timeouts(Timeouts::idle(CHAT_POLL_INTERVAL));
+            while !tx.is_closed() {
+                let msg = match client.chat_recv(remote_id) {
+                    Err(Error::Timeout(_)) => continue,
+                    msg => msg,
+                };
+                let failed = msg.is_err();
+                if tx.send(msg).is_err() || failed {
+                    break;
+                }
+            }
+            client.set_timeouts(timeouts);
+            let _ = client.chat_subscribe_all();
+        });
+        ChatStream { messages }
+    }
+
+    pub fn upload(&self, remote_id: NodeId, container_id: ContainerId) -> ProgressStream<()> {
+        self.call_with_progress(move |client, progress| {
+            client.upload(remote_id, container_id, progress)
+        })
+    }
+
+    pub fn download(&self, remote_id: NodeId, container_id: ContainerId) -> ProgressStream<()> {
+        self.call_with_progress(move |client, progress| {
+            client.download(remote_id, container_id, progress)
+        })
+    }
+
+    /// Deletes container from the Store database. Container chunks are reclaimed by the next
+    /// garbage collection pass.
+    pub fn delete_container(&self, container_id: ContainerId) -> ProgressStream<()> {
+        self.call_with_progress(move |client, progress| {
+            client.delete_container(container_id, progress)
+        })
+    }
+
+    /// Pins container, protecting it from deletion, or unpins it.
+    pub fn pin_container(&self, container_id: ContainerId, pin: bool) -> ProgressStream<()> {
+        self.call_with_progress(move |client, progress| {
+            client.pin_container(container_id, pin, progress)
+        })
+    }
+
+    /// Runs garbage collection pass in the Store database.
+    pub fn collect_garbage(&self, dry_run: bool) -> ProgressStream<GcReport> {
+        self.call_with_progress(move |client, progress| client.collect_garbage(dry_run, progress))
+    }
+
+    /// Checks integrity of the containers and chunks kept by the node. If `repair_from` peer is
+    /// given, damaged containers are re-fetched from it in background.
+    pub fn fsck(&self, repair_from: Option<NodeId>) -> ProgressStream<FsckReport> {
+        self.call_with_progress(move |client, progress| client.fsck(repair_from, progress))
+    }
+
+    /// Lists containers kept by the node which match the filter.
+    pub async fn containers(
+        &self,
+        filter: ContainerFilter,
+    ) -> Result<Vec<ContainerSummary>, Error> {
+        self.call(move |client| client.containers(filter)).await
+    }
+
+    /// Returns information about a container kept by the node.
+    pub async fn container_info(
+        &self,
+        container_id: ContainerId,
+    ) -> Result<ContainerSummary, Error> {
+        self.call(move |client| client.container_info(container_id)).await
+    }
+
+    /// Reports storage used by the containers received from remote peers and the quotas.
+    pub async fn quotas(&self) -> Result<Vec<QuotaUsage>, Error> {
+        self.call(|client| client.quotas()).await
+    }
+
+    /// Connects to a remote peer over Bifrost protocol.
+    pub async fn connect(&self, node_addr: NodeAddr) -> Result<(), Error> {
+        self.call(move |client| client.connect(node_addr)).await
+    }
+
+    /// Disconnects from a remote peer.
+    pub async fn disconnect(&self, remote_id: NodeId) -> Result<(), Error> {
+        self.call(move |client| client.disconnect(remote_id)).await
+    }
+
+    /// Lists remote Bifrost peers connected to the node.
+    pub async fn peers(&self) -> Result<Vec<PeerInfo>, Error> {
+        self.call(|client| client.peers()).await
+    }
+
+    /// Lists container announcements which were put on hold by the downpour policy.
+    pub async fn pending_containers(&self) -> Result<Vec<PendingContainer>, Error> {
+        self.call(|client| client.pending_containers()).await
+    }
+
+    /// Announces locally present container to the downpour seed peers.
+    pub fn seed(&self, container_id: ContainerId) -> ProgressStream<()> {
+        self.call_with_progress(move |client, progress| client.seed(container_id, progress))
+    }
+
+    /// Lists containers from the downpour catalog matching the filter.
+    pub async fn catalog(&self, filter: CatalogFilter) -> Result<Vec<CatalogEntry>, Error> {
+        self.call(move |client| client.catalog(filter)).await
+    }
+
+    pub fn accept_container(&self, container_id: ContainerId) -> ProgressStream<()> {
+        self.call_with_progress(move |client, progress| {
+            client.accept_container(container_id, progress)
+        })
+    }
+
+    pub fn decline_container(&self, container_id: ContainerId) -> ProgressStream<()> {
+        self.call_with_progress(move |client, progress| {
+            client.decline_container(container_id, progress)
+        })
+    }
+}
+
+/// Progress reports of a running request followed by its result.
+///
+/// The stream yields progress reports with [`ProgressStream::next`] or as a [`Stream`]; once it
+/// ends, the request result is available with [`ProgressStream::finish`]. Awaiting the
+/// `ProgressStream` itself skips the progress reports and returns the result directly.
+#[derive(Debug)]
+pub struct ProgressStream<T> {
+    progress: Option<mpsc::UnboundedReceiver<ProgressEvent>>,
+    result: oneshot::Receiver<Result<T, Error>>,
+}
+
+impl<T> ProgressStream<T> {
+    /// Returns next progress report, or `None` if the request has completed.
+    pub async fn next(&mut self) -> Option<ProgressEvent> {
+        match &mut self.progress {
+            Some(progress) => progress.recv().await,
+            None => None,
+        }
+    }
+
+    /// Waits for the request to complete and returns its result, discarding pending progress
+    /// reports.
+    pub async fn finish(self) -> Result<T, Error> { self.await }
+}
+
+impl<T> Stream for ProgressStream<T> {
+    type Item = ProgressEvent;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        match &mut self.progress {
+            Some(progress) => progress.poll_recv(cx),
+            None => Poll::Ready(None),
+        }
+    }
+}
+
+impl<T> Future for ProgressStream<T> {
+    type Output = Result<T, Error>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        Pin::new(&mut self.result)
+            .poll(cx)
+            .map(|result| result.unwrap_or(Err(Error::ClientTerminated)))
+    }
+}
+
+/// Chat messages received from a remote peer.
+#[derive(Debug)]
+pub struct ChatStream {
+    messages: mpsc::UnboundedReceiver<Result<ChatMsg, Error>>,
+}
+
+impl ChatStream {
+    /// Returns next chat message, or `None` once receiving has stopped after a failure.
+    pub async fn next(&mut self) -> Option<Result<ChatMsg, Error>> { self.messages.recv().await }
+}
+
+impl Stream for ChatStream {
+    type Item = Result<ChatMsg, Error>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.messages.poll_recv(cx)
+    }
+}
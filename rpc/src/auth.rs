@@ -0,0 +1,266 @@
+// Storm node providing distributed storage & messaging for lightning network.
+//
+// Written in 2022 by
+//     Dr. Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2022 by LNP/BP Standards Association, Switzerland.
+//
+// You should have received a copy of the MIT License along with this software.
+// If not, see <https://opensource.org/licenses/MIT>.
+
+//! Authentication of RPC clients.
+//!
+//! Each RPC request carries an access token, which the node maps to a client [`Role`]. The node
+//! accepts two kinds of tokens, both kept in its data directory:
+//! - cookie, stored in [`RPC_COOKIE_FILE`], which is generated by the node on the first start and
+//!   grants [`Role::Full`] access to anybody able to read the file;
+//! - preshared tokens, listed in optional [`RPC_TOKENS_FILE`] one per line in form of `<role>
+//!   <token>`; empty lines and lines starting with `#` are ignored.
+//!
+//! Chat messages published by the chat daemon on its PUB socket are not covered by the tokens:
+//! anybody able to connect to the socket receives all of them. For this reason the socket is
+//! bound to the local interface by default (see [`crate::CHATD_RPC_ENDPOINT`]) and must not be
+//! exposed to untrusted networks.
+
+use std::fmt::{self, Debug, Formatter};
+use std::io::Write;
+use std::path::Path;
+use std::str::FromStr;
+use std::{fs, hash, io};
+
+use crate::RpcMsg;
+
+/// Name of the file in the node data directory containing access cookie
+pub const RPC_COOKIE_FILE: &str = "rpc.cookie";
+
+/// Name of the file in the node data directory listing preshared access tokens and their roles
+pub const RPC_TOKENS_FILE: &str = "rpc.tokens";
+
+/// Token authenticating RPC client requests
+#[derive(Clone, Eq, NetworkEncode, NetworkDecode)]
+pub struct AuthToken(String);
+
+impl AuthToken {
+    /// Generates new random token.
+    pub fn random() -> AuthToken {
+        AuthToken(rand::random::<[u8; 32]>().iter().map(|byte| format!("{:02x}", byte)).collect())
+    }
+
+    /// Reads token from a file, like the node cookie file.
+    pub fn read(path: impl AsRef<Path>) -> Result<AuthToken, io::Error> {
+        let path = path.as_ref();
+        let token = fs::read_to_string(path)?;
+        token.parse().map_err(|_| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("access token file {} is empty", path.display()),
+            )
+        })
+    }
+
+    /// Reads token from a file, generating a new random token and saving it into the file if
+    /// the file does not exist. New files are readable by the owner only.
+    pub fn read_or_create(path: impl AsRef<Path>) -> Result<AuthToken, io::Error> {
+        let path = path.as_ref();
+        match AuthToken::read(path) {
+            Err(err) if err.kind() == io::ErrorKind::NotFound => {}
+            res => return res,
+        }
+
+        let token = AuthToken::random();
+        let mut options = fs::OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+        match options.open(path) {
+            Ok(mut file) => {
+                writeln!(file, "{}", token.as_str())?;
+                info!("New access token is saved to {}", path.display());
+                Ok(token)
+            }
+            // Other process has just created the file
+            Err(err) if err.kind() == io::ErrorKind::AlreadyExists => AuthToken::read(path),
+            Err(err) => Err(err),
+        }
+    }
+
+    pub fn as_str(&self) -> &str { &self.0 }
+}
+
+impl FromStr for AuthToken {
+    type Err = EmptyToken;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim() {
+            "" => Err(EmptyToken),
+            token => Ok(AuthToken(token.to_owned())),
+        }
+    }
+}
+
+/// Comparison takes time not depending on the position of the first difference, so tokens can't
+/// be guessed byte by byte.
+impl PartialEq for AuthToken {
+    fn eq(&self, other: &Self) -> bool {
+        let (a, b) = (self.0.as_bytes(), other.0.as_bytes());
+        a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (a, b)| acc | (a ^ b)) == 0
+    }
+}
+
+impl hash::Hash for AuthToken {
+    fn hash<H: hash::Hasher>(&self, state: &mut H) { self.0.hash(state) }
+}
+
+/// Tokens are never printed, so they do not leak into the logs.
+impl Debug for AuthToken {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result { f.write_str("AuthToken(..)") }
+}
+
+/// Error parsing [`AuthToken`] from an empty string
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Display, Error)]
+#[display("access token must not be empty")]
+pub struct EmptyToken;
+
+/// Role of an RPC client, defining which requests the client may make
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, Display)]
+pub enum Role {
+    /// Lists peers, containers, quotas and downpour catalog; does not change the node state.
+    #[display("read-only")]
+    ReadOnly,
+
+    /// Lists peers, sends chat messages and reads chat history.
+    #[display("chat")]
+    Chat,
+
+    /// Makes any requests.
+    #[display("full")]
+    Full,
+}
+
+impl Role {
+    /// Checks whether the role permits the request.
+    pub fn permits(self, msg: &RpcMsg) -> bool {
+        match self {
+            Role::Full => true,
+            Role::ReadOnly => matches!(
+                msg,
+                RpcMsg::ListPeers
+                    | RpcMsg::ListContainers(_)
+                    | RpcMsg::ContainerInfo(_)
+                    | RpcMsg::ListQuotas
+                    | RpcMsg::ListPending
+                    | RpcMsg::ListCatalog(_)
+                    | RpcMsg::CollectGarbage(true)
+            ),
+            Role::Chat => {
+                matches!(msg, RpcMsg::ListPeers | RpcMsg::SendChat(_) | RpcMsg::ChatHistory(_))
+            }
+        }
+    }
+}
+
+/// Error parsing [`Role`] from a string
+#[derive(Clone, Eq, PartialEq, Hash, Debug, Display, Error)]
+#[display("unknown client role `{0}`; possible values are read-only, chat and full")]
+pub struct RoleParseError(String);
+
+impl FromStr for Role {
+    type Err = RoleParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s.to_lowercase().as_str() {
+            "read-only" => Role::ReadOnly,
+            "chat" => Role::Chat,
+            "full" => Role::Full,
+            other => return Err(RoleParseError(other.to_owned())),
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use internet2::addr::LocalNode;
+    use storm::ContainerId;
+
+    use super::*;
+    use crate::AddressedMsg;
+
+    #[test]
+    fn permits() {
+        let remote_id = LocalNode::with(
+            secp256k1::SECP256K1,
+            secp256k1::SecretKey::from_slice(&[1; 32]).unwrap(),
+        )
+        .node_id();
+        let chat = RpcMsg::SendChat(AddressedMsg {
+            remote_id,
+            data: s!("hello"),
+        });
+        let list = RpcMsg::ListContainers(default!());
+        let delete = RpcMsg::DeleteContainer(ContainerId::default());
+
+        for msg in [&RpcMsg::ListPeers, &list, &RpcMsg::ListQuotas, &RpcMsg::CollectGarbage(true)] {
+            assert!(Role::ReadOnly.permits(msg));
+        }
+        for msg in [&chat, &delete, &RpcMsg::CollectGarbage(false), &RpcMsg::Fsck(None)] {
+            assert!(!Role::ReadOnly.permits(msg));
+        }
+
+        for msg in [&RpcMsg::ListPeers, &chat, &RpcMsg::ChatHistory(remote_id)] {
+            assert!(Role::Chat.permits(msg));
+        }
+        for msg in [&list, &delete, &RpcMsg::CollectGarbage(true)] {
+            assert!(!Role::Chat.permits(msg));
+        }
+
+        for msg in [&RpcMsg::ListPeers, &chat, &list, &delete, &RpcMsg::CollectGarbage(false)] {
+            assert!(Role::Full.permits(msg));
+        }
+    }
+
+    #[test]
+    fn role_parse() {
+        for role in [Role::ReadOnly, Role::Chat, Role::Full] {
+            assert_eq!(role.to_string().parse(), Ok(role));
+        }
+        assert_eq!("Read-Only".parse(), Ok(Role::ReadOnly));
+        assert_eq!("admin".parse::<Role>(), Err(RoleParseError(s!("admin"))));
+    }
+
+    #[test]
+    fn token() {
+        assert_eq!(" abc\n".parse(), Ok(AuthToken(s!("abc"))));
+        assert_eq!(" \n".parse::<AuthToken>(), Err(EmptyToken));
+        assert_ne!(AuthToken(s!("abc")), AuthToken(s!("abd")));
+        assert_ne!(AuthToken(s!("abc")), AuthToken(s!("abcd")));
+        assert_ne!(AuthToken::random(), AuthToken::random());
+        assert_eq!(AuthToken::random().as_str().len(), 64);
+        assert_eq!(format!("{:?}", AuthToken(s!("secret"))), "AuthToken(..)");
+    }
+
+    #[test]
+    fn token_file() {
+        let path = std::env::temp_dir().join(format!("storm-auth-token-{}", std::process::id()));
+        let _ = fs::remove_file(&path);
+        assert_eq!(AuthToken::read(&path).unwrap_err().kind(), io::ErrorKind::NotFound);
+
+        let token = AuthToken::read_or_create(&path).unwrap();
+        assert_eq!(AuthToken::read(&path).unwrap(), token);
+        assert_eq!(AuthToken::read_or_create(&path).unwrap(), token);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            assert_eq!(fs::metadata(&path).unwrap().permissions().mode() & 0o777, 0o600);
+        }
+
+        fs::write(&path, "\n").unwrap();
+        assert_eq!(
+            AuthToken::read_or_create(&path).unwrap_err().kind(),
+            io::ErrorKind::InvalidData
+        );
+        fs::remove_file(&path).unwrap();
+    }
+}
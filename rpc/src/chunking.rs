@@ -0,0 +1,327 @@
+// Storm node providing distributed storage & messaging for lightning network.
+//
+// Written in 2022 by
+//     Dr. Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2022 by LNP/BP Standards Association, Switzerland.
+//
+// You should have received a copy of the MIT License along with this software.
+// If not, see <https://opensource.org/licenses/MIT>.
+
+//! Strategies for splitting data into chunks.
+//!
+//! Chunking strategy used for a container is recorded as a `chunking` parameter of the container
+//! header MIME type, for instance `application/octet-stream; chunking=cdc-524288-2097152-8388608`.
+//! Containers produced with [`Chunking::Fixed`] strategy have no such parameter.
+
+use std::fmt::{self, Display, Formatter};
+use std::io::{self, Read};
+use std::str::FromStr;
+
+use stens::AsciiString;
+use storm::ContainerHeader;
+
+use crate::container::CHUNK_SIZE;
+
+const MIME_PARAM: &str = "chunking";
+
+/// Strategy for splitting data into chunks
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug)]
+pub enum Chunking {
+    /// Splits data at fixed offsets, producing chunks of the maximal possible size.
+    Fixed,
+
+    /// Splits data at content-defined boundaries detected with a rolling hash, such that
+    /// insertions and deletions in the data change only chunks around the modification.
+    ContentDefined {
+        /// Minimal chunk size
+        min: u32,
+        /// Average chunk size
+        avg: u32,
+        /// Maximal chunk size
+        max: u32,
+    },
+}
+
+impl Default for Chunking {
+    fn default() -> Self { Chunking::Fixed }
+}
+
+impl Chunking {
+    /// Default parameters for content-defined chunking
+    pub const CDC_DEFAULT: Chunking = Chunking::ContentDefined {
+        min: 512 * 1024,
+        avg: 2 * 1024 * 1024,
+        max: 8 * 1024 * 1024,
+    };
+
+    /// Detects chunking strategy from the container header MIME type.
+    pub fn from_header(header: &ContainerHeader) -> Result<Chunking, ChunkingError> {
+        header
+            .mime
+            .as_str()
+            .split(';')
+            .skip(1)
+            .filter_map(|param| param.split_once('='))
+            .find(|(name, _)| name.trim() == MIME_PARAM)
+            .map(|(_, value)| value.trim().parse())
+            .unwrap_or(Ok(Chunking::Fixed))
+    }
+
+    /// Adds chunking parameter to the MIME type. Types used with [`Chunking::Fixed`] are returned
+    /// unchanged.
+    pub fn apply_to_mime(self, mime: AsciiString) -> AsciiString {
+        match self {
+            Chunking::Fixed => mime,
+            _ => AsciiString::from_str(&format!("{}; {}={}", mime, MIME_PARAM, self))
+                .expect("chunking parameter is an ASCII string"),
+        }
+    }
+
+    fn max_size(self) -> usize {
+        match self {
+            Chunking::Fixed => CHUNK_SIZE,
+            Chunking::ContentDefined { max, .. } => max as usize,
+        }
+    }
+
+    /// Finds the end of the first chunk in the data. Returns `None` if the data do not contain
+    /// chunk boundary and more data are required.
+    fn boundary(self, data: &[u8]) -> Option<usize> {
+        let (min, avg) = match self {
+            Chunking::Fixed => return (data.len() >= CHUNK_SIZE).then(|| CHUNK_SIZE),
+            Chunking::ContentDefined { min, avg, .. } => (min as usize, avg),
+        };
+        let max = self.max_size();
+        if data.len() <= min {
+            return (data.len() >= max).then(|| data.len());
+        }
+        let mask = (1u64 << (31 - avg.leading_zeros())) - 1;
+        let mut hash = 0u64;
+        for (pos, byte) in data.iter().enumerate().take(max).skip(min) {
+            hash = (hash << 1).wrapping_add(GEAR[*byte as usize]);
+            if hash & mask == 0 {
+                return Some(pos + 1);
+            }
+        }
+        (data.len() >= max).then(|| max)
+    }
+}
+
+impl Display for Chunking {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Chunking::Fixed => f.write_str("fixed"),
+            Chunking::ContentDefined { min, avg, max } => write!(f, "cdc-{}-{}-{}", min, avg, max),
+        }
+    }
+}
+
+impl FromStr for Chunking {
+    type Err = ChunkingError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = s.split('-');
+        let chunking = match (parts.next(), parts.next(), parts.next(), parts.next()) {
+            (Some("fixed"), None, None, None) => Chunking::Fixed,
+            (Some("cdc"), None, None, None) => Chunking::CDC_DEFAULT,
+            (Some("cdc"), Some(min), Some(avg), Some(max)) => {
+                let parse = |val: &str| {
+                    val.parse::<u32>().map_err(|_| ChunkingError::Unknown(s.to_owned()))
+                };
+                Chunking::ContentDefined {
+                    min: parse(min)?,
+                    avg: parse(avg)?,
+                    max: parse(max)?,
+                }
+            }
+            _ => return Err(ChunkingError::Unknown(s.to_owned())),
+        };
+        if parts.next().is_some() {
+            return Err(ChunkingError::Unknown(s.to_owned()));
+        }
+        if let Chunking::ContentDefined { min, avg, max } = chunking {
+            if min == 0 || min > avg || avg > max || max as usize > CHUNK_SIZE {
+                return Err(ChunkingError::InvalidSizes { min, avg, max });
+            }
+        }
+        Ok(chunking)
+    }
+}
+
+/// Errors in chunking strategy specification
+#[derive(Clone, Eq, PartialEq, Hash, Debug, Display, Error)]
+#[display(doc_comments)]
+pub enum ChunkingError {
+    /// unknown chunking strategy `{0}`; use `fixed`, `cdc` or `cdc-MIN-AVG-MAX`
+    Unknown(String),
+
+    /// invalid content-defined chunking sizes {min}, {avg}, {max}; sizes must be non-zero, follow
+    /// in non-decreasing order and not exceed 16777215 bytes
+    InvalidSizes { min: u32, avg: u32, max: u32 },
+}
+
+/// Reads data from a reader and splits them into chunks according to the chunking strategy. Keeps
+/// no more than a single maximally-sized chunk in memory.
+pub(crate) struct Chunker<R: Read> {
+    reader: R,
+    chunking: Chunking,
+    limit: usize,
+    buf: Vec<u8>,
+    len: usize,
+    eof: bool,
+}
+
+impl<R: Read> Chunker<R> {
+    /// Constructs chunker producing chunks of no more than `limit` bytes, even if the chunking
+    /// strategy allows larger chunks.
+    pub fn with(reader: R, chunking: Chunking, limit: usize) -> Self {
+        Chunker {
+            reader,
+            chunking,
+            limit,
+            buf: vec![0u8; chunking.max_size()],
+            len: 0,
+            eof: false,
+        }
+    }
+
+    /// Returns the next chunk data, or `None` once all data are read.
+    pub fn next_chunk(&mut self) -> io::Result<Option<Vec<u8>>> {
+        while !self.eof && self.chunking.boundary(&self.buf[..self.len]).is_none() {
+            match self.reader.read(&mut self.buf[self.len..]) {
+                Ok(0) => self.eof = true,
+                Ok(len) => self.len += len,
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+                Err(err) => return Err(err),
+            }
+        }
+        if self.len == 0 {
+            return Ok(None);
+        }
+        let end = self.chunking.boundary(&self.buf[..self.len]).unwrap_or(self.len).min(self.limit);
+        let chunk = self.buf[..end].to_vec();
+        self.buf.copy_within(end..self.len, 0);
+        self.len -= end;
+        Ok(Some(chunk))
+    }
+}
+
+/// Random values used by the gear rolling hash, generated with SplitMix64 from a fixed seed.
+/// Changing them changes chunk boundaries of all content-defined chunked containers.
+static GEAR: [u64; 256] = gear_table();
+
+const fn gear_table() -> [u64; 256] {
+    let mut table = [0u64; 256];
+    let mut state = 0x5354_4f52_4d43_4443u64; // "STORMCDC"
+    let mut i = 0;
+    while i < 256 {
+        state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        table[i] = z ^ (z >> 31);
+        i += 1;
+    }
+    table
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn data(len: usize) -> Vec<u8> {
+        let mut state = 1u64;
+        (0..len)
+            .map(|_| {
+                state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+                (state >> 56) as u8
+            })
+            .collect()
+    }
+
+    fn split(data: &[u8], chunking: Chunking, limit: usize) -> Vec<Vec<u8>> {
+        let mut chunker = Chunker::with(data, chunking, limit);
+        let mut chunks = vec![];
+        while let Some(chunk) = chunker.next_chunk().unwrap() {
+            chunks.push(chunk);
+        }
+        chunks
+    }
+
+    const CDC: Chunking = Chunking::ContentDefined {
+        min: 64,
+        avg: 256,
+        max: 1024,
+    };
+
+    #[test]
+    fn parse() {
+        assert_eq!("fixed".parse(), Ok(Chunking::Fixed));
+        assert_eq!("cdc".parse(), Ok(Chunking::CDC_DEFAULT));
+        assert_eq!("cdc-64-256-1024".parse(), Ok(CDC));
+        assert_eq!(CDC.to_string().parse(), Ok(CDC));
+        assert_eq!(Chunking::CDC_DEFAULT.to_string(), "cdc-524288-2097152-8388608");
+        for s in ["", "cdc-", "cdc-1-2", "cdc-1-2-3-4", "cdc-a-2-3", "fixed-1", "rabin"] {
+            assert_eq!(s.parse::<Chunking>(), Err(ChunkingError::Unknown(s.to_owned())));
+        }
+        for (min, avg, max) in [(0, 1, 2), (3, 2, 4), (1, 3, 2), (1, 2, CHUNK_SIZE as u32 + 1)] {
+            assert_eq!(
+                format!("cdc-{}-{}-{}", min, avg, max).parse::<Chunking>(),
+                Err(ChunkingError::InvalidSizes { min, avg, max })
+            );
+        }
+    }
+
+    #[test]
+    fn mime() {
+        let header = |mime: AsciiString| ContainerHeader {
+            version: 0,
+            mime,
+            info: s!(""),
+            size: 0,
+        };
+        let mime = AsciiString::from_str("text/plain").unwrap();
+        assert_eq!(Chunking::Fixed.apply_to_mime(mime.clone()), mime);
+        assert_eq!(Chunking::from_header(&header(mime.clone())), Ok(Chunking::Fixed));
+        let mime = CDC.apply_to_mime(mime);
+        assert_eq!(mime.as_str(), "text/plain; chunking=cdc-64-256-1024");
+        assert_eq!(Chunking::from_header(&header(mime)), Ok(CDC));
+    }
+
+    #[test]
+    fn fixed_boundaries() {
+        let data = data(1000);
+        let chunks = split(&data, Chunking::Fixed, 300);
+        assert_eq!(chunks.iter().map(Vec::len).collect::<Vec<_>>(), vec![300, 300, 300, 100]);
+        assert_eq!(chunks.concat(), data);
+        assert!(Chunker::with(&b""[..], Chunking::Fixed, 300).next_chunk().unwrap().is_none());
+    }
+
+    #[test]
+    fn cdc_boundaries() {
+        let data = data(64 * 1024);
+        let chunks = split(&data, CDC, CHUNK_SIZE);
+        assert_eq!(chunks.concat(), data);
+        let (last, rest) = chunks.split_last().unwrap();
+        assert!(last.len() <= 1024);
+        assert!(rest.iter().all(|chunk| chunk.len() > 64 && chunk.len() <= 1024));
+        // Boundaries depend on the content, not on the offsets
+        assert!(rest.iter().any(|chunk| chunk.len() != rest[0].len()));
+
+        // Inserting data at the beginning changes only the chunks around the insertion
+        let mut modified = b"inserted".to_vec();
+        modified.extend(&data);
+        let modified_chunks = split(&modified, CDC, CHUNK_SIZE);
+        let shared = modified_chunks.iter().filter(|chunk| chunks.contains(chunk)).count();
+        assert!(shared >= chunks.len() - 2);
+    }
+
+    #[test]
+    fn cdc_limit() {
+        let data = data(4096);
+        let chunks = split(&data, CDC, 100);
+        assert_eq!(chunks.concat(), data);
+        assert!(chunks.iter().all(|chunk| chunk.len() <= 100));
+    }
+}
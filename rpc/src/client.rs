@@ -8,17 +8,25 @@
 // You should have received a copy of the MIT License along with this software.
 // If not, see <https://opensource.org/licenses/MIT>.
 
-use std::thread::sleep;
-use std::time::Duration;
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::thread::{self, sleep};
+use std::time::{Duration, Instant};
 
-use internet2::addr::{NodeId, ServiceAddr};
-use internet2::ZmqSocketType;
+use internet2::addr::{NodeAddr, NodeId, ServiceAddr};
+use internet2::session::LocalSession;
+use internet2::{SendRecvMessage, TypedEnum, ZmqSocketType};
 use microservices::esb::{self, BusId, ClientId, PollItem};
-use microservices::util::OptionDetails;
-use storm::{ContainerFullId, ContainerId, StormApp};
+use microservices::ZMQ_CONTEXT;
+use storm::{ContainerFullId, ContainerId, MesgId, StormApp};
 
 use crate::messages::RadioMsg;
-use crate::{AddressedMsg, AppContainer, BusMsg, Error, RpcMsg, ServiceId};
+use crate::{
+    AddressedMsg, AppContainer, AuthToken, BusMsg, CatalogEntry, CatalogFilter, ChatMsg,
+    ChatRecord, ChatTopic, ContainerFilter, ContainerSummary, Error, FsckReport, GcReport,
+    PeerInfo, PendingContainer, ProgressEvent, QuotaUsage, RequestId, RpcEnvelope, RpcMsg,
+    ServiceId,
+};
 
 #[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, Display)]
 enum Bus {
@@ -29,6 +37,10 @@ enum Bus {
     /// Pub/sub bus used for chat daemon
     #[display("CHAT")]
     Chat,
+
+    /// Internal bus waking the client up when a timeout expires
+    #[display("TIMER")]
+    Timer,
 }
 
 impl BusId for Bus {
@@ -36,13 +48,49 @@ impl BusId for Bus {
 }
 
 type Endpoints = esb::EndpointList<Bus>;
+type Controller = esb::Controller<Bus, BusMsg, Handler>;
+
+/// Timeouts for waiting replies from the node
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Default)]
+pub struct Timeouts {
+    /// Maximal duration of a request, from sending it to receiving the final reply. `None` waits
+    /// for the reply forever.
+    pub request: Option<Duration>,
+
+    /// Maximal time between two consecutive replies, including progress reports and chat
+    /// messages. `None` waits for the replies forever.
+    pub idle: Option<Duration>,
+}
+
+impl Timeouts {
+    /// Uses the same timeout for idle periods and does not limit request duration, so long
+    /// transfers which keep reporting progress do not time out.
+    pub fn idle(timeout: Duration) -> Self {
+        Timeouts {
+            request: None,
+            idle: Some(timeout),
+        }
+    }
+}
 
 #[repr(C)]
 pub struct Client {
     client_id: ClientId,
     user_agent: String,
-    response_queue: Vec<PollItem<Bus, BusMsg>>,
-    esb: esb::Controller<Bus, BusMsg, Handler>,
+    rpc_endpoint: ServiceAddr,
+    chat_endpoint: ServiceAddr,
+    chat_topic: Option<String>,
+    auth: Option<AuthToken>,
+    timeouts: Timeouts,
+    last_request_id: RequestId,
+    /// Requests awaiting replies, with the time they were sent
+    pending: BTreeMap<RequestId, Instant>,
+    response_queue: VecDeque<PollItem<Bus, BusMsg>>,
+    // Number of reconnections, used to give each connection its own timer endpoint
+    connections: u32,
+    watchdog: Watchdog,
+    // Always present; taken only while reconnecting
+    esb: Option<Controller>,
 }
 
 impl Client {
@@ -55,52 +103,167 @@ impl Client {
 
         debug!("Setting up RPC client...");
         let client_id = rand::random();
-        let esb = esb::Controller::with(
-            map! {
-                Bus::Rpc => esb::BusConfig::with_addr(
-                    rpc_endpoint,
-                    ZmqSocketType::RouterConnect,
-                    Some(ServiceId::stormd()),
-                ),
-                Bus::Chat => esb::BusConfig::with_subscription(
-                    chat_endpoint,
-                    ZmqSocketType::Sub,
-                    None,
-                )
-            },
-            Handler {
-                identity: ServiceId::Client(client_id),
-            },
-        )?;
-
-        // We have to sleep in order for ZMQ to bootstrap
-        sleep(Duration::from_secs_f32(0.1));
+        let timer_endpoint = timer_endpoint(client_id, 0);
+        let esb = connect(client_id, &rpc_endpoint, &chat_endpoint, None, &timer_endpoint)?;
+        let watchdog = Watchdog::with(client_id, timer_endpoint);
 
         Ok(Self {
             client_id,
             user_agent,
+            rpc_endpoint,
+            chat_endpoint,
+            chat_topic: None,
+            auth: None,
+            timeouts: default!(),
+            last_request_id: default!(),
+            pending: empty!(),
             response_queue: empty!(),
-            esb,
+            connections: 0,
+            watchdog,
+            esb: Some(esb),
         })
     }
 
     pub fn client_id(&self) -> ClientId { self.client_id }
 
-    fn request(&mut self, req: impl Into<RpcMsg>, service_id: ServiceId) -> Result<(), Error> {
-        let req = req.into();
-        debug!("Executing {}", req);
-        self.esb.send_to(Bus::Rpc, service_id, BusMsg::Rpc(req))?;
+    pub fn timeouts(&self) -> Timeouts { self.timeouts }
+
+    /// Sets timeouts for waiting replies from the node. By default, the client waits forever.
+    pub fn set_timeouts(&mut self, timeouts: Timeouts) { self.timeouts = timeouts; }
+
+    /// Sets access token put into all further requests. The node rejects requests without a
+    /// valid token, see [`crate::auth`].
+    pub fn set_auth_token(&mut self, token: AuthToken) { self.auth = Some(token); }
+
+    fn esb(&mut self) -> &mut Controller {
+        self.esb.as_mut().expect("ESB controller is taken only while reconnecting")
+    }
+
+    /// Re-creates connections to the node RPC and chat sockets, for instance after the node
+    /// restart. Replies which were not yet received are lost.
+    pub fn reconnect(&mut self) -> Result<(), Error> {
+        debug!("Reconnecting to RPC socket {}", self.rpc_endpoint);
+        // Old sockets must be closed before the new ones re-use the client identity
+        self.esb = None;
+        self.response_queue.clear();
+        // Closed in-process endpoints are not released immediately, so a new one is used
+        self.connections += 1;
+        let timer_endpoint = timer_endpoint(self.client_id, self.connections);
+        self.esb = Some(connect(
+            self.client_id,
+            &self.rpc_endpoint,
+            &self.chat_endpoint,
+            self.chat_topic.clone(),
+            &timer_endpoint,
+        )?);
+        self.watchdog.endpoint = timer_endpoint;
         Ok(())
     }
 
-    fn response(&mut self) -> Result<PollItem<Bus, BusMsg>, Error> {
+    /// Sends request to a node service without waiting for the reply. Replies to the request are
+    /// received with [`Client::next_reply`], which allows to run several requests at the same
+    /// time.
+    pub fn start_request(
+        &mut self,
+        req: impl Into<RpcMsg>,
+        service_id: ServiceId,
+    ) -> Result<RequestId, Error> {
+        let request_id = self.send(req, service_id)?;
+        self.pending.insert(request_id, Instant::now());
+        Ok(request_id)
+    }
+
+    /// Waits for the next progress report or reply to a request sent with
+    /// [`Client::start_request`]. Replies to other requests and chat messages are kept until
+    /// they are requested.
+    ///
+    /// Failure replies are returned as errors; no more replies are expected after any message
+    /// other than [`RpcMsg::Progress`]. Once the request times out, the client reconnects to the
+    /// node, losing replies to other requests which were not received yet.
+    pub fn next_reply(&mut self, request_id: RequestId) -> Result<RpcMsg, Error> {
+        let started = *self.pending.get(&request_id).ok_or(Error::UnknownRequest(request_id))?;
+        let deadline = self.timeouts.request.map(|timeout| started + timeout);
+        let poll = self.receive(deadline, started, |poll| {
+            matches!(&poll.request, BusMsg::Rpc(envelope) if envelope.request_id == request_id)
+        });
+        let msg = match poll {
+            Ok(PollItem {
+                request: BusMsg::Rpc(envelope),
+                ..
+            }) => envelope.msg,
+            Ok(_) => unreachable!("only replies to the request are received"),
+            Err(err) => {
+                self.pending.remove(&request_id);
+                // Node might have been restarted, in which case the old connection is stale
+                if matches!(err, Error::Timeout(_)) {
+                    warn!("Request {} timed out, reconnecting", request_id);
+                    if let Err(err) = self.reconnect() {
+                        warn!("Failed to reconnect: {}", err);
+                    }
+                }
+                return Err(err);
+            }
+        };
+        if !matches!(msg, RpcMsg::Progress(_)) {
+            self.pending.remove(&request_id);
+        }
+        msg.failure_to_error()
+    }
+
+    fn send(&mut self, req: impl Into<RpcMsg>, service_id: ServiceId) -> Result<RequestId, Error> {
+        self.last_request_id = self.last_request_id.next();
+        let req = BusMsg::Rpc(RpcEnvelope {
+            request_id: self.last_request_id,
+            auth: self.auth.clone(),
+            msg: req.into(),
+        });
+        debug!("Executing {}", req);
+        if let Err(err) = self.esb().send_to(Bus::Rpc, service_id.clone(), req.clone()) {
+            warn!("Failed to send request ({}), reconnecting", err);
+            self.reconnect()?;
+            self.esb().send_to(Bus::Rpc, service_id, req)?;
+        }
+        Ok(self.last_request_id)
+    }
+
+    /// Sends request and waits for its single reply.
+    fn request(&mut self, req: impl Into<RpcMsg>, service_id: ServiceId) -> Result<RpcMsg, Error> {
+        let request_id = self.start_request(req, service_id)?;
+        self.next_reply(request_id)
+    }
+
+    /// Receives next message matching the filter, waiting no longer than the idle timeout and the
+    /// `deadline`. Messages not matching the filter are queued.
+    fn receive(
+        &mut self,
+        deadline: Option<Instant>,
+        started: Instant,
+        filter: impl Fn(&PollItem<Bus, BusMsg>) -> bool,
+    ) -> Result<PollItem<Bus, BusMsg>, Error> {
+        let idle_deadline = self.timeouts.idle.map(|timeout| Instant::now() + timeout);
+        let deadline = match (deadline, idle_deadline) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        };
+        self.watchdog.arm(deadline);
         loop {
-            if let Some(poll) = self.response_queue.pop() {
+            if let Some(pos) = self.response_queue.iter().position(&filter) {
+                let poll = self.response_queue.remove(pos).expect("position is within the queue");
                 trace!("Got response {} from {} via {}", poll.request, poll.source, poll.bus_id);
                 return Ok(poll);
-            } else {
-                for poll in self.esb.recv_poll()? {
-                    self.response_queue.push(poll);
+            }
+            for poll in self.esb().recv_poll()? {
+                match &poll.request {
+                    _ if poll.bus_id == Bus::Timer => {
+                        // Wake-ups from the previous requests are ignored
+                        if matches!(deadline, Some(deadline) if Instant::now() >= deadline) {
+                            return Err(Error::Timeout(started.elapsed()));
+                        }
+                    }
+                    BusMsg::Rpc(envelope) if !self.pending.contains_key(&envelope.request_id) => {
+                        trace!("Dropping reply {} to a request which is not awaited", envelope);
+                    }
+                    _ => self.response_queue.push_back(poll),
                 }
             }
         }
@@ -110,61 +273,215 @@ impl Client {
         &mut self,
         request: impl Into<RpcMsg>,
         service_id: ServiceId,
-        progress: impl Fn(String),
+        progress: impl Fn(ProgressEvent),
     ) -> Result<(), Error> {
-        self.request(request, service_id)?;
+        let request_id = self.start_request(request, service_id)?;
         loop {
-            match self.response()?.request {
-                BusMsg::Rpc(rpc) => match rpc.failure_to_error()? {
-                    RpcMsg::Success(OptionDetails(Some(info))) => {
-                        return {
-                            progress(format!("Success. {}", info));
-                            Ok(())
-                        }
+            match self.next_reply(request_id) {
+                Ok(RpcMsg::Success(details)) => {
+                    progress(ProgressEvent::Completed(details));
+                    return Ok(());
+                }
+                Ok(RpcMsg::Progress(event)) => progress(event),
+                Ok(_) => return Err(Error::UnexpectedServerResponse),
+                Err(err) => {
+                    if let Some(code) = err.failure_code() {
+                        progress(ProgressEvent::Failed(code));
                     }
-                    RpcMsg::Success(OptionDetails(None)) => {
-                        return {
-                            progress(s!("Success"));
-                            Ok(())
+                    return Err(err);
+                }
+            }
+        }
+    }
+}
+
+fn timer_endpoint(client_id: ClientId, connection: u32) -> ServiceAddr {
+    ServiceAddr::Inproc(format!("storm-client-timer-{}-{}", client_id, connection))
+}
+
+fn connect(
+    client_id: ClientId,
+    rpc_endpoint: &ServiceAddr,
+    chat_endpoint: &ServiceAddr,
+    chat_topic: Option<String>,
+    timer_endpoint: &ServiceAddr,
+) -> Result<Controller, Error> {
+    let esb = esb::Controller::with(
+        map! {
+            Bus::Rpc => esb::BusConfig::with_addr(
+                rpc_endpoint.clone(),
+                ZmqSocketType::RouterConnect,
+                Some(ServiceId::stormd()),
+            ),
+            Bus::Chat => esb::BusConfig::with_subscription(
+                chat_endpoint.clone(),
+                ZmqSocketType::Sub,
+                chat_topic,
+            ),
+            Bus::Timer => esb::BusConfig::with_addr(
+                timer_endpoint.clone(),
+                ZmqSocketType::RouterBind,
+                None,
+            )
+        },
+        Handler {
+            identity: ServiceId::Client(client_id),
+        },
+    )?;
+
+    // We have to sleep in order for ZMQ to bootstrap
+    sleep(Duration::from_secs_f32(0.1));
+
+    Ok(esb)
+}
+
+/// Thread waking the client up from waiting on ZMQ sockets once a deadline expires.
+///
+/// The wake-up is sent as a message to the client timer bus over an in-process socket, since
+/// ESB controller does not support polling with a timeout.
+struct Watchdog {
+    endpoint: ServiceAddr,
+    alarms: mpsc::Sender<(ServiceAddr, Option<Instant>)>,
+}
+
+impl Watchdog {
+    fn with(client_id: ClientId, endpoint: ServiceAddr) -> Self {
+        let (alarms, receiver) = mpsc::channel::<(ServiceAddr, Option<Instant>)>();
+        thread::spawn(move || {
+            let identity: Vec<u8> = ServiceId::Client(client_id).into();
+            let wakeup = BusMsg::Rpc(RpcEnvelope {
+                request_id: default!(),
+                auth: None,
+                msg: RpcMsg::Progress(ProgressEvent::Notice(s!("timeout"))),
+            })
+            .serialize();
+            let mut session: Option<(ServiceAddr, LocalSession)> = None;
+            let mut alarm: Option<(ServiceAddr, Instant)> = None;
+            loop {
+                let next = match alarm {
+                    None => receiver.recv().map_err(|_| RecvTimeoutError::Disconnected),
+                    Some((_, deadline)) => {
+                        receiver.recv_timeout(deadline.saturating_duration_since(Instant::now()))
+                    }
+                };
+                match next {
+                    Ok((endpoint, deadline)) => {
+                        alarm = deadline.map(|deadline| (endpoint, deadline))
+                    }
+                    Err(RecvTimeoutError::Timeout) => {
+                        let (endpoint, _) = alarm.take().expect("timeout happens only when armed");
+                        if !matches!(&session, Some((current, _)) if *current == endpoint) {
+                            session = LocalSession::connect(
+                                ZmqSocketType::RouterConnect,
+                                &endpoint,
+                                None,
+                                Some(&identity),
+                                &ZMQ_CONTEXT,
+                            )
+                            .map_err(|err| error!("Unable to set up RPC client timer: {}", err))
+                            .ok()
+                            .map(|session| (endpoint, session));
+                            // Wait for the connection to be established
+                            sleep(Duration::from_millis(10));
+                        }
+                        if let Some((_, session)) = &mut session {
+                            if let Err(err) = session
+                                .send_routed_message(&identity, &identity, &identity, &wakeup)
+                            {
+                                warn!("Unable to wake up RPC client: {}", err);
+                            }
                         }
                     }
-                    RpcMsg::Progress(info) => progress(info),
-                    _ => return Err(Error::UnexpectedServerResponse),
-                },
-                _ => return Err(Error::UnexpectedServerResponse),
+                    Err(RecvTimeoutError::Disconnected) => break,
+                }
             }
-        }
+        });
+        Watchdog { endpoint, alarms }
+    }
+
+    fn arm(&self, deadline: Option<Instant>) {
+        let _ = self.alarms.send((self.endpoint.clone(), deadline));
     }
 }
 
 impl Client {
     pub fn chat_tell(&mut self, remote_id: NodeId, text: String) -> Result<(), Error> {
-        self.request(
-            RpcMsg::SendChat(AddressedMsg {
-                remote_id,
-                data: text,
-            }),
-            ServiceId::chatd(),
-        )
+        let msg = RpcMsg::SendChat(AddressedMsg {
+            remote_id,
+            data: text,
+        });
+        match self.request(msg, ServiceId::chatd())? {
+            RpcMsg::Success(_) => Ok(()),
+            _ => Err(Error::UnexpectedServerResponse),
+        }
     }
 
-    pub fn chat_recv(&mut self, from_remote_id: NodeId) -> Result<String, Error> {
-        let poll = self.response()?;
-        match poll.request {
-            BusMsg::Chat(RadioMsg::Received(AddressedMsg { remote_id, data }))
-                if remote_id == from_remote_id =>
-            {
-                Ok(data)
-            }
+    /// Returns chat messages sent to and received from the remote peer, oldest first. The chat
+    /// daemon keeps only a limited number of the latest messages per peer.
+    pub fn chat_history(&mut self, remote_id: NodeId) -> Result<Vec<ChatRecord>, Error> {
+        match self.request(RpcMsg::ChatHistory(remote_id), ServiceId::chatd())? {
+            RpcMsg::ChatRecords(records) => Ok(records),
             _ => Err(Error::UnexpectedServerResponse),
         }
     }
 
+    /// Limits chat messages received by the client to the ones coming from a specific remote peer
+    /// and, optionally, a specific room within the conversation with that peer.
+    ///
+    /// By default, clients receive chat messages from all peers.
+    pub fn chat_subscribe(&mut self, remote_id: NodeId, room: Option<MesgId>) -> Result<(), Error> {
+        let topic = match room {
+            None => ChatTopic::peer_subscription(remote_id),
+            Some(room) => ChatTopic::with(remote_id, Some(room)).subscription(),
+        };
+        self.chat_resubscribe(Some(topic))
+    }
+
+    /// Resets chat message filtering set up with [`Client::chat_subscribe`], such that the client
+    /// receives chat messages from all peers.
+    pub fn chat_subscribe_all(&mut self) -> Result<(), Error> { self.chat_resubscribe(None) }
+
+    fn chat_resubscribe(&mut self, topic: Option<String>) -> Result<(), Error> {
+        debug!("Subscribing to chat topic {:?}", topic);
+        self.chat_topic = topic.clone();
+        let chat_endpoint = self.chat_endpoint.clone();
+        self.esb().add_service_bus(
+            Bus::Chat,
+            esb::BusConfig::with_subscription(chat_endpoint, ZmqSocketType::Sub, topic),
+        )?;
+
+        // We have to sleep in order for ZMQ to bootstrap
+        sleep(Duration::from_secs_f32(0.1));
+
+        Ok(())
+    }
+
+    /// Waits for the next chat message from a remote peer. Fails with [`Error::Timeout`] if no
+    /// message is received within the idle timeout.
+    pub fn chat_recv(&mut self, from_remote_id: NodeId) -> Result<ChatMsg, Error> {
+        let started = Instant::now();
+        loop {
+            let poll =
+                self.receive(None, started, |poll| matches!(poll.request, BusMsg::Chat(_)))?;
+            match poll.request {
+                BusMsg::Chat(RadioMsg::Received(AddressedMsg { remote_id, data }))
+                    if remote_id == from_remote_id =>
+                {
+                    return Ok(data)
+                }
+                BusMsg::Chat(RadioMsg::Received(AddressedMsg { remote_id, .. })) => {
+                    trace!("Skipping chat message from {}", remote_id);
+                }
+                _ => return Err(Error::UnexpectedServerResponse),
+            }
+        }
+    }
+
     pub fn upload(
         &mut self,
         remote_id: NodeId,
         container_id: ContainerId,
-        progress: impl Fn(String),
+        progress: impl Fn(ProgressEvent),
     ) -> Result<(), Error> {
         let msg = AddressedMsg {
             remote_id,
@@ -189,7 +506,7 @@ impl Client {
         &mut self,
         remote_id: NodeId,
         container_id: ContainerId,
-        progress: impl Fn(String),
+        progress: impl Fn(ProgressEvent),
     ) -> Result<(), Error> {
         let msg = AddressedMsg {
             remote_id,
@@ -209,6 +526,169 @@ impl Client {
             progress,
         )
     }
+
+    /// Deletes container from the Store database. Container chunks are reclaimed by the next
+    /// garbage collection pass.
+    pub fn delete_container(
+        &mut self,
+        container_id: ContainerId,
+        progress: impl Fn(ProgressEvent),
+    ) -> Result<(), Error> {
+        self.progressive_request(
+            RpcMsg::DeleteContainer(container_id),
+            ServiceId::stormd(),
+            progress,
+        )
+    }
+
+    /// Pins container, protecting it from deletion, or unpins it.
+    pub fn pin_container(
+        &mut self,
+        container_id: ContainerId,
+        pin: bool,
+        progress: impl Fn(ProgressEvent),
+    ) -> Result<(), Error> {
+        let request = match pin {
+            true => RpcMsg::PinContainer(container_id),
+            false => RpcMsg::UnpinContainer(container_id),
+        };
+        self.progressive_request(request, ServiceId::stormd(), progress)
+    }
+
+    /// Runs garbage collection pass in the Store database.
+    pub fn collect_garbage(
+        &mut self,
+        dry_run: bool,
+        progress: impl Fn(ProgressEvent),
+    ) -> Result<GcReport, Error> {
+        let request_id =
+            self.start_request(RpcMsg::CollectGarbage(dry_run), ServiceId::stormd())?;
+        loop {
+            match self.next_reply(request_id)? {
+                RpcMsg::GcReport(report) => return Ok(report),
+                RpcMsg::Progress(event) => progress(event),
+                _ => return Err(Error::UnexpectedServerResponse),
+            }
+        }
+    }
+
+    /// Checks integrity of the containers and chunks kept by the node. If `repair_from` peer is
+    /// given, damaged containers are re-fetched from it in background.
+    pub fn fsck(
+        &mut self,
+        repair_from: Option<NodeId>,
+        progress: impl Fn(ProgressEvent),
+    ) -> Result<FsckReport, Error> {
+        let request_id = self.start_request(RpcMsg::Fsck(repair_from), ServiceId::stormd())?;
+        loop {
+            match self.next_reply(request_id)? {
+                RpcMsg::FsckReport(report) => return Ok(report),
+                RpcMsg::Progress(event) => progress(event),
+                _ => return Err(Error::UnexpectedServerResponse),
+            }
+        }
+    }
+
+    /// Lists containers kept by the node which match the filter.
+    pub fn containers(&mut self, filter: ContainerFilter) -> Result<Vec<ContainerSummary>, Error> {
+        match self.request(RpcMsg::ListContainers(filter), ServiceId::stormd())? {
+            RpcMsg::Containers(containers) => Ok(containers),
+            _ => Err(Error::UnexpectedServerResponse),
+        }
+    }
+
+    /// Returns information about a container kept by the node.
+    pub fn container_info(&mut self, container_id: ContainerId) -> Result<ContainerSummary, Error> {
+        match self.request(RpcMsg::ContainerInfo(container_id), ServiceId::stormd())? {
+            RpcMsg::ContainerSummary(summary) => Ok(summary),
+            _ => Err(Error::UnexpectedServerResponse),
+        }
+    }
+
+    /// Reports storage used by the containers received from remote peers and the quotas.
+    pub fn quotas(&mut self) -> Result<Vec<QuotaUsage>, Error> {
+        match self.request(RpcMsg::ListQuotas, ServiceId::stormd())? {
+            RpcMsg::Quotas(quotas) => Ok(quotas),
+            _ => Err(Error::UnexpectedServerResponse),
+        }
+    }
+
+    /// Connects to a remote peer over Bifrost protocol.
+    pub fn connect(&mut self, node_addr: NodeAddr) -> Result<(), Error> {
+        match self.request(RpcMsg::Connect(node_addr), ServiceId::stormd())? {
+            RpcMsg::Success(_) => Ok(()),
+            _ => Err(Error::UnexpectedServerResponse),
+        }
+    }
+
+    /// Disconnects from a remote peer.
+    pub fn disconnect(&mut self, remote_id: NodeId) -> Result<(), Error> {
+        match self.request(RpcMsg::Disconnect(remote_id), ServiceId::stormd())? {
+            RpcMsg::Success(_) => Ok(()),
+            _ => Err(Error::UnexpectedServerResponse),
+        }
+    }
+
+    /// Lists remote Bifrost peers connected to the node.
+    pub fn peers(&mut self) -> Result<Vec<PeerInfo>, Error> {
+        match self.request(RpcMsg::ListPeers, ServiceId::stormd())? {
+            RpcMsg::Peers(peers) => Ok(peers),
+            _ => Err(Error::UnexpectedServerResponse),
+        }
+    }
+
+    /// Lists container announcements which were put on hold by the downpour policy.
+    pub fn pending_containers(&mut self) -> Result<Vec<PendingContainer>, Error> {
+        match self.request(RpcMsg::ListPending, ServiceId::downpourd())? {
+            RpcMsg::PendingContainers(pending) => Ok(pending),
+            _ => Err(Error::UnexpectedServerResponse),
+        }
+    }
+
+    /// Announces locally present container to the downpour seed peers.
+    pub fn seed(
+        &mut self,
+        container_id: ContainerId,
+        progress: impl Fn(ProgressEvent),
+    ) -> Result<(), Error> {
+        self.progressive_request(
+            RpcMsg::SeedContainer(container_id),
+            ServiceId::downpourd(),
+            progress,
+        )
+    }
+
+    /// Lists containers from the downpour catalog matching the filter.
+    pub fn catalog(&mut self, filter: CatalogFilter) -> Result<Vec<CatalogEntry>, Error> {
+        match self.request(RpcMsg::ListCatalog(filter), ServiceId::downpourd())? {
+            RpcMsg::Catalog(entries) => Ok(entries),
+            _ => Err(Error::UnexpectedServerResponse),
+        }
+    }
+
+    pub fn accept_container(
+        &mut self,
+        container_id: ContainerId,
+        progress: impl Fn(ProgressEvent),
+    ) -> Result<(), Error> {
+        self.progressive_request(
+            RpcMsg::AcceptContainer(container_id),
+            ServiceId::downpourd(),
+            progress,
+        )
+    }
+
+    pub fn decline_container(
+        &mut self,
+        container_id: ContainerId,
+        progress: impl Fn(ProgressEvent),
+    ) -> Result<(), Error> {
+        self.progressive_request(
+            RpcMsg::DeclineContainer(container_id),
+            ServiceId::downpourd(),
+            progress,
+        )
+    }
 }
 
 pub struct Handler {
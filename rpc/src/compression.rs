@@ -0,0 +1,245 @@
+// Storm node providing distributed storage & messaging for lightning network.
+//
+// Written in 2022 by
+//     Dr. Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2022 by LNP/BP Standards Association, Switzerland.
+//
+// You should have received a copy of the MIT License along with this software.
+// If not, see <https://opensource.org/licenses/MIT>.
+
+//! Compression of chunk payloads.
+//!
+//! Compression applied to a container is recorded as a `compression` parameter of the container
+//! header MIME type, for instance `text/csv; compression=deflate`. Each chunk of a compressed
+//! container is compressed independently, and chunk ids are computed over the compressed form,
+//! so the compressed data are both kept in the Store database and sent over the network.
+//!
+//! Compressed chunk data are prefixed with a single byte: `1` for data compressed with zlib
+//! deflate, or `0` for data which do not compress and are kept as is.
+
+use std::fmt::{self, Display, Formatter};
+use std::str::FromStr;
+
+use inflate::InflateStream;
+use stens::AsciiString;
+use storm::ContainerHeader;
+
+use crate::container::CHUNK_SIZE;
+
+const MIME_PARAM: &str = "compression";
+
+const TAG_RAW: u8 = 0;
+const TAG_DEFLATE: u8 = 1;
+
+/// Compression applied to the container chunks
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug)]
+pub enum Compression {
+    /// Chunks keep the original data.
+    None,
+
+    /// Chunks are compressed with zlib deflate.
+    Deflate,
+}
+
+impl Default for Compression {
+    fn default() -> Self { Compression::None }
+}
+
+impl Compression {
+    /// Detects compression from the container header MIME type.
+    pub fn from_header(header: &ContainerHeader) -> Result<Compression, CompressionError> {
+        header
+            .mime
+            .as_str()
+            .split(';')
+            .skip(1)
+            .filter_map(|param| param.split_once('='))
+            .find(|(name, _)| name.trim() == MIME_PARAM)
+            .map(|(_, value)| value.trim().parse())
+            .unwrap_or(Ok(Compression::None))
+    }
+
+    /// Adds compression parameter to the MIME type. Types of uncompressed containers are returned
+    /// unchanged.
+    pub fn apply_to_mime(self, mime: AsciiString) -> AsciiString {
+        match self {
+            Compression::None => mime,
+            _ => AsciiString::from_str(&format!("{}; {}={}", mime, MIME_PARAM, self))
+                .expect("compression parameter is an ASCII string"),
+        }
+    }
+
+    /// Number of bytes which compressed chunk data may take in addition to the original data.
+    pub(crate) fn overhead(self) -> usize {
+        match self {
+            Compression::None => 0,
+            Compression::Deflate => 1,
+        }
+    }
+
+    /// Converts data into the form kept in the chunk.
+    pub(crate) fn compress(self, data: Vec<u8>) -> Vec<u8> {
+        match self {
+            Compression::None => data,
+            Compression::Deflate => {
+                let compressed = deflate::deflate_bytes_zlib(&data);
+                let (tag, payload) = if compressed.len() < data.len() {
+                    (TAG_DEFLATE, compressed)
+                } else {
+                    (TAG_RAW, data)
+                };
+                let mut chunk = Vec::with_capacity(payload.len() + 1);
+                chunk.push(tag);
+                chunk.extend(payload);
+                chunk
+            }
+        }
+    }
+
+    /// Restores original data from the form kept in the chunk.
+    pub(crate) fn decompress(self, chunk: &[u8]) -> Result<Vec<u8>, CompressionError> {
+        match self {
+            Compression::None => Ok(chunk.to_vec()),
+            Compression::Deflate => match chunk.split_first() {
+                Some((&TAG_RAW, data)) => Ok(data.to_vec()),
+                Some((&TAG_DEFLATE, data)) => inflate_limited(data, CHUNK_SIZE),
+                _ => Err(CompressionError::Corrupted(s!("unknown chunk data tag"))),
+            },
+        }
+    }
+}
+
+/// Decompresses zlib data, failing once the decompressed data exceed `limit` bytes, such that
+/// a malicious chunk can't exhaust memory.
+fn inflate_limited(data: &[u8], limit: usize) -> Result<Vec<u8>, CompressionError> {
+    let mut inflater = InflateStream::from_zlib();
+    let mut decompressed = Vec::new();
+    let mut pos = 0;
+    loop {
+        let (read, output) = inflater.update(&data[pos..]).map_err(CompressionError::Corrupted)?;
+        if output.is_empty() {
+            break;
+        }
+        if decompressed.len() + output.len() > limit {
+            return Err(CompressionError::Corrupted(format!(
+                "decompressed data exceed {} bytes",
+                limit
+            )));
+        }
+        pos += read;
+        decompressed.extend_from_slice(output);
+    }
+    Ok(decompressed)
+}
+
+impl Display for Compression {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Compression::None => f.write_str("none"),
+            Compression::Deflate => f.write_str("deflate"),
+        }
+    }
+}
+
+impl FromStr for Compression {
+    type Err = CompressionError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "none" => Ok(Compression::None),
+            "deflate" => Ok(Compression::Deflate),
+            _ => Err(CompressionError::Unknown(s.to_owned())),
+        }
+    }
+}
+
+/// Errors in chunk compression
+#[derive(Clone, Eq, PartialEq, Hash, Debug, Display, Error)]
+#[display(doc_comments)]
+pub enum CompressionError {
+    /// unknown compression `{0}`; use `none` or `deflate`
+    Unknown(String),
+
+    /// compressed chunk data are corrupted. Details: {0}
+    Corrupted(String),
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn roundtrip() {
+        let text = b"Storm node providing distributed storage & messaging. ".repeat(100);
+        let compressed = Compression::Deflate.compress(text.clone());
+        assert_eq!(compressed[0], TAG_DEFLATE);
+        assert!(compressed.len() < text.len());
+        assert_eq!(Compression::Deflate.decompress(&compressed).unwrap(), text);
+
+        // Incompressible data are kept as is
+        let data = vec![0x42u8];
+        let compressed = Compression::Deflate.compress(data.clone());
+        assert_eq!(compressed, vec![TAG_RAW, 0x42]);
+        assert_eq!(Compression::Deflate.decompress(&compressed).unwrap(), data);
+
+        assert_eq!(Compression::None.compress(text.clone()), text);
+        assert_eq!(Compression::None.decompress(&text).unwrap(), text);
+    }
+
+    #[test]
+    fn corrupted() {
+        let mut compressed = Compression::Deflate.compress(vec![0u8; 1000]);
+        assert!(matches!(
+            Compression::Deflate.decompress(&[2, 0]),
+            Err(CompressionError::Corrupted(_))
+        ));
+        assert!(matches!(
+            Compression::Deflate.decompress(&[]),
+            Err(CompressionError::Corrupted(_))
+        ));
+        let last = compressed.len() - 1;
+        compressed[last] ^= 0xFF;
+        assert!(matches!(
+            Compression::Deflate.decompress(&compressed),
+            Err(CompressionError::Corrupted(_))
+        ));
+    }
+
+    #[test]
+    fn oversized() {
+        let data = vec![0u8; CHUNK_SIZE];
+        let compressed = Compression::Deflate.compress(data.clone());
+        assert_eq!(Compression::Deflate.decompress(&compressed).unwrap(), data);
+
+        // Highly compressible data decompressing into more than a chunk are rejected
+        let mut bomb = vec![TAG_DEFLATE];
+        bomb.extend(deflate::deflate_bytes_zlib(&vec![0u8; CHUNK_SIZE + 1]));
+        assert!(bomb.len() < CHUNK_SIZE / 100);
+        assert!(matches!(
+            Compression::Deflate.decompress(&bomb),
+            Err(CompressionError::Corrupted(_))
+        ));
+        assert!(matches!(inflate_limited(&bomb[1..], 1000), Err(CompressionError::Corrupted(_))));
+    }
+
+    #[test]
+    fn mime() {
+        let header = |mime: &str| ContainerHeader {
+            version: 0,
+            mime: AsciiString::from_str(mime).unwrap(),
+            info: s!(""),
+            size: 0,
+        };
+        let mime = AsciiString::from_str("text/csv").unwrap();
+        assert_eq!(Compression::None.apply_to_mime(mime.clone()), mime);
+        let mime = Compression::Deflate.apply_to_mime(mime);
+        assert_eq!(mime.as_str(), "text/csv; compression=deflate");
+        assert_eq!(Compression::from_header(&header(mime.as_str())), Ok(Compression::Deflate));
+        assert_eq!(Compression::from_header(&header("text/csv")), Ok(Compression::None));
+        assert_eq!(
+            Compression::from_header(&header("text/csv; compression=lz4")),
+            Err(CompressionError::Unknown(s!("lz4")))
+        );
+    }
+}
@@ -0,0 +1,444 @@
+// Storm node providing distributed storage & messaging for lightning network.
+//
+// Written in 2022 by
+//     Dr. Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2022 by LNP/BP Standards Association, Switzerland.
+//
+// You should have received a copy of the MIT License along with this software.
+// If not, see <https://opensource.org/licenses/MIT>.
+
+//! Conversion of arbitrary data into containers kept in the Store database and assembling
+//! containers back into data.
+
+use std::collections::BTreeSet;
+use std::fs;
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+
+use amplify::IoError;
+use internet2::addr::{LocalNode, NodeId};
+use microservices::rpc::ServerError;
+use stens::AsciiString;
+use storm::{Chunk, ChunkId, Container, ContainerHeader, ContainerId};
+use strict_encoding::{MediumVec, StrictDecode, StrictEncode};
+
+use crate::chunking::{Chunker, Chunking};
+use crate::compression::{Compression, CompressionError};
+use crate::directory::is_directory;
+use crate::encryption::{ContentKey, EncryptionError, KeyEnvelope};
+use crate::gc::{missing_chunks, retrieve_live};
+use crate::{inventory, DB_TABLE_CHUNKS, DB_TABLE_CONTAINERS, DB_TABLE_CONTAINER_HEADERS};
+
+/// Maximal size of a single chunk data (`u24::MAX`)
+pub const CHUNK_SIZE: usize = (1 << 24) - 1;
+
+/// Errors happening during containerization
+#[derive(Debug, Display, Error, From)]
+#[display(doc_comments)]
+pub enum ContainerizeError {
+    /// unable to read data for containerization. Details: {0}
+    #[from]
+    #[from(io::Error)]
+    Io(IoError),
+
+    /// error storing container in the Store database. Details: {0}
+    #[from]
+    Store(ServerError<store_rpc::FailureCode>),
+
+    /// data can't be split into chunks. Details: {0}
+    #[from]
+    Encoding(strict_encoding::Error),
+
+    /// path `{0}` can't be represented in the directory manifest
+    InvalidPath(String),
+}
+
+/// Errors happening during assembling of containers
+#[derive(Debug, Display, Error, From)]
+#[display(doc_comments)]
+pub enum AssembleError {
+    /// container {0} is not known to the Store database
+    UnknownContainer(ContainerId),
+
+    /// container {container_id} can't be assembled since the following chunks are absent from
+    /// the Store database: {chunk_ids:?}
+    MissingChunks {
+        container_id: ContainerId,
+        chunk_ids: Vec<ChunkId>,
+    },
+
+    /// chunk {0} is absent from the Store database
+    MissingChunk(ChunkId),
+
+    /// data in the Store database for chunk {0} do not match the chunk id
+    CorruptedChunk(ChunkId),
+
+    /// container {0} holds a directory and can't be assembled into a single file
+    DirectoryContainer(ContainerId),
+
+    /// container {0} does not hold a directory
+    NotDirectoryContainer(ContainerId),
+
+    /// directory manifest contains invalid path `{0}`
+    InvalidPath(String),
+
+    /// directory manifest references chunks outside of the container
+    InvalidChunkRange,
+
+    /// assembled data size of {actual} bytes does not match size of {expected} bytes specified in
+    /// the container header
+    SizeMismatch { expected: u64, actual: u64 },
+
+    /// unable to write assembled data. Details: {0}
+    #[from]
+    #[from(io::Error)]
+    Io(IoError),
+
+    /// error retrieving container from the Store database. Details: {0}
+    #[from]
+    Store(ServerError<store_rpc::FailureCode>),
+
+    /// container data in the Store database are corrupted. Details: {0}
+    #[from]
+    Encoding(strict_encoding::Error),
+
+    /// unable to decompress container data. Details: {0}
+    #[from]
+    Compression(CompressionError),
+
+    /// unable to decrypt container data. Details: {0}
+    #[from]
+    Encryption(EncryptionError),
+}
+
+/// Returns MIME type without its parameters, such as the ones describing chunking, compression
+/// and encryption of the container data.
+pub fn mime_base(mime: &str) -> &str { mime.split(';').next().unwrap_or_default().trim() }
+
+/// Checks whether container has a given MIME type, comparing only the types without their
+/// parameters.
+pub fn has_mime(header: &ContainerHeader, mime: &str) -> bool {
+    mime_base(header.mime.as_str()).eq_ignore_ascii_case(mime_base(mime))
+}
+
+/// Parameters defining how container data are split into chunks and transformed before being
+/// saved to the Store database
+#[derive(Clone, Eq, PartialEq, Hash, Debug, Default)]
+pub struct ContainerizeOptions {
+    /// Strategy for splitting data into chunks.
+    pub chunking: Chunking,
+
+    /// Compression of the chunk data.
+    pub compression: Compression,
+
+    /// Nodes able to decrypt the container. The container is not encrypted if the list is empty.
+    pub encrypt_to: Vec<NodeId>,
+}
+
+impl ContainerizeOptions {
+    /// Adds parameters of the chunking, compression and encryption to the MIME type.
+    pub fn apply_to_mime(&self, mime: AsciiString) -> AsciiString {
+        let mime = self.compression.apply_to_mime(self.chunking.apply_to_mime(mime));
+        if self.encrypt_to.is_empty() {
+            mime
+        } else {
+            ContentKey::apply_to_mime(mime)
+        }
+    }
+
+    /// Constructs codec for the container chunks, generating a new content key for encrypted
+    /// containers.
+    pub(crate) fn codec(&self) -> ChunkCodec {
+        ChunkCodec {
+            compression: self.compression,
+            key: (!self.encrypt_to.is_empty()).then(ContentKey::random),
+        }
+    }
+}
+
+/// Transformations applied to the data of container chunks
+#[derive(Copy, Clone)]
+pub(crate) struct ChunkCodec {
+    pub compression: Compression,
+    pub key: Option<ContentKey>,
+}
+
+impl ChunkCodec {
+    /// Number of bytes which chunk data may take in addition to the original data.
+    fn overhead(self) -> usize {
+        self.compression.overhead() + self.key.map(|_| ContentKey::OVERHEAD).unwrap_or_default()
+    }
+
+    /// Converts data into the form kept in the chunk.
+    pub fn encode(self, data: Vec<u8>) -> Vec<u8> {
+        let data = self.compression.compress(data);
+        match self.key {
+            Some(key) => key.encrypt(data),
+            None => data,
+        }
+    }
+
+    /// Restores original data from the form kept in the chunk.
+    pub fn decode(self, chunk: &[u8]) -> Result<Vec<u8>, AssembleError> {
+        let data = match self.key {
+            Some(key) => key.decrypt(chunk)?,
+            None => chunk.to_vec(),
+        };
+        Ok(self.compression.decompress(&data)?)
+    }
+}
+
+/// Container loaded from the Store database with all of its chunks present
+pub(crate) struct OpenContainer {
+    pub header: ContainerHeader,
+
+    /// Chunks with the container data, not including the chunk with the key envelope.
+    pub chunks: Vec<ChunkId>,
+
+    pub codec: ChunkCodec,
+}
+
+/// Containerizes data from a file, reading it from the disk chunk by chunk. See
+/// [`containerize`] for the details.
+pub fn containerize_file(
+    store: &mut store_rpc::Client,
+    path: impl AsRef<Path>,
+    mime: AsciiString,
+    info: String,
+    options: &ContainerizeOptions,
+    progress: impl Fn(String),
+) -> Result<ContainerId, ContainerizeError> {
+    let file = fs::File::open(path)?;
+    containerize(store, file, mime, info, options, progress)
+}
+
+/// Splits data coming from the `reader` into chunks according to the chunking strategy from the
+/// `options`, saving each chunk to the Store database as soon as it is read, and then saves
+/// container and container header referencing these chunks. Each chunk is compressed and, if
+/// recipients are given, encrypted before being saved; the content key wrapped for the
+/// recipients is saved as the last container chunk. Parameters of the chunking, compression and
+/// encryption are recorded in the header MIME type.
+///
+/// Only a single chunk is kept in memory at a time, so the data may be of arbitrary size.
+pub fn containerize(
+    store: &mut store_rpc::Client,
+    reader: impl Read,
+    mime: AsciiString,
+    info: String,
+    options: &ContainerizeOptions,
+    progress: impl Fn(String),
+) -> Result<ContainerId, ContainerizeError> {
+    let codec = options.codec();
+    let mut chunk_ids = vec![];
+    let size = store_chunks(store, reader, options.chunking, codec, &mut chunk_ids, &progress)?;
+    store_key_envelope(store, codec, &options.encrypt_to, &mut chunk_ids)?;
+    let header = ContainerHeader {
+        version: 0,
+        mime: options.apply_to_mime(mime),
+        info,
+        size,
+    };
+    let total_chunks = chunk_ids.len();
+    let id = store_container(store, header, chunk_ids)?;
+    progress(format!("Containerized ({} chunks in total)", total_chunks));
+    Ok(id)
+}
+
+/// Reads data from the `reader` chunk by chunk, saving each encoded chunk to the Store database
+/// and adding its id to `chunk_ids`. Returns size of the original data.
+pub(crate) fn store_chunks(
+    store: &mut store_rpc::Client,
+    reader: impl Read,
+    chunking: Chunking,
+    codec: ChunkCodec,
+    chunk_ids: &mut Vec<ChunkId>,
+    progress: &impl Fn(String),
+) -> Result<u64, ContainerizeError> {
+    let mut size = 0u64;
+    let mut chunker = Chunker::with(reader, chunking, CHUNK_SIZE - codec.overhead());
+    while let Some(data) = chunker.next_chunk()? {
+        size += data.len() as u64;
+        let chunk = Chunk::try_from(codec.encode(data))?;
+        let chunk_id = chunk.chunk_id();
+        store.store(DB_TABLE_CHUNKS, chunk_id, &chunk)?;
+        chunk_ids.push(chunk_id);
+        progress(format!("Stored chunk {} ({} bytes processed)", chunk_id, size));
+    }
+    Ok(size)
+}
+
+/// Saves content key wrapped for the recipients as a chunk, adding its id to the end of
+/// `chunk_ids`. Does nothing for containers which are not encrypted.
+pub(crate) fn store_key_envelope(
+    store: &mut store_rpc::Client,
+    codec: ChunkCodec,
+    recipients: &[NodeId],
+    chunk_ids: &mut Vec<ChunkId>,
+) -> Result<(), ContainerizeError> {
+    if let Some(key) = codec.key {
+        let chunk = Chunk::try_from(key.seal(recipients).strict_serialize()?)?;
+        let chunk_id = chunk.chunk_id();
+        store.store(DB_TABLE_CHUNKS, chunk_id, &chunk)?;
+        chunk_ids.push(chunk_id);
+    }
+    Ok(())
+}
+
+/// Saves container and its header to the Store database.
+pub(crate) fn store_container(
+    store: &mut store_rpc::Client,
+    header: ContainerHeader,
+    chunk_ids: Vec<ChunkId>,
+) -> Result<ContainerId, ContainerizeError> {
+    let header_chunk = Chunk::try_from(header.strict_serialize()?)?;
+    let container = Container {
+        header,
+        chunks: MediumVec::try_from(chunk_ids)?,
+    };
+    let container_chunk = Chunk::try_from(container.strict_serialize()?)?;
+
+    let id = container.container_id();
+    store.store(DB_TABLE_CONTAINER_HEADERS, id, &header_chunk)?;
+    store.store(DB_TABLE_CONTAINERS, id, &container_chunk)?;
+    inventory::record_origin::<ContainerizeError>(store, id, None, None)?;
+    Ok(id)
+}
+
+/// Assembles container into a file. The data are written into a temporary file in the same
+/// directory, which is renamed into `path` only once the whole container is assembled and
+/// verified; thus no partial files are left on failures. See [`assemble`] for the details.
+pub fn assemble_file(
+    store: &mut store_rpc::Client,
+    container_id: ContainerId,
+    node: Option<&LocalNode>,
+    path: impl AsRef<Path>,
+    progress: impl Fn(String),
+) -> Result<ContainerHeader, AssembleError> {
+    let path = path.as_ref();
+    let tmp_path = tmp_path(path);
+    let mut file = fs::File::create(&tmp_path)?;
+    let header = assemble(store, container_id, node, &mut file, progress)
+        .and_then(|header| {
+            file.sync_all()?;
+            Ok(header)
+        })
+        .map_err(|err| {
+            let _ = fs::remove_file(&tmp_path);
+            err
+        })?;
+    fs::rename(&tmp_path, path)?;
+    Ok(header)
+}
+
+/// Assembles container data, writing them into the `writer`, and returns the container header.
+///
+/// Before writing any data the function checks that all container chunks are present in the
+/// Store database, returning [`AssembleError::MissingChunks`] otherwise. Each chunk is verified
+/// against its id, decrypted and decompressed, and the total size of the assembled data is
+/// verified against the container header. Encrypted containers require key of the local `node`,
+/// which must be one of the container recipients.
+pub fn assemble(
+    store: &mut store_rpc::Client,
+    container_id: ContainerId,
+    node: Option<&LocalNode>,
+    mut writer: impl Write,
+    progress: impl Fn(String),
+) -> Result<ContainerHeader, AssembleError> {
+    let container = open_container(store, container_id, node)?;
+    if is_directory(&container.header) {
+        return Err(AssembleError::DirectoryContainer(container_id));
+    }
+
+    let size = write_chunks(store, &container.chunks, container.codec, &mut writer, &progress)?;
+    writer.flush()?;
+
+    if size != container.header.size {
+        return Err(AssembleError::SizeMismatch {
+            expected: container.header.size,
+            actual: size,
+        });
+    }
+
+    Ok(container.header)
+}
+
+/// Retrieves container from the Store database, checking that all of its chunks are present, and
+/// prepares codec for its chunks. Content key of encrypted containers is unwrapped with the key of
+/// the local `node`.
+pub(crate) fn open_container(
+    store: &mut store_rpc::Client,
+    container_id: ContainerId,
+    node: Option<&LocalNode>,
+) -> Result<OpenContainer, AssembleError> {
+    let container_chunk = retrieve_live(store, DB_TABLE_CONTAINERS, container_id)?
+        .ok_or(AssembleError::UnknownContainer(container_id))?;
+    let container = Container::strict_deserialize(container_chunk)?;
+
+    let chunk_ids = container.chunks.iter().copied().collect::<BTreeSet<_>>();
+    let missing = missing_chunks(store, chunk_ids)?;
+    if !missing.is_empty() {
+        return Err(AssembleError::MissingChunks {
+            container_id,
+            chunk_ids: missing.into_iter().collect(),
+        });
+    }
+
+    let mut chunks = container.chunks.to_vec();
+    let key = if ContentKey::required(&container.header)? {
+        let node = node.ok_or(EncryptionError::KeyRequired)?;
+        let envelope_id = chunks.pop().ok_or(EncryptionError::Corrupted)?;
+        let envelope = KeyEnvelope::strict_deserialize(retrieve_chunk(store, envelope_id)?)?;
+        Some(ContentKey::open(&envelope, node)?)
+    } else {
+        None
+    };
+    let codec = ChunkCodec {
+        compression: Compression::from_header(&container.header)?,
+        key,
+    };
+
+    Ok(OpenContainer {
+        header: container.header,
+        chunks,
+        codec,
+    })
+}
+
+/// Retrieves chunk from the Store database and verifies it against its id.
+pub(crate) fn retrieve_chunk(
+    store: &mut store_rpc::Client,
+    chunk_id: ChunkId,
+) -> Result<Chunk, AssembleError> {
+    let chunk = retrieve_live(store, DB_TABLE_CHUNKS, chunk_id)?
+        .ok_or(AssembleError::MissingChunk(chunk_id))?;
+    if chunk.chunk_id() != chunk_id {
+        return Err(AssembleError::CorruptedChunk(chunk_id));
+    }
+    Ok(chunk)
+}
+
+/// Writes decoded data of the chunks into the `writer`, returning total size of the written data.
+pub(crate) fn write_chunks(
+    store: &mut store_rpc::Client,
+    chunk_ids: &[ChunkId],
+    codec: ChunkCodec,
+    mut writer: impl Write,
+    progress: &impl Fn(String),
+) -> Result<u64, AssembleError> {
+    let mut size = 0u64;
+    for chunk_id in chunk_ids {
+        let chunk = retrieve_chunk(store, *chunk_id)?;
+        let data = codec.decode(chunk.as_slice())?;
+        writer.write_all(&data)?;
+        size += data.len() as u64;
+        progress(format!("Assembled chunk {} ({} bytes processed)", chunk_id, size));
+    }
+    Ok(size)
+}
+
+/// Constructs path for a hidden temporary file placed next to the `path`.
+pub(crate) fn tmp_path(path: &Path) -> PathBuf {
+    let file_name = path.file_name().map(|name| name.to_string_lossy()).unwrap_or_default();
+    path.with_file_name(format!(".{}.storm-tmp", file_name))
+}
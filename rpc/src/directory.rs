@@ -0,0 +1,431 @@
+// Storm node providing distributed storage & messaging for lightning network.
+//
+// Written in 2022 by
+//     Dr. Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2022 by LNP/BP Standards Association, Switzerland.
+//
+// You should have received a copy of the MIT License along with this software.
+// If not, see <https://opensource.org/licenses/MIT>.
+
+//! Containers holding whole directory trees.
+//!
+//! The first chunk of a directory container contains strict-encoded [`Manifest`] describing all
+//! directories and files of the tree. The rest of the chunks contain file data; each file refers
+//! to a range of these chunks. Files with identical content share the same chunk range.
+
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use std::str::FromStr;
+
+use internet2::addr::LocalNode;
+use stens::AsciiString;
+use storm::{Chunk, ChunkId, ContainerHeader, ContainerId};
+use strict_encoding::{MediumVec, StrictDecode, StrictEncode};
+
+use crate::container::{
+    has_mime, open_container, retrieve_chunk, store_chunks, store_container, store_key_envelope,
+    tmp_path, write_chunks, ChunkCodec,
+};
+use crate::{AssembleError, Chunking, ContainerizeError, ContainerizeOptions, DB_TABLE_CHUNKS};
+
+/// MIME type of containers holding directory trees
+pub const DIRECTORY_MIME: &str = "application/x-storm-directory";
+
+/// Checks whether container holds a directory tree, ignoring MIME type parameters.
+pub fn is_directory(header: &ContainerHeader) -> bool { has_mime(header, DIRECTORY_MIME) }
+
+/// Permission bits of the tree entries which are kept in the manifest and applied on assembly
+const PERMISSION_BITS: u32 = 0o777;
+
+/// Kind of directory tree entry
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, Display)]
+#[derive(StrictEncode, StrictDecode)]
+#[strict_encoding(by_value, repr = u8)]
+#[repr(u8)]
+pub enum EntryKind {
+    #[display("dir")]
+    Directory = 0,
+
+    #[display("file")]
+    File = 1,
+}
+
+/// Entry of a directory container manifest
+#[derive(Clone, Eq, PartialEq, Hash, Debug, Display)]
+#[derive(StrictEncode, StrictDecode)]
+#[display("{kind} {path}")]
+pub struct ManifestEntry {
+    /// Path relative to the root of the tree, using `/` as a separator.
+    pub path: String,
+
+    pub kind: EntryKind,
+
+    /// Unix permission bits. Setuid, setgid and sticky bits are not kept: they are dropped both on
+    /// containerization and on assembly.
+    pub mode: u32,
+
+    /// File size; always zero for directories.
+    pub size: u64,
+
+    /// Index of the first container chunk with the file data.
+    pub first_chunk: u32,
+
+    /// Number of container chunks with the file data.
+    pub chunk_count: u32,
+}
+
+/// Manifest of a directory container listing entries of the directory tree. Parent directories
+/// always precede their content.
+#[derive(Clone, Eq, PartialEq, Hash, Debug, Default)]
+#[derive(StrictEncode, StrictDecode)]
+pub struct Manifest {
+    pub entries: MediumVec<ManifestEntry>,
+}
+
+/// Directory tree being containerized
+#[derive(Default)]
+struct Tree {
+    manifest: Manifest,
+    data_chunks: Vec<ChunkId>,
+}
+
+/// Containerizes directory tree, saving all file chunks to the Store database. Symbolic links and
+/// special files are skipped. The manifest and file chunks are transformed according to the
+/// `options` in the same way as the chunks of file containers.
+pub fn containerize_dir(
+    store: &mut store_rpc::Client,
+    path: impl AsRef<Path>,
+    info: String,
+    options: &ContainerizeOptions,
+    progress: impl Fn(String),
+) -> Result<ContainerId, ContainerizeError> {
+    let codec = options.codec();
+    let mut tree = Tree::default();
+    let size = walk_dir(store, path.as_ref(), "", options.chunking, codec, &mut tree, &progress)?;
+    let Tree {
+        manifest,
+        data_chunks,
+    } = tree;
+
+    let manifest_chunk = Chunk::try_from(codec.encode(manifest.strict_serialize()?))?;
+    let manifest_id = manifest_chunk.chunk_id();
+    store.store(DB_TABLE_CHUNKS, manifest_id, &manifest_chunk)?;
+    let mut chunk_ids = vec![manifest_id];
+    chunk_ids.extend(data_chunks);
+    store_key_envelope(store, codec, &options.encrypt_to, &mut chunk_ids)?;
+
+    let mime = AsciiString::from_str(DIRECTORY_MIME).expect("constant ASCII string");
+    let header = ContainerHeader {
+        version: 0,
+        mime: options.apply_to_mime(mime),
+        info,
+        size,
+    };
+    let total_chunks = chunk_ids.len();
+    let id = store_container(store, header, chunk_ids)?;
+    progress(format!(
+        "Containerized {} entries ({} chunks in total)",
+        manifest.entries.len(),
+        total_chunks
+    ));
+    Ok(id)
+}
+
+fn walk_dir(
+    store: &mut store_rpc::Client,
+    dir: &Path,
+    prefix: &str,
+    chunking: Chunking,
+    codec: ChunkCodec,
+    tree: &mut Tree,
+    progress: &impl Fn(String),
+) -> Result<u64, ContainerizeError> {
+    let mut entries = fs::read_dir(dir)?.collect::<Result<Vec<_>, _>>()?;
+    entries.sort_by_key(|entry| entry.file_name());
+
+    let mut size = 0u64;
+    for entry in entries {
+        let name = entry.file_name();
+        let name = name
+            .to_str()
+            .ok_or_else(|| ContainerizeError::InvalidPath(entry.path().display().to_string()))?;
+        let path = format!("{}{}", prefix, name);
+        let file_type = entry.file_type()?;
+        let mode = mode(&entry.metadata()?);
+
+        if file_type.is_dir() {
+            tree.manifest.entries.push(ManifestEntry {
+                path: path.clone(),
+                kind: EntryKind::Directory,
+                mode,
+                size: 0,
+                first_chunk: 0,
+                chunk_count: 0,
+            })?;
+            size += walk_dir(
+                store,
+                &entry.path(),
+                &format!("{}/", path),
+                chunking,
+                codec,
+                tree,
+                progress,
+            )?;
+        } else if file_type.is_file() {
+            progress(format!("Containerizing {}", path));
+            let start = tree.data_chunks.len();
+            let file = fs::File::open(entry.path())?;
+            let file_size =
+                store_chunks(store, file, chunking, codec, &mut tree.data_chunks, progress)?;
+            let chunk_count = tree.data_chunks.len() - start;
+            // Container chunk with index 0 holds the manifest
+            let mut first_chunk = start as u32 + 1;
+            // Reusing chunk range of an identical file
+            let data_chunks = &tree.data_chunks;
+            let new_range = &data_chunks[start..];
+            if let Some(same) = tree.manifest.entries.iter().find(|other| {
+                if other.kind != EntryKind::File || other.size != file_size {
+                    return false;
+                }
+                let other_start = other.first_chunk as usize - 1;
+                data_chunks.get(other_start..other_start + other.chunk_count as usize)
+                    == Some(new_range)
+            }) {
+                progress(format!("{} is identical to {}", path, same.path));
+                first_chunk = same.first_chunk;
+                tree.data_chunks.truncate(start);
+            }
+            tree.manifest.entries.push(ManifestEntry {
+                path,
+                kind: EntryKind::File,
+                mode,
+                size: file_size,
+                first_chunk,
+                chunk_count: chunk_count as u32,
+            })?;
+            size += file_size;
+        } else {
+            progress(format!("Skipping {}: not a regular file or directory", path));
+        }
+    }
+
+    Ok(size)
+}
+
+/// Recreates directory tree from a directory container. The tree is assembled into a temporary
+/// directory, which is renamed into `path` only once all the files are assembled and verified.
+/// The `path` must not exist. Encrypted containers require key of the local `node`.
+pub fn assemble_dir(
+    store: &mut store_rpc::Client,
+    container_id: ContainerId,
+    node: Option<&LocalNode>,
+    path: impl AsRef<Path>,
+    progress: impl Fn(String),
+) -> Result<ContainerHeader, AssembleError> {
+    let container = open_container(store, container_id, node)?;
+    if !is_directory(&container.header) || container.chunks.is_empty() {
+        return Err(AssembleError::NotDirectoryContainer(container_id));
+    }
+    let codec = container.codec;
+    let manifest_chunk = retrieve_chunk(store, container.chunks[0])?;
+    let manifest = Manifest::strict_deserialize(codec.decode(manifest_chunk.as_slice())?)?;
+
+    let path = path.as_ref();
+    let tmp_path = tmp_path(path);
+    fs::create_dir(&tmp_path)?;
+    let size = assemble_entries(store, &container.chunks, codec, &manifest, &tmp_path, &progress)
+        .map_err(|err| {
+        let _ = fs::remove_dir_all(&tmp_path);
+        err
+    })?;
+    if size != container.header.size {
+        let _ = fs::remove_dir_all(&tmp_path);
+        return Err(AssembleError::SizeMismatch {
+            expected: container.header.size,
+            actual: size,
+        });
+    }
+    fs::rename(&tmp_path, path)?;
+
+    Ok(container.header)
+}
+
+fn assemble_entries(
+    store: &mut store_rpc::Client,
+    chunk_ids: &[ChunkId],
+    codec: ChunkCodec,
+    manifest: &Manifest,
+    root: &Path,
+    progress: &impl Fn(String),
+) -> Result<u64, AssembleError> {
+    let mut size = 0u64;
+    for entry in manifest.entries.iter() {
+        let path = root.join(entry_path(&entry.path)?);
+        match entry.kind {
+            EntryKind::Directory => fs::create_dir(&path)?,
+            EntryKind::File => {
+                progress(format!("Assembling {}", entry.path));
+                let first = entry.first_chunk as usize;
+                let range = chunk_ids
+                    .get(first..first + entry.chunk_count as usize)
+                    .filter(|_| first > 0)
+                    .ok_or(AssembleError::InvalidChunkRange)?;
+                let mut file = fs::File::create(&path)?;
+                let file_size = write_chunks(store, range, codec, &mut file, progress)?;
+                if file_size != entry.size {
+                    return Err(AssembleError::SizeMismatch {
+                        expected: entry.size,
+                        actual: file_size,
+                    });
+                }
+                file.sync_all()?;
+                set_mode(&path, entry.mode)?;
+                size += file_size;
+            }
+        }
+    }
+    // Directory permissions are applied once all files are written, such that read-only
+    // directories do not prevent writing their content
+    for entry in manifest.entries.iter().rev() {
+        if entry.kind == EntryKind::Directory {
+            set_mode(&root.join(entry_path(&entry.path)?), entry.mode)?;
+        }
+    }
+    Ok(size)
+}
+
+/// Converts manifest path into a relative file system path, rejecting paths which may point
+/// outside of the tree root.
+fn entry_path(path: &str) -> Result<PathBuf, AssembleError> {
+    let relative = PathBuf::from(path);
+    if path.is_empty()
+        || !relative.components().all(|component| matches!(component, Component::Normal(_)))
+    {
+        return Err(AssembleError::InvalidPath(path.to_owned()));
+    }
+    Ok(relative)
+}
+
+#[cfg(unix)]
+fn mode(metadata: &fs::Metadata) -> u32 {
+    use std::os::unix::fs::PermissionsExt;
+    metadata.permissions().mode() & PERMISSION_BITS
+}
+
+#[cfg(not(unix))]
+fn mode(metadata: &fs::Metadata) -> u32 {
+    match (metadata.is_dir(), metadata.permissions().readonly()) {
+        (true, _) => 0o755,
+        (false, true) => 0o444,
+        (false, false) => 0o644,
+    }
+}
+
+/// Applies permission bits from the manifest, which may come from a remote peer and thus is
+/// masked to exclude setuid, setgid and sticky bits.
+#[cfg(unix)]
+fn set_mode(path: &Path, mode: u32) -> std::io::Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    fs::set_permissions(path, fs::Permissions::from_mode(mode & PERMISSION_BITS))
+}
+
+#[cfg(not(unix))]
+fn set_mode(path: &Path, mode: u32) -> std::io::Result<()> {
+    let mut permissions = fs::metadata(path)?.permissions();
+    permissions.set_readonly(mode & 0o200 == 0);
+    fs::set_permissions(path, permissions)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_store;
+
+    #[test]
+    fn entry_paths() {
+        assert_eq!(entry_path("dir/file").unwrap(), PathBuf::from("dir/file"));
+        for path in ["", "..", "../file", "dir/../../file", "/etc/passwd", "./file"] {
+            assert!(
+                matches!(entry_path(path), Err(AssembleError::InvalidPath(p)) if p == path),
+                "path {:?} is accepted",
+                path
+            );
+        }
+    }
+
+    #[test]
+    fn roundtrip() {
+        let root = std::env::temp_dir().join(format!("storm-directory-{}", std::process::id()));
+        let src = root.join("src");
+        let dst = root.join("dst");
+        let _ = fs::remove_dir_all(&root);
+        // Directory goes first, such that deduplication of files compares them with it
+        fs::create_dir_all(src.join("a")).unwrap();
+        fs::write(src.join("a/unique.txt"), b"unique").unwrap();
+        fs::write(src.join("b.txt"), b"identical").unwrap();
+        fs::write(src.join("c.txt"), b"identical").unwrap();
+
+        let mut store = test_store::client();
+        let id = containerize_dir(&mut store, &src, s!("tree"), &default!(), |_| {}).unwrap();
+        let container = open_container(&mut store, id, None).unwrap();
+        // Manifest and a single chunk for each of the distinct files
+        assert_eq!(container.chunks.len(), 3);
+
+        let header = assemble_dir(&mut store, id, None, &dst, |_| {}).unwrap();
+        assert!(is_directory(&header));
+        assert_eq!(header.size, 24);
+        assert_eq!(fs::read(dst.join("a/unique.txt")).unwrap(), b"unique");
+        assert_eq!(fs::read(dst.join("b.txt")).unwrap(), b"identical");
+        assert_eq!(fs::read(dst.join("c.txt")).unwrap(), b"identical");
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn special_mode_bits() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let root = std::env::temp_dir().join(format!("storm-modes-{}", std::process::id()));
+        let src = root.join("src");
+        let dst = root.join("dst");
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(src.join("dir")).unwrap();
+        fs::write(src.join("dir/file"), b"data").unwrap();
+
+        let mut store = test_store::client();
+        let id = containerize_dir(&mut store, &src, s!(""), &default!(), |_| {}).unwrap();
+        let container = open_container(&mut store, id, None).unwrap();
+        let codec = container.codec;
+
+        // Manifest received from a malicious peer
+        let chunk = retrieve_chunk(&mut store, container.chunks[0]).unwrap();
+        let manifest =
+            Manifest::strict_deserialize(codec.decode(chunk.as_slice()).unwrap()).unwrap();
+        let mut tampered = Manifest::default();
+        for entry in manifest.entries.iter() {
+            let mode = match entry.kind {
+                EntryKind::Directory => 0o3755,
+                EntryKind::File => 0o4755,
+            };
+            tampered
+                .entries
+                .push(ManifestEntry {
+                    mode,
+                    ..entry.clone()
+                })
+                .unwrap();
+        }
+        let chunk = Chunk::try_from(codec.encode(tampered.strict_serialize().unwrap())).unwrap();
+        store.store(DB_TABLE_CHUNKS, chunk.chunk_id(), &chunk).unwrap();
+        let mut chunk_ids = container.chunks.clone();
+        chunk_ids[0] = chunk.chunk_id();
+        let id = store_container(&mut store, container.header, chunk_ids).map_err(|_| ()).unwrap();
+
+        assemble_dir(&mut store, id, None, &dst, |_| {}).unwrap();
+        let mode = |path: &str| fs::metadata(dst.join(path)).unwrap().permissions().mode() & 0o7777;
+        assert_eq!(mode("dir"), 0o755);
+        assert_eq!(mode("dir/file"), 0o755);
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,345 @@
+// Storm node providing distributed storage & messaging for lightning network.
+//
+// Written in 2022 by
+//     Dr. Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2022 by LNP/BP Standards Association, Switzerland.
+//
+// You should have received a copy of the MIT License along with this software.
+// If not, see <https://opensource.org/licenses/MIT>.
+
+//! Encryption of container data to a set of recipient nodes.
+//!
+//! Chunk data of an encrypted container are encrypted with ChaCha20-Poly1305 under a random
+//! content key, which is unique for the container. The content key is wrapped for each recipient
+//! with a key derived from the ECDH shared secret between an ephemeral key and the recipient node
+//! id. Wrapped keys are kept in a [`KeyEnvelope`], which is saved as the last container chunk.
+//!
+//! Encrypted containers have `encryption=chacha20poly1305` parameter of the container header MIME
+//! type. Encrypted chunk data start with 8-byte nonce followed by the ciphertext; if the container
+//! is also compressed, chunk data are compressed before the encryption.
+
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+use std::str::FromStr;
+
+use bitcoin_hashes::{sha256, Hash, HashEngine, Hmac, HmacEngine};
+use internet2::addr::{LocalNode, NodeId};
+use internet2::session::noise::chacha;
+use secp256k1::ecdh::SharedSecret;
+use secp256k1::{PublicKey, SecretKey};
+use stens::AsciiString;
+use storm::ContainerHeader;
+use strict_encoding::{StrictDecode, StrictEncode};
+
+const MIME_PARAM: &str = "encryption";
+const CIPHER: &str = "chacha20poly1305";
+
+/// Tag used in derivation of the key wrapping keys from ECDH shared secret.
+const WRAP_KEY_TAG: &[u8] = b"storm:container:key";
+
+/// Errors in container encryption
+#[derive(Clone, Eq, PartialEq, Hash, Debug, Display, Error)]
+#[display(doc_comments)]
+pub enum EncryptionError {
+    /// container is encrypted; a node key is required to decrypt it
+    KeyRequired,
+
+    /// container is not encrypted to node {0}
+    NotRecipient(NodeId),
+
+    /// unknown container encryption `{0}`
+    Unknown(String),
+
+    /// encrypted data are corrupted or were tampered with
+    Corrupted,
+
+    /// node key file {0} is invalid or can't be read
+    NodeKey(String),
+}
+
+/// Checks whether container data are encrypted.
+pub fn is_encrypted(header: &ContainerHeader) -> bool { encryption_param(header).is_some() }
+
+/// Reads node private key from a file, which must contain 32 bytes of the secret key.
+pub fn read_node_key(path: impl AsRef<Path>) -> Result<LocalNode, EncryptionError> {
+    let path = path.as_ref();
+    let key_err = || EncryptionError::NodeKey(path.display().to_string());
+    let data = fs::read(path).map_err(|_| key_err())?;
+    let private_key = SecretKey::from_slice(&data).map_err(|_| key_err())?;
+    Ok(LocalNode::with(secp256k1::SECP256K1, private_key))
+}
+
+/// Reads node private key from a file like [`read_node_key`]. If the file does not exist,
+/// generates a new key and saves it into the file readable only by the owner.
+pub fn read_or_create_node_key(path: impl AsRef<Path>) -> Result<LocalNode, EncryptionError> {
+    let path = path.as_ref();
+    if path.exists() {
+        return read_node_key(path);
+    }
+    let key_err = || EncryptionError::NodeKey(path.display().to_string());
+    let private_key = SecretKey::new(&mut rand::thread_rng());
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    options
+        .open(path)
+        .and_then(|mut file| file.write_all(&private_key.secret_bytes()))
+        .map_err(|_| key_err())?;
+    info!("New node key is saved to {}", path.display());
+    Ok(LocalNode::with(secp256k1::SECP256K1, private_key))
+}
+
+/// Derives symmetric key from the ECDH shared secret between the keys. The key is bound to the
+/// `tag` identifying its purpose and to the `context` data.
+pub fn shared_key(
+    tag: &[u8],
+    public_key: &PublicKey,
+    secret_key: &SecretKey,
+    context: &[&[u8]],
+) -> [u8; 32] {
+    let secret = SharedSecret::new(public_key, secret_key);
+    let mut engine = HmacEngine::<sha256::Hash>::new(tag);
+    engine.input(&secret.secret_bytes());
+    for data in context {
+        engine.input(data);
+    }
+    Hmac::from_engine(engine).into_inner()
+}
+
+fn encryption_param(header: &ContainerHeader) -> Option<&str> {
+    header
+        .mime
+        .as_str()
+        .split(';')
+        .skip(1)
+        .filter_map(|param| param.split_once('='))
+        .find(|(name, _)| name.trim() == MIME_PARAM)
+        .map(|(_, value)| value.trim())
+}
+
+/// Content key wrapped for a single recipient
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+#[derive(StrictEncode, StrictDecode)]
+pub struct WrappedKey {
+    pub recipient: NodeId,
+    pub ciphertext: Vec<u8>,
+}
+
+/// Content key of an encrypted container wrapped for all of its recipients
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+#[derive(StrictEncode, StrictDecode)]
+pub struct KeyEnvelope {
+    /// Ephemeral public key used in ECDH with the recipient node keys
+    pub ephemeral_key: PublicKey,
+    pub keys: Vec<WrappedKey>,
+}
+
+/// Random key encrypting data of a single container
+#[derive(Copy, Clone, Eq, PartialEq)]
+pub(crate) struct ContentKey([u8; 32]);
+
+impl ContentKey {
+    /// Number of bytes which encrypted chunk data take in addition to the original data.
+    pub const OVERHEAD: usize = 8 + chacha::TAG_SIZE;
+
+    pub fn random() -> Self { ContentKey(rand::random()) }
+
+    /// Adds encryption parameter to the MIME type.
+    pub fn apply_to_mime(mime: AsciiString) -> AsciiString {
+        AsciiString::from_str(&format!("{}; {}={}", mime, MIME_PARAM, CIPHER))
+            .expect("encryption parameter is an ASCII string")
+    }
+
+    /// Wraps the key for each of the recipients.
+    pub fn seal(self, recipients: &[NodeId]) -> KeyEnvelope {
+        let ephemeral = loop {
+            if let Ok(key) = SecretKey::from_slice(&rand::random::<[u8; 32]>()) {
+                break key;
+            }
+        };
+        let keys = recipients
+            .iter()
+            .map(|recipient| {
+                let wrap_key = wrap_key(&recipient.public_key(), &ephemeral);
+                let aad = recipient.public_key().serialize();
+                let mut ciphertext = vec![0u8; 32 + chacha::TAG_SIZE];
+                chacha::encrypt(&wrap_key, 0, &aad, &self.0, &mut ciphertext)
+                    .expect("ciphertext buffer size always matches plaintext");
+                WrappedKey {
+                    recipient: *recipient,
+                    ciphertext,
+                }
+            })
+            .collect();
+        KeyEnvelope {
+            ephemeral_key: PublicKey::from_secret_key(secp256k1::SECP256K1, &ephemeral),
+            keys,
+        }
+    }
+
+    /// Unwraps the key for the local node.
+    pub fn open(envelope: &KeyEnvelope, node: &LocalNode) -> Result<Self, EncryptionError> {
+        let node_id = node.node_id();
+        let wrapped = envelope
+            .keys
+            .iter()
+            .find(|wrapped| wrapped.recipient == node_id)
+            .ok_or(EncryptionError::NotRecipient(node_id))?;
+        if wrapped.ciphertext.len() != 32 + chacha::TAG_SIZE {
+            return Err(EncryptionError::Corrupted);
+        }
+        let wrap_key = wrap_key(&envelope.ephemeral_key, &node.private_key());
+        let aad = node_id.public_key().serialize();
+        let mut key = [0u8; 32];
+        chacha::decrypt(&wrap_key, 0, &aad, &wrapped.ciphertext, &mut key)
+            .map_err(|_| EncryptionError::Corrupted)?;
+        Ok(ContentKey(key))
+    }
+
+    /// Detects whether container is encrypted and checks that the encryption is supported.
+    pub fn required(header: &ContainerHeader) -> Result<bool, EncryptionError> {
+        match encryption_param(header) {
+            None => Ok(false),
+            Some(CIPHER) => Ok(true),
+            Some(other) => Err(EncryptionError::Unknown(other.to_owned())),
+        }
+    }
+
+    pub fn encrypt(self, data: Vec<u8>) -> Vec<u8> {
+        let nonce: u64 = rand::random();
+        let mut chunk = vec![0u8; 8 + data.len() + chacha::TAG_SIZE];
+        chunk[..8].copy_from_slice(&nonce.to_le_bytes());
+        chacha::encrypt(&self.0, nonce, &[], &data, &mut chunk[8..])
+            .expect("ciphertext buffer size always matches plaintext");
+        chunk
+    }
+
+    pub fn decrypt(self, chunk: &[u8]) -> Result<Vec<u8>, EncryptionError> {
+        if chunk.len() < Self::OVERHEAD {
+            return Err(EncryptionError::Corrupted);
+        }
+        let (nonce, ciphertext) = chunk.split_at(8);
+        let nonce = u64::from_le_bytes(nonce.try_into().expect("fixed size slice"));
+        let mut data = vec![0u8; ciphertext.len() - chacha::TAG_SIZE];
+        chacha::decrypt(&self.0, nonce, &[], ciphertext, &mut data)
+            .map_err(|_| EncryptionError::Corrupted)?;
+        Ok(data)
+    }
+}
+
+fn wrap_key(public_key: &PublicKey, secret_key: &SecretKey) -> [u8; 32] {
+    shared_key(WRAP_KEY_TAG, public_key, secret_key, &[])
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn node(byte: u8) -> LocalNode {
+        LocalNode::with(secp256k1::SECP256K1, SecretKey::from_slice(&[byte; 32]).unwrap())
+    }
+
+    #[test]
+    fn key_envelope() {
+        let (alice, bob, eve) = (node(1), node(2), node(3));
+        let key = ContentKey::random();
+        let envelope = key.seal(&[alice.node_id(), bob.node_id()]);
+        assert!(ContentKey::open(&envelope, &alice).unwrap() == key);
+        assert!(ContentKey::open(&envelope, &bob).unwrap() == key);
+        assert_eq!(
+            ContentKey::open(&envelope, &eve).err(),
+            Some(EncryptionError::NotRecipient(eve.node_id()))
+        );
+
+        let mut tampered = envelope;
+        tampered.keys[0].ciphertext[0] ^= 1;
+        assert_eq!(ContentKey::open(&tampered, &alice).err(), Some(EncryptionError::Corrupted));
+    }
+
+    #[test]
+    fn roundtrip() {
+        let key = ContentKey::random();
+        let data = b"chunk data".to_vec();
+        let chunk = key.encrypt(data.clone());
+        assert_eq!(chunk.len(), data.len() + ContentKey::OVERHEAD);
+        assert_eq!(key.decrypt(&chunk).unwrap(), data);
+        assert_eq!(ContentKey::random().decrypt(&chunk), Err(EncryptionError::Corrupted));
+
+        let mut tampered = chunk.clone();
+        tampered[10] ^= 1;
+        assert_eq!(key.decrypt(&tampered), Err(EncryptionError::Corrupted));
+        assert_eq!(
+            key.decrypt(&chunk[..ContentKey::OVERHEAD - 1]),
+            Err(EncryptionError::Corrupted)
+        );
+        assert_eq!(key.decrypt(&key.encrypt(vec![])).unwrap(), Vec::<u8>::new());
+    }
+
+    #[test]
+    fn mime() {
+        let header = |mime: &str| ContainerHeader {
+            version: 0,
+            mime: AsciiString::from_str(mime).unwrap(),
+            info: s!(""),
+            size: 0,
+        };
+        let mime = ContentKey::apply_to_mime(AsciiString::from_str("text/plain").unwrap());
+        assert_eq!(mime.as_str(), "text/plain; encryption=chacha20poly1305");
+        assert!(is_encrypted(&header(mime.as_str())));
+        assert_eq!(ContentKey::required(&header(mime.as_str())), Ok(true));
+        assert!(!is_encrypted(&header("text/plain")));
+        assert_eq!(ContentKey::required(&header("text/plain")), Ok(false));
+        assert_eq!(
+            ContentKey::required(&header("text/plain; encryption=aes")),
+            Err(EncryptionError::Unknown(s!("aes")))
+        );
+    }
+
+    #[test]
+    fn shared_keys() {
+        let (alice, bob) = (node(1), node(2));
+        let alice_pk = alice.node_id().public_key();
+        let bob_pk = bob.node_id().public_key();
+        assert_eq!(
+            shared_key(b"tag", &bob_pk, &alice.private_key(), &[b"context"]),
+            shared_key(b"tag", &alice_pk, &bob.private_key(), &[b"context"])
+        );
+        assert_ne!(
+            shared_key(b"tag", &bob_pk, &alice.private_key(), &[]),
+            shared_key(b"other", &bob_pk, &alice.private_key(), &[])
+        );
+        assert_ne!(
+            shared_key(b"tag", &bob_pk, &alice.private_key(), &[]),
+            shared_key(b"tag", &bob_pk, &alice.private_key(), &[b"context"])
+        );
+    }
+
+    #[test]
+    fn node_key_file() {
+        let path = std::env::temp_dir().join(format!("storm-node-key-{}", std::process::id()));
+        let _ = fs::remove_file(&path);
+        assert_eq!(
+            read_node_key(&path).err(),
+            Some(EncryptionError::NodeKey(path.display().to_string()))
+        );
+
+        let node = read_or_create_node_key(&path).unwrap();
+        assert_eq!(read_node_key(&path).unwrap().node_id(), node.node_id());
+        assert_eq!(read_or_create_node_key(&path).unwrap().node_id(), node.node_id());
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            assert_eq!(fs::metadata(&path).unwrap().permissions().mode() & 0o777, 0o600);
+        }
+
+        fs::write(&path, b"invalid").unwrap();
+        assert!(read_or_create_node_key(&path).is_err());
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -9,11 +9,42 @@
 // If not, see <https://opensource.org/licenses/MIT>.
 
 use std::fmt::{self, Display, Formatter};
+use std::time::Duration;
 
 use microservices::{esb, rpc};
 
-use crate::{RpcMsg, ServiceId};
+use crate::{RequestId, RpcMsg, ServiceId};
 
+/// Version of the failure code registry.
+///
+/// The version is increased each time codes are added to [`FailureCode`]; codes are never
+/// renumbered or removed, so a client knowing registry of a given version correctly interprets
+/// all codes of that and previous versions. Codes which are not known to the client are
+/// converted into [`FailureCode::Unknown`]. Version 1 is the set of codes of the initial release.
+pub const FAILURE_CODES_VERSION: u16 = 2;
+
+/// Failure codes reported by the node in RPC failure replies.
+///
+/// | Code  | Failure               | Registry version |
+/// |-------|-----------------------|------------------|
+/// | 0x02  | `Encoding`            | 1                |
+/// | 0x03  | `Launch`              | 1                |
+/// | 0x10  | `Esb`                 | 1                |
+/// | 0x11  | `UnexpectedRequest`   | 1                |
+/// | 0x12  | `Store`               | 1                |
+/// | 0x13  | `Lnp`                 | 2                |
+/// | 0x14  | `Timeout`             | 2                |
+/// | 0x20  | `TransferAutomation`  | 1                |
+/// | 0x21  | `UnknownContainer`    | 1                |
+/// | 0x22  | `PinnedContainer`     | 2                |
+/// | 0x23  | `QuotaExceeded`       | 2                |
+/// | 0x24  | `PolicyRejected`      | 2                |
+/// | 0x25  | `TransfersInProgress` | 2                |
+/// | 0x30  | `UnknownPeer`         | 2                |
+/// | 0x31  | `PeerUnreachable`     | 2                |
+/// | 0x40  | `Unauthenticated`     | 2                |
+/// | 0x41  | `PermissionDenied`    | 2                |
+/// | 0xFFF | `Unknown`             | 1                |
 #[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug)]
 pub enum FailureCode {
     /// Catch-all
@@ -25,15 +56,50 @@ pub enum FailureCode {
     /// Launching service
     Launch = 0x03,
 
+    /// Message bus failure
     Esb = 0x10,
 
+    /// Request is not supported
     UnexpectedRequest = 0x11,
 
+    /// Store service failure
     Store = 0x12,
 
+    /// LNP node failure
+    Lnp = 0x13,
+
+    /// Request has not completed in time
+    Timeout = 0x14,
+
+    /// Container transfer failure
     TransferAutomation = 0x20,
 
+    /// Container is not known
     UnknownContainer = 0x21,
+
+    /// Container is pinned and can't be deleted
+    PinnedContainer = 0x22,
+
+    /// Storage quota is exceeded
+    QuotaExceeded = 0x23,
+
+    /// Request is rejected by the node policy
+    PolicyRejected = 0x24,
+
+    /// Request can't be served while container transfers are in progress
+    TransfersInProgress = 0x25,
+
+    /// Peer is not connected
+    UnknownPeer = 0x30,
+
+    /// Peer can't be reached
+    PeerUnreachable = 0x31,
+
+    /// Request does not have a valid access token
+    Unauthenticated = 0x40,
+
+    /// Client role does not permit the request
+    PermissionDenied = 0x41,
 }
 
 impl Display for FailureCode {
@@ -46,6 +112,23 @@ impl Display for FailureCode {
 impl From<u16> for FailureCode {
     fn from(value: u16) -> Self {
         match value {
+            0x02 => FailureCode::Encoding,
+            0x03 => FailureCode::Launch,
+            0x10 => FailureCode::Esb,
+            0x11 => FailureCode::UnexpectedRequest,
+            0x12 => FailureCode::Store,
+            0x13 => FailureCode::Lnp,
+            0x14 => FailureCode::Timeout,
+            0x20 => FailureCode::TransferAutomation,
+            0x21 => FailureCode::UnknownContainer,
+            0x22 => FailureCode::PinnedContainer,
+            0x23 => FailureCode::QuotaExceeded,
+            0x24 => FailureCode::PolicyRejected,
+            0x25 => FailureCode::TransfersInProgress,
+            0x30 => FailureCode::UnknownPeer,
+            0x31 => FailureCode::PeerUnreachable,
+            0x40 => FailureCode::Unauthenticated,
+            0x41 => FailureCode::PermissionDenied,
             _ => FailureCode::Unknown,
         }
     }
@@ -65,8 +148,7 @@ impl rpc::FailureCodeExt for FailureCode {}
 #[display(doc_comments)]
 pub enum Error {
     #[display(inner)]
-    #[from]
-    Esb(esb::Error<ServiceId>),
+    Esb(Box<esb::Error<ServiceId>>),
 
     /// (STORM#{code:06}) {message}
     LocalFailure { code: FailureCode, message: String },
@@ -79,6 +161,32 @@ pub enum Error {
 
     /// unexpected server response
     UnexpectedServerResponse,
+
+    /// RPC client worker thread has terminated
+    ClientTerminated,
+
+    /// request {0} is unknown or has already completed
+    UnknownRequest(RequestId),
+
+    /// request timed out after {0:?} without a reply from the node
+    Timeout(Duration),
+}
+
+impl From<esb::Error<ServiceId>> for Error {
+    fn from(err: esb::Error<ServiceId>) -> Self { Error::Esb(Box::new(err)) }
+}
+
+impl Error {
+    /// Returns failure code reported by the node, if the error comes from a failure reply.
+    /// Requests timed out on the client side are reported with [`FailureCode::Timeout`].
+    pub fn failure_code(&self) -> Option<rpc::FailureCode<FailureCode>> {
+        match self {
+            Error::LocalFailure { code, .. } => Some(rpc::FailureCode::Other(*code)),
+            Error::RemoteFailure { code, .. } => Some(*code),
+            Error::Timeout(_) => Some(rpc::FailureCode::Other(FailureCode::Timeout)),
+            _ => None,
+        }
+    }
 }
 
 impl RpcMsg {
@@ -99,3 +207,68 @@ impl RpcMsg {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use std::collections::BTreeSet;
+
+    use super::*;
+
+    const CODES: [FailureCode; 18] = [
+        FailureCode::Unknown,
+        FailureCode::Encoding,
+        FailureCode::Launch,
+        FailureCode::Esb,
+        FailureCode::UnexpectedRequest,
+        FailureCode::Store,
+        FailureCode::Lnp,
+        FailureCode::Timeout,
+        FailureCode::TransferAutomation,
+        FailureCode::UnknownContainer,
+        FailureCode::PinnedContainer,
+        FailureCode::QuotaExceeded,
+        FailureCode::PolicyRejected,
+        FailureCode::TransfersInProgress,
+        FailureCode::UnknownPeer,
+        FailureCode::PeerUnreachable,
+        FailureCode::Unauthenticated,
+        FailureCode::PermissionDenied,
+    ];
+
+    #[test]
+    fn failure_code_roundtrip() {
+        for code in CODES {
+            let value = u16::from(code);
+            assert_eq!(FailureCode::from(value), code, "code {:#x} is not roundtripped", value);
+            assert_eq!(code.to_string(), value.to_string());
+        }
+        assert_eq!(
+            CODES.iter().map(|code| *code as u16).collect::<BTreeSet<_>>().len(),
+            CODES.len()
+        );
+    }
+
+    #[test]
+    fn unknown_failure_code() {
+        let known = CODES.iter().map(|code| *code as u16).collect::<BTreeSet<_>>();
+        for value in (0..=0x1000u16).filter(|value| !known.contains(value)) {
+            assert_eq!(FailureCode::from(value), FailureCode::Unknown);
+        }
+        assert_eq!(FailureCode::from(u16::MAX), FailureCode::Unknown);
+    }
+
+    #[test]
+    fn failure_reply() {
+        let msg = RpcMsg::Failure(rpc::Failure {
+            code: FailureCode::QuotaExceeded.into(),
+            info: s!("quota"),
+        });
+        let err = msg.failure_to_error().unwrap_err();
+        assert_eq!(err.failure_code(), Some(rpc::FailureCode::Other(FailureCode::QuotaExceeded)));
+        assert_eq!(
+            Error::Timeout(Duration::from_secs(1)).failure_code(),
+            Some(rpc::FailureCode::Other(FailureCode::Timeout))
+        );
+        assert_eq!(Error::ClientTerminated.failure_code(), None);
+    }
+}
@@ -0,0 +1,526 @@
+// Storm node providing distributed storage & messaging for lightning network.
+//
+// Written in 2022 by
+//     Dr. Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2022 by LNP/BP Standards Association, Switzerland.
+//
+// You should have received a copy of the MIT License along with this software.
+// If not, see <https://opensource.org/licenses/MIT>.
+
+//! Integrity check of containers and chunks kept in the Store database.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::{self, Display, Formatter};
+
+use internet2::addr::LocalNode;
+use storm::{ChunkId, Container, ContainerHeader, ContainerId};
+use strict_encoding::StrictDecode;
+
+use crate::compression::Compression;
+use crate::container::ChunkCodec;
+use crate::directory::{is_directory, EntryKind, Manifest};
+use crate::encryption::{is_encrypted, ContentKey, KeyEnvelope};
+use crate::gc::{self, retrieve_live, GcError};
+use crate::{DB_TABLE_CHUNKS, DB_TABLE_CONTAINER_HEADERS};
+
+/// Problem found in a container during the integrity check
+#[derive(Clone, Eq, PartialEq, Hash, Debug, Display)]
+#[derive(NetworkEncode, NetworkDecode)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", rename_all = "snake_case")
+)]
+pub enum FsckIssue {
+    /// Container is stored under a key which is not its id
+    #[display("id mismatch")]
+    IdMismatch,
+
+    /// Container header is absent from the headers table
+    #[display("missing header")]
+    MissingHeader,
+
+    /// Header stored in the headers table differs from the container header
+    #[display("header mismatch")]
+    HeaderMismatch,
+
+    /// Chunk is absent from the Store database
+    #[display("missing chunk {0}")]
+    MissingChunk(ChunkId),
+
+    /// Chunk data doesn't hash to the chunk id
+    #[display("corrupted chunk {0}")]
+    CorruptedChunk(ChunkId),
+
+    /// Container size doesn't match the size of its data
+    #[display("size mismatch: header says {expected} bytes, data has {actual} bytes")]
+    SizeMismatch { expected: u64, actual: u64 },
+
+    /// Directory container manifest can't be decoded or refers to non-existing chunks
+    #[display("invalid directory manifest")]
+    InvalidManifest,
+
+    /// Container header specifies compression which is not supported
+    #[display("unknown compression")]
+    UnknownCompression,
+
+    /// Encrypted container has no chunk keeping its key envelope
+    #[display("invalid key envelope")]
+    InvalidKeyEnvelope,
+}
+
+/// Container which failed the integrity check
+#[derive(Clone, Eq, PartialEq, Hash, Debug, Display)]
+#[derive(NetworkEncode, NetworkDecode)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(crate = "serde_crate"))]
+#[display("{container_id}")]
+pub struct DamagedContainer {
+    pub container_id: ContainerId,
+    pub issues: Vec<FsckIssue>,
+}
+
+impl DamagedContainer {
+    /// Returns whether the container may be repaired by re-fetching it from a remote peer.
+    pub fn is_repairable(&self) -> bool {
+        self.issues.iter().any(|issue| {
+            matches!(
+                issue,
+                FsckIssue::IdMismatch
+                    | FsckIssue::MissingHeader
+                    | FsckIssue::HeaderMismatch
+                    | FsckIssue::MissingChunk(_)
+                    | FsckIssue::CorruptedChunk(_)
+            )
+        })
+    }
+}
+
+/// Results of the integrity check
+#[derive(Clone, Eq, PartialEq, Hash, Debug, Default)]
+#[derive(NetworkEncode, NetworkDecode)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(crate = "serde_crate"))]
+pub struct FsckReport {
+    /// Number of checked containers
+    pub containers: u64,
+
+    /// Number of distinct chunks referenced by the checked containers
+    pub chunks: u64,
+
+    /// Containers which failed the check
+    pub damaged: Vec<DamagedContainer>,
+
+    /// Number of chunks not referenced by any of the containers
+    pub orphaned_chunks: u64,
+
+    /// Size of the data in the orphaned chunks
+    pub orphaned_bytes: u64,
+
+    /// Number of damaged containers requested from a remote peer
+    pub refetching: u64,
+}
+
+impl Display for FsckReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} containers, {} chunks, {} damaged containers",
+            self.containers,
+            self.chunks,
+            self.damaged.len()
+        )
+    }
+}
+
+/// Checks all live containers from the Store database: that container is stored under its id,
+/// that container header matches the one kept in the headers table, that all container chunks are
+/// present and hash to their ids, and that container size matches the size of its data. Also counts
+/// chunks not referenced by any of the containers, which may be reclaimed with
+/// [`gc::collect_garbage`].
+///
+/// Sizes of encrypted containers are checked only if the key of the local `node` is given and
+/// the node is one of the container recipients.
+pub fn fsck(
+    store: &mut store_rpc::Client,
+    node: Option<&LocalNode>,
+    progress: impl Fn(String),
+) -> Result<FsckReport, GcError> {
+    let containers = gc::live_containers(store)?;
+    progress(format!("Checking {} containers", containers.len()));
+
+    // Decompressed lengths of valid chunks, `None` for missing or corrupted ones
+    let mut chunks = BTreeMap::<(ChunkId, Compression), Option<u64>>::new();
+    let mut report = FsckReport {
+        containers: containers.len() as u64,
+        ..default!()
+    };
+    for (container_id, container) in containers {
+        let issues = check_container(store, container_id, &container, node, &mut chunks)?;
+        if issues.is_empty() {
+            progress(format!("Container {} is valid", container_id));
+        } else {
+            progress(format!("Container {} is damaged", container_id));
+            report.damaged.push(DamagedContainer {
+                container_id,
+                issues,
+            });
+        }
+    }
+    report.chunks =
+        chunks.keys().map(|(chunk_id, _)| chunk_id).collect::<BTreeSet<_>>().len() as u64;
+
+    let orphaned = gc::collect_garbage(store, true, |_| {})?;
+    report.orphaned_chunks = orphaned.chunks;
+    report.orphaned_bytes = orphaned.bytes;
+    progress(format!("Found {} orphaned chunks", orphaned.chunks));
+
+    Ok(report)
+}
+
+/// Filters chunks which are absent from the Store database or do not hash to their ids. Unlike
+/// [`gc::missing_chunks`], retrieves all present chunks, so it is used only for containers which
+/// are re-fetched from a remote peer; their corrupted chunks are overwritten once received.
+pub fn damaged_chunks(
+    store: &mut store_rpc::Client,
+    chunk_ids: BTreeSet<ChunkId>,
+) -> Result<BTreeSet<ChunkId>, GcError> {
+    let missing = gc::missing_chunks(store, chunk_ids.clone())?;
+    let mut damaged = missing.clone();
+    for chunk_id in chunk_ids.difference(&missing) {
+        let codec = ChunkCodec {
+            compression: Compression::None,
+            key: None,
+        };
+        if check_chunk(store, *chunk_id, codec, false)?.is_none() {
+            damaged.insert(*chunk_id);
+        }
+    }
+    Ok(damaged)
+}
+
+fn check_container(
+    store: &mut store_rpc::Client,
+    container_id: ContainerId,
+    container: &Container,
+    node: Option<&LocalNode>,
+    chunks: &mut BTreeMap<(ChunkId, Compression), Option<u64>>,
+) -> Result<Vec<FsckIssue>, GcError> {
+    let mut issues = vec![];
+
+    let compression = match Compression::from_header(&container.header) {
+        Ok(compression) => compression,
+        Err(_) => {
+            issues.push(FsckIssue::UnknownCompression);
+            Compression::None
+        }
+    };
+    // Data of encrypted containers can be decrypted only with the key of one of the recipients;
+    // without it only presence and hashes of their chunks are checked
+    let encrypted = is_encrypted(&container.header);
+    let key = match node {
+        Some(node) if encrypted => content_key(store, container, node)?,
+        _ => None,
+    };
+    let decodable = !encrypted || key.is_some();
+    let codec = ChunkCodec { compression, key };
+    // The last chunk of encrypted containers keeps the key envelope
+    let data_chunks = container.chunks.len().checked_sub(encrypted as usize).unwrap_or_else(|| {
+        issues.push(FsckIssue::InvalidKeyEnvelope);
+        0
+    });
+
+    if container.container_id() != container_id {
+        issues.push(FsckIssue::IdMismatch);
+    }
+    match retrieve_live(store, DB_TABLE_CONTAINER_HEADERS, container_id)? {
+        None => issues.push(FsckIssue::MissingHeader),
+        Some(chunk) => match ContainerHeader::strict_deserialize(chunk.as_slice()) {
+            Ok(header) if header == container.header => {}
+            _ => issues.push(FsckIssue::HeaderMismatch),
+        },
+    }
+
+    let mut lengths = Vec::with_capacity(data_chunks);
+    let missing = gc::missing_chunks(store, container.chunks.iter().copied().collect())?;
+    for (index, chunk_id) in container.chunks.iter().copied().enumerate() {
+        let is_missing = missing.contains(&chunk_id);
+        let len = match (index < data_chunks && decodable, key) {
+            // Encrypted chunks are unique for the container, so they are not cached
+            (true, Some(_)) => check_chunk(store, chunk_id, codec, is_missing)?,
+            (decode, _) => {
+                let compression = if decode { compression } else { Compression::None };
+                match chunks.get(&(chunk_id, compression)) {
+                    Some(len) => *len,
+                    None => {
+                        let codec = ChunkCodec {
+                            compression,
+                            key: None,
+                        };
+                        let len = check_chunk(store, chunk_id, codec, is_missing)?;
+                        chunks.insert((chunk_id, compression), len);
+                        len
+                    }
+                }
+            }
+        };
+        if len.is_none() {
+            let issue = if is_missing {
+                FsckIssue::MissingChunk(chunk_id)
+            } else {
+                FsckIssue::CorruptedChunk(chunk_id)
+            };
+            // Chunk may be used by a container multiple times
+            if !issues.contains(&issue) {
+                issues.push(issue);
+            }
+        }
+        if index < data_chunks {
+            lengths.push(len);
+        }
+    }
+
+    // Sizes can be checked only when all the data are present and can be decoded
+    if !decodable
+        || lengths.iter().any(Option::is_none)
+        || issues.contains(&FsckIssue::UnknownCompression)
+    {
+        return Ok(issues);
+    }
+    let lengths = lengths.into_iter().flatten().collect::<Vec<_>>();
+    if is_directory(&container.header) {
+        issues.extend(check_manifest(store, container, codec, &lengths)?);
+    } else {
+        let actual = lengths.iter().sum();
+        if actual != container.header.size {
+            issues.push(FsckIssue::SizeMismatch {
+                expected: container.header.size,
+                actual,
+            });
+        }
+    }
+
+    Ok(issues)
+}
+
+/// Unwraps content key of an encrypted container with the key of the local `node`. Returns `None`
+/// if the node is not a recipient of the container or the key envelope can't be read.
+fn content_key(
+    store: &mut store_rpc::Client,
+    container: &Container,
+    node: &LocalNode,
+) -> Result<Option<ContentKey>, GcError> {
+    let envelope_id = match container.chunks.last() {
+        Some(chunk_id) if ContentKey::required(&container.header) == Ok(true) => *chunk_id,
+        _ => return Ok(None),
+    };
+    Ok(retrieve_live(store, DB_TABLE_CHUNKS, envelope_id)?
+        .filter(|chunk| chunk.chunk_id() == envelope_id)
+        .and_then(|chunk| KeyEnvelope::strict_deserialize(chunk.as_slice()).ok())
+        .and_then(|envelope| ContentKey::open(&envelope, node).ok()))
+}
+
+/// Retrieves chunk and returns length of its decoded data if it is present, hashes to its id and
+/// can be decoded.
+fn check_chunk(
+    store: &mut store_rpc::Client,
+    chunk_id: ChunkId,
+    codec: ChunkCodec,
+    missing: bool,
+) -> Result<Option<u64>, GcError> {
+    if missing {
+        return Ok(None);
+    }
+    Ok(retrieve_live(store, DB_TABLE_CHUNKS, chunk_id)?
+        .filter(|chunk| chunk.chunk_id() == chunk_id)
+        .and_then(|chunk| codec.decode(chunk.as_slice()).ok())
+        .map(|data| data.len() as u64))
+}
+
+/// Checks that file entries of a directory manifest refer to the existing chunks, and that the
+/// sizes of the files match their data and add up to the container size.
+fn check_manifest(
+    store: &mut store_rpc::Client,
+    container: &Container,
+    codec: ChunkCodec,
+    lengths: &[u64],
+) -> Result<Option<FsckIssue>, GcError> {
+    let manifest = match container.chunks.first() {
+        Some(chunk_id) => retrieve_live(store, DB_TABLE_CHUNKS, *chunk_id)?
+            .and_then(|chunk| codec.decode(chunk.as_slice()).ok())
+            .and_then(|data| Manifest::strict_deserialize(data).ok()),
+        None => None,
+    };
+    let manifest = match manifest {
+        Some(manifest) => manifest,
+        None => return Ok(Some(FsckIssue::InvalidManifest)),
+    };
+
+    let mut actual = 0u64;
+    for entry in manifest.entries.iter().filter(|entry| entry.kind == EntryKind::File) {
+        let start = entry.first_chunk as usize;
+        let end = start + entry.chunk_count as usize;
+        if start == 0 || end > lengths.len() {
+            return Ok(Some(FsckIssue::InvalidManifest));
+        }
+        let size = lengths[start..end].iter().sum::<u64>();
+        if size != entry.size {
+            return Ok(Some(FsckIssue::SizeMismatch {
+                expected: entry.size,
+                actual: size,
+            }));
+        }
+        actual += size;
+    }
+    if actual != container.header.size {
+        return Ok(Some(FsckIssue::SizeMismatch {
+            expected: container.header.size,
+            actual,
+        }));
+    }
+    Ok(None)
+}
+
+#[cfg(test)]
+mod test {
+    use storm::Chunk;
+    use strict_encoding::StrictEncode;
+
+    use super::*;
+    use crate::container::store_container;
+    use crate::{containerize, test_store, ContainerizeOptions, DB_TABLE_CONTAINERS};
+
+    fn container(store: &mut store_rpc::Client, data: &[u8]) -> (ContainerId, Container) {
+        let mime = "text/plain".parse().unwrap();
+        let id = containerize(store, data, mime, s!(""), &default!(), |_| {}).unwrap();
+        let chunk = retrieve_live(store, DB_TABLE_CONTAINERS, id).unwrap().unwrap();
+        (id, Container::strict_deserialize(chunk.as_slice()).unwrap())
+    }
+
+    fn issues(report: &FsckReport, container_id: ContainerId) -> Vec<FsckIssue> {
+        report
+            .damaged
+            .iter()
+            .find(|damaged| damaged.container_id == container_id)
+            .map(|damaged| damaged.issues.clone())
+            .unwrap_or_default()
+    }
+
+    #[test]
+    fn valid() {
+        let mut store = test_store::client();
+        let (id, container) = container(&mut store, b"data");
+        let report = fsck(&mut store, None, |_| {}).unwrap();
+        assert_eq!(report.containers, 1);
+        assert_eq!(report.chunks, 1);
+        assert!(report.damaged.is_empty());
+        let chunk_ids = container.chunks.iter().copied().collect();
+        assert!(damaged_chunks(&mut store, chunk_ids).unwrap().is_empty());
+        assert!(issues(&report, id).is_empty());
+    }
+
+    #[test]
+    fn corrupted_chunk() {
+        let mut store = test_store::client();
+        let (id, container) = container(&mut store, b"data");
+        let chunk_id = container.chunks[0];
+        let corrupted = Chunk::try_from(&b"dat"[..]).unwrap();
+        store.store(DB_TABLE_CHUNKS, chunk_id, &corrupted).unwrap();
+
+        let report = fsck(&mut store, None, |_| {}).unwrap();
+        assert_eq!(issues(&report, id), vec![FsckIssue::CorruptedChunk(chunk_id)]);
+        assert!(report.damaged[0].is_repairable());
+        // Corrupted chunk is kept until the container is re-fetched
+        assert!(gc::missing_chunks(&mut store, bset! {chunk_id}).unwrap().is_empty());
+        assert_eq!(damaged_chunks(&mut store, bset! {chunk_id}).unwrap(), bset! {chunk_id});
+    }
+
+    #[test]
+    fn wrong_key() {
+        let mut store = test_store::client();
+        let (id, container) = container(&mut store, b"data");
+        let (other_id, _) = self::container(&mut store, b"other data");
+        let chunk = Chunk::try_from(container.strict_serialize().unwrap()).unwrap();
+        store.store(DB_TABLE_CONTAINERS, other_id, &chunk).unwrap();
+
+        let report = fsck(&mut store, None, |_| {}).unwrap();
+        assert!(issues(&report, id).is_empty());
+        assert_eq!(issues(&report, other_id), vec![
+            FsckIssue::IdMismatch,
+            FsckIssue::HeaderMismatch
+        ]);
+    }
+
+    #[test]
+    fn encrypted() {
+        let node = LocalNode::with(
+            secp256k1::SECP256K1,
+            secp256k1::SecretKey::from_slice(&[1; 32]).unwrap(),
+        );
+        let other = LocalNode::with(
+            secp256k1::SECP256K1,
+            secp256k1::SecretKey::from_slice(&[2; 32]).unwrap(),
+        );
+        let mut store = test_store::client();
+        let options = ContainerizeOptions {
+            compression: Compression::Deflate,
+            encrypt_to: vec![node.node_id()],
+            ..default!()
+        };
+        let mime = "text/plain".parse().unwrap();
+        let data = b"encrypted data".repeat(10);
+        let id = containerize(&mut store, &data[..], mime, s!(""), &options, |_| {}).unwrap();
+        for node in [None, Some(&node), Some(&other)] {
+            assert!(fsck(&mut store, node, |_| {}).unwrap().damaged.is_empty());
+        }
+
+        // Container with a wrong size is detected only with the key of the recipient
+        let chunk = retrieve_live(&mut store, DB_TABLE_CONTAINERS, id).unwrap().unwrap();
+        let mut container = Container::strict_deserialize(chunk.as_slice()).unwrap();
+        container.header.size += 1;
+        let wrong_id = store_container(&mut store, container.header, container.chunks.to_vec())
+            .map_err(|_| ())
+            .unwrap();
+        assert!(issues(&fsck(&mut store, None, |_| {}).unwrap(), wrong_id).is_empty());
+        assert!(issues(&fsck(&mut store, Some(&other), |_| {}).unwrap(), wrong_id).is_empty());
+        assert_eq!(issues(&fsck(&mut store, Some(&node), |_| {}).unwrap(), wrong_id), vec![
+            FsckIssue::SizeMismatch {
+                expected: data.len() as u64 + 1,
+                actual: data.len() as u64
+            }
+        ]);
+    }
+
+    #[test]
+    fn encrypted_without_chunks() {
+        let node = LocalNode::with(
+            secp256k1::SECP256K1,
+            secp256k1::SecretKey::from_slice(&[1; 32]).unwrap(),
+        );
+        let mut store = test_store::client();
+        let options = ContainerizeOptions {
+            encrypt_to: vec![node.node_id()],
+            ..default!()
+        };
+        let mime = "text/plain".parse().unwrap();
+        let id = containerize(&mut store, &b"data"[..], mime, s!(""), &options, |_| {}).unwrap();
+        let chunk = retrieve_live(&mut store, DB_TABLE_CONTAINERS, id).unwrap().unwrap();
+        let container = Container::strict_deserialize(chunk.as_slice()).unwrap();
+        let empty_id =
+            store_container(&mut store, container.header, vec![]).map_err(|_| ()).unwrap();
+
+        for node in [None, Some(&node)] {
+            let report = fsck(&mut store, node, |_| {}).unwrap();
+            assert_eq!(issues(&report, empty_id), vec![FsckIssue::InvalidKeyEnvelope]);
+            assert!(issues(&report, id).is_empty());
+        }
+    }
+
+    #[test]
+    fn missing_header() {
+        let mut store = test_store::client();
+        let (id, _) = container(&mut store, b"data");
+        store.store(DB_TABLE_CONTAINER_HEADERS, id, &Chunk::default()).unwrap();
+        let report = fsck(&mut store, None, |_| {}).unwrap();
+        assert_eq!(issues(&report, id), vec![FsckIssue::MissingHeader]);
+    }
+}
@@ -0,0 +1,275 @@
+// Storm node providing distributed storage & messaging for lightning network.
+//
+// Written in 2022 by
+//     Dr. Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2022 by LNP/BP Standards Association, Switzerland.
+//
+// You should have received a copy of the MIT License along with this software.
+// If not, see <https://opensource.org/licenses/MIT>.
+
+//! Deletion of containers and garbage collection of chunks.
+//!
+//! Store RPC has no operation for removing keys, so deleted containers and reclaimed chunks are
+//! overwritten with an empty value, releasing the space taken by their data. Valid containers,
+//! headers and chunks are never empty, so an empty value is treated everywhere as an absent
+//! one. Since the Store still reports keys of reclaimed chunks as known, their ids are also
+//! recorded in [`DB_TABLE_RECLAIMED_CHUNKS`], which is consulted by [`missing_chunks`].
+
+use std::collections::BTreeSet;
+
+use amplify::Wrapper;
+use bitcoin_hashes::{sha256t, Hash};
+use microservices::rpc::ServerError;
+use storm::{Chunk, ChunkId, Container, ContainerId};
+use strict_encoding::StrictDecode;
+
+use crate::{
+    DB_TABLE_CHUNKS, DB_TABLE_CONTAINERS, DB_TABLE_CONTAINER_HEADERS, DB_TABLE_CONTAINER_ORIGINS,
+    DB_TABLE_DOWNPOUR_CATALOG, DB_TABLE_PINNED_CONTAINERS, DB_TABLE_RECLAIMED_CHUNKS,
+};
+
+/// Errors happening during container deletion and garbage collection
+#[derive(Debug, Display, Error, From)]
+#[display(doc_comments)]
+pub enum GcError {
+    /// container {0} is not known
+    UnknownContainer(ContainerId),
+
+    /// container {0} is pinned and can't be deleted
+    PinnedContainer(ContainerId),
+
+    /// {0} container transfers are in progress; garbage collection may reclaim their chunks and
+    /// must be repeated once they complete
+    TransfersInProgress(usize),
+
+    /// error accessing the Store database. Details: {0}
+    #[from]
+    Store(ServerError<store_rpc::FailureCode>),
+
+    /// data in the Store database are corrupted. Details: {0}
+    #[from]
+    Encoding(strict_encoding::Error),
+}
+
+/// Results of a garbage collection pass
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Default, Display)]
+#[derive(NetworkEncode, NetworkDecode)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(crate = "serde_crate"))]
+#[display("{chunks} chunks, {bytes} bytes")]
+pub struct GcReport {
+    /// Whether the chunks were left intact
+    pub dry_run: bool,
+
+    /// Number of live containers keeping their chunks from collection
+    pub containers: u64,
+
+    /// Number of unreferenced chunks which were (or, for a dry run, would be) reclaimed
+    pub chunks: u64,
+
+    /// Size of the reclaimed data
+    pub bytes: u64,
+}
+
+/// Tables which need to be used by a Store client before calling functions from this module.
+pub const GC_TABLES: [&str; 7] = [
+    DB_TABLE_CONTAINER_HEADERS,
+    DB_TABLE_CONTAINERS,
+    DB_TABLE_CONTAINER_ORIGINS,
+    DB_TABLE_CHUNKS,
+    DB_TABLE_PINNED_CONTAINERS,
+    DB_TABLE_RECLAIMED_CHUNKS,
+    DB_TABLE_DOWNPOUR_CATALOG,
+];
+
+/// Retrieves value from the Store database, treating deleted (empty) values as absent.
+pub fn retrieve_live(
+    store: &mut store_rpc::Client,
+    table: &str,
+    key: impl store_rpc::PrimaryKey,
+) -> Result<Option<Chunk>, ServerError<store_rpc::FailureCode>> {
+    let chunk: Option<Chunk> = store.retrieve_chunk(table, key)?;
+    Ok(chunk.filter(|chunk| !chunk.is_empty()))
+}
+
+/// Filters chunk ids which are absent from the Store database, including reclaimed chunks.
+pub fn missing_chunks(
+    store: &mut store_rpc::Client,
+    chunk_ids: BTreeSet<ChunkId>,
+) -> Result<BTreeSet<ChunkId>, ServerError<store_rpc::FailureCode>> {
+    let mut missing = store.filter_unknown(DB_TABLE_CHUNKS, chunk_ids.clone())?;
+    let known = chunk_ids.difference(&missing).copied().collect::<BTreeSet<_>>();
+    let not_reclaimed = store.filter_unknown(DB_TABLE_RECLAIMED_CHUNKS, known.clone())?;
+    for chunk_id in known.difference(&not_reclaimed) {
+        // Reclaimed chunk may have been stored again since then
+        if retrieve_live(store, DB_TABLE_CHUNKS, *chunk_id)?.is_none() {
+            missing.insert(*chunk_id);
+        }
+    }
+    Ok(missing)
+}
+
+/// Checks whether container is pinned and thus protected from deletion.
+pub fn is_pinned(
+    store: &mut store_rpc::Client,
+    container_id: ContainerId,
+) -> Result<bool, ServerError<store_rpc::FailureCode>> {
+    let chunk: Option<Chunk> = store.retrieve_chunk(DB_TABLE_PINNED_CONTAINERS, container_id)?;
+    Ok(chunk.map(|chunk| chunk.as_slice() == [1u8]).unwrap_or_default())
+}
+
+/// Pins or unpins container.
+pub fn set_pinned(
+    store: &mut store_rpc::Client,
+    container_id: ContainerId,
+    pinned: bool,
+) -> Result<(), GcError> {
+    if retrieve_live(store, DB_TABLE_CONTAINERS, container_id)?.is_none() {
+        return Err(GcError::UnknownContainer(container_id));
+    }
+    let flag = Chunk::try_from(&[pinned as u8][..])?;
+    store.store(DB_TABLE_PINNED_CONTAINERS, container_id, &flag)?;
+    Ok(())
+}
+
+/// Deletes container and its header. Container chunks are left intact until the next garbage
+/// collection pass, since they may be shared with other containers.
+///
+/// The container is also removed from the downpour catalog, such that it is not reported as
+/// complete and may be downloaded again once announced by a peer.
+pub fn delete_container(
+    store: &mut store_rpc::Client,
+    container_id: ContainerId,
+) -> Result<(), GcError> {
+    if retrieve_live(store, DB_TABLE_CONTAINERS, container_id)?.is_none() {
+        return Err(GcError::UnknownContainer(container_id));
+    }
+    if is_pinned(store, container_id)? {
+        return Err(GcError::PinnedContainer(container_id));
+    }
+    store.store(DB_TABLE_CONTAINERS, container_id, &Chunk::default())?;
+    store.store(DB_TABLE_CONTAINER_HEADERS, container_id, &Chunk::default())?;
+    store.store(DB_TABLE_CONTAINER_ORIGINS, container_id, &Chunk::default())?;
+    if retrieve_live(store, DB_TABLE_DOWNPOUR_CATALOG, container_id)?.is_some() {
+        store.store(DB_TABLE_DOWNPOUR_CATALOG, container_id, &Chunk::default())?;
+    }
+    Ok(())
+}
+
+/// Returns all containers present in the Store database, skipping deleted ones. Each container
+/// is returned together with the key it is stored under, which differs from the container id
+/// only if the Store database is corrupted.
+pub fn live_containers(
+    store: &mut store_rpc::Client,
+) -> Result<Vec<(ContainerId, Container)>, GcError> {
+    let mut containers = vec![];
+    for key in store.ids(DB_TABLE_CONTAINERS)? {
+        let container_id = ContainerId::from_inner(sha256t::Hash::from_inner(key.into_inner()));
+        if let Some(chunk) = retrieve_live(store, DB_TABLE_CONTAINERS, container_id)? {
+            let container = Container::strict_deserialize(chunk.as_slice())?;
+            containers.push((container_id, container));
+        }
+    }
+    Ok(containers)
+}
+
+/// Reclaims space taken by chunks which are not referenced by any of the live containers. With
+/// `dry_run` set, only reports what would be reclaimed.
+///
+/// Chunks of containers being containerized or received at the moment of collection may be
+/// reclaimed, since they are stored before their container; so the collection must not run
+/// concurrently with these processes. The node refuses to collect garbage while container
+/// transfers are in progress with [`GcError::TransfersInProgress`].
+pub fn collect_garbage(
+    store: &mut store_rpc::Client,
+    dry_run: bool,
+    progress: impl Fn(String),
+) -> Result<GcReport, GcError> {
+    let containers = live_containers(store)?;
+    let mut referenced = BTreeSet::new();
+    for (_, container) in &containers {
+        referenced.extend(container.chunks.iter().copied());
+    }
+    progress(format!("{} live containers reference {} chunks", containers.len(), referenced.len()));
+
+    let mut report = GcReport {
+        dry_run,
+        containers: containers.len() as u64,
+        ..default!()
+    };
+    for chunk_id in store.ids(DB_TABLE_CHUNKS)? {
+        if referenced.contains(&chunk_id) {
+            continue;
+        }
+        let chunk = match retrieve_live(store, DB_TABLE_CHUNKS, chunk_id)? {
+            Some(chunk) => chunk,
+            None => continue,
+        };
+        report.chunks += 1;
+        report.bytes += chunk.len() as u64;
+        if !dry_run {
+            store.store(DB_TABLE_RECLAIMED_CHUNKS, chunk_id, &Chunk::default())?;
+            store.store(DB_TABLE_CHUNKS, chunk_id, &Chunk::default())?;
+            progress(format!("Reclaimed chunk {} ({} bytes)", chunk_id, chunk.len()));
+        } else {
+            progress(format!("Chunk {} ({} bytes) is not referenced", chunk_id, chunk.len()));
+        }
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{containerize, test_store};
+
+    fn container(store: &mut store_rpc::Client, data: &[u8]) -> ContainerId {
+        let mime = "text/plain".parse().unwrap();
+        containerize(store, data, mime, s!(""), &default!(), |_| {}).unwrap()
+    }
+
+    #[test]
+    fn delete() {
+        let mut store = test_store::client();
+        let id = container(&mut store, b"data");
+        let entry = Chunk::try_from(&b"catalog entry"[..]).unwrap();
+        store.store(DB_TABLE_DOWNPOUR_CATALOG, id, &entry).unwrap();
+
+        set_pinned(&mut store, id, true).unwrap();
+        assert!(
+            matches!(delete_container(&mut store, id), Err(GcError::PinnedContainer(i)) if i == id)
+        );
+        set_pinned(&mut store, id, false).unwrap();
+        delete_container(&mut store, id).unwrap();
+
+        assert!(live_containers(&mut store).unwrap().is_empty());
+        assert_eq!(retrieve_live(&mut store, DB_TABLE_DOWNPOUR_CATALOG, id).unwrap(), None);
+        assert!(
+            matches!(delete_container(&mut store, id), Err(GcError::UnknownContainer(i)) if i == id)
+        );
+    }
+
+    #[test]
+    fn collect() {
+        let mut store = test_store::client();
+        let kept = container(&mut store, b"kept");
+        let deleted = container(&mut store, b"deleted");
+        delete_container(&mut store, deleted).unwrap();
+
+        let report = collect_garbage(&mut store, true, |_| {}).unwrap();
+        assert_eq!(report, GcReport {
+            dry_run: true,
+            containers: 1,
+            chunks: 1,
+            bytes: 7,
+        });
+        let report = collect_garbage(&mut store, false, |_| {}).unwrap();
+        assert_eq!(report.chunks, 1);
+        assert_eq!(collect_garbage(&mut store, false, |_| {}).unwrap().chunks, 0);
+
+        let (_, container) = live_containers(&mut store).unwrap().pop().unwrap();
+        let chunk_ids = container.chunks.iter().copied().collect::<BTreeSet<_>>();
+        assert!(missing_chunks(&mut store, chunk_ids).unwrap().is_empty());
+        assert_eq!(live_containers(&mut store).unwrap()[0].0, kept);
+    }
+}
@@ -0,0 +1,312 @@
+// Storm node providing distributed storage & messaging for lightning network.
+//
+// Written in 2022 by
+//     Dr. Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2022 by LNP/BP Standards Association, Switzerland.
+//
+// You should have received a copy of the MIT License along with this software.
+// If not, see <https://opensource.org/licenses/MIT>.
+
+//! Inventory of containers kept in the Store database.
+//!
+//! For each stored container the node records its [`ContainerOrigin`] in
+//! [`DB_TABLE_CONTAINER_ORIGINS`]: the app which requested the container, the peer it was
+//! received from and the time it was stored. Containers stored before origins were recorded
+//! have no origin and never match date filters.
+//!
+//! Origins are also used to account storage usage per app and per remote peer, against which
+//! storage quotas are enforced.
+
+use std::collections::BTreeMap;
+use std::fmt::{self, Display, Formatter};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use internet2::addr::NodeId;
+use microservices::rpc::ServerError;
+use storm::{Chunk, Container, ContainerHeader, ContainerId, StormApp};
+use strict_encoding::{StrictDecode, StrictEncode};
+
+use crate::container::has_mime;
+use crate::gc::{self, retrieve_live, GcError};
+use crate::{DB_TABLE_CONTAINERS, DB_TABLE_CONTAINER_ORIGINS};
+
+/// Information on how the container got into the Store database
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+#[derive(NetworkEncode, NetworkDecode)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(crate = "serde_crate"))]
+pub struct ContainerOrigin {
+    /// App which requested the container; `None` for containers created locally.
+    pub storm_app: Option<StormApp>,
+
+    /// Remote peer the container was received from; `None` for containers created locally.
+    pub remote_id: Option<NodeId>,
+
+    /// Time when the container was stored for the first time, as a UNIX timestamp.
+    pub stored_at: u64,
+}
+
+impl Display for ContainerOrigin {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match (self.storm_app, self.remote_id) {
+            (Some(app), Some(remote_id)) => write!(f, "{} from {}", app, remote_id),
+            (Some(app), None) => write!(f, "{}", app),
+            (None, Some(remote_id)) => write!(f, "{}", remote_id),
+            (None, None) => f.write_str("local"),
+        }
+    }
+}
+
+/// Information about a container kept in the Store database
+#[derive(Clone, Eq, PartialEq, Hash, Debug, Display)]
+#[derive(NetworkEncode, NetworkDecode)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(crate = "serde_crate"))]
+#[display("{container_id}")]
+pub struct ContainerSummary {
+    pub container_id: ContainerId,
+
+    pub header: ContainerHeader,
+
+    /// Number of chunks referenced by the container.
+    pub chunks: u32,
+
+    /// Number of container chunks present in the Store database.
+    pub present_chunks: u32,
+
+    /// Origin of the container, if known.
+    pub origin: Option<ContainerOrigin>,
+}
+
+impl ContainerSummary {
+    /// Checks whether all container chunks are present in the Store database.
+    pub fn is_complete(&self) -> bool { self.present_chunks == self.chunks }
+}
+
+/// Filter for selecting stored containers
+#[derive(Clone, Eq, PartialEq, Hash, Debug, Default)]
+#[derive(NetworkEncode, NetworkDecode)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(crate = "serde_crate"))]
+pub struct ContainerFilter {
+    /// Match only containers of the given MIME type.
+    pub mime: Option<String>,
+
+    /// Match only containers of at least the given size.
+    pub min_size: Option<u64>,
+
+    /// Match only containers of at most the given size.
+    pub max_size: Option<u64>,
+
+    /// Match only containers stored at or after the given UNIX timestamp.
+    pub since: Option<u64>,
+
+    /// Match only containers stored before the given UNIX timestamp.
+    pub until: Option<u64>,
+}
+
+impl Display for ContainerFilter {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let mut filters = vec![];
+        if let Some(mime) = &self.mime {
+            filters.push(format!("mime={}", mime));
+        }
+        if let Some(min_size) = self.min_size {
+            filters.push(format!("min_size={}", min_size));
+        }
+        if let Some(max_size) = self.max_size {
+            filters.push(format!("max_size={}", max_size));
+        }
+        if let Some(since) = self.since {
+            filters.push(format!("since={}", since));
+        }
+        if let Some(until) = self.until {
+            filters.push(format!("until={}", until));
+        }
+        f.write_str(&filters.join(", "))
+    }
+}
+
+impl ContainerFilter {
+    /// Checks whether a container matches the filter.
+    pub fn matches(&self, summary: &ContainerSummary) -> bool {
+        let header = &summary.header;
+        let stored_at = summary.origin.map(|origin| origin.stored_at);
+        self.mime.as_ref().map(|mime| has_mime(header, mime)).unwrap_or(true)
+            && self.min_size.map(|size| header.size >= size).unwrap_or(true)
+            && self.max_size.map(|size| header.size <= size).unwrap_or(true)
+            && self.since.map(|since| stored_at.map(|t| t >= since) == Some(true)).unwrap_or(true)
+            && self.until.map(|until| stored_at.map(|t| t < until) == Some(true)).unwrap_or(true)
+    }
+}
+
+/// Scope to which a storage quota applies
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, Display)]
+#[derive(NetworkEncode, NetworkDecode)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", rename_all = "snake_case")
+)]
+pub enum QuotaScope {
+    /// Containers requested by a Storm app
+    #[display("app {0}")]
+    App(StormApp),
+
+    /// Containers received from a remote peer
+    #[display("peer {0}")]
+    Peer(NodeId),
+}
+
+/// Storage used within a quota scope
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug)]
+#[derive(NetworkEncode, NetworkDecode)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(crate = "serde_crate"))]
+pub struct QuotaUsage {
+    pub scope: QuotaScope,
+
+    /// Total size of the containers (in bytes) within the scope.
+    pub used: u64,
+
+    /// Maximal total size of the containers (in bytes) within the scope; `None` if the scope is
+    /// not limited.
+    pub limit: Option<u64>,
+}
+
+impl Display for QuotaUsage {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self.limit {
+            Some(limit) => write!(f, "{} uses {} of {} bytes", self.scope, self.used, limit),
+            None => write!(f, "{} uses {} bytes", self.scope, self.used),
+        }
+    }
+}
+
+/// Storage used by the containers received from remote peers
+#[derive(Clone, Eq, PartialEq, Debug, Default)]
+pub struct StorageUsage {
+    /// Total size of the containers (in bytes) per app which requested them.
+    pub apps: BTreeMap<StormApp, u64>,
+
+    /// Total size of the containers (in bytes) per remote peer they were received from.
+    pub peers: BTreeMap<NodeId, u64>,
+}
+
+impl StorageUsage {
+    /// Returns size of the containers (in bytes) stored within the scope.
+    pub fn used(&self, scope: QuotaScope) -> u64 {
+        match scope {
+            QuotaScope::App(app) => self.apps.get(&app),
+            QuotaScope::Peer(remote_id) => self.peers.get(&remote_id),
+        }
+        .copied()
+        .unwrap_or_default()
+    }
+
+    /// Accounts container of `size` bytes requested by `storm_app` from `remote_id` peer.
+    pub fn add(&mut self, storm_app: Option<StormApp>, remote_id: Option<NodeId>, size: u64) {
+        if let Some(app) = storm_app {
+            *self.apps.entry(app).or_default() += size;
+        }
+        if let Some(remote_id) = remote_id {
+            *self.peers.entry(remote_id).or_default() += size;
+        }
+    }
+}
+
+/// Records origin of a container, unless it is already known.
+pub fn record_origin<E>(
+    store: &mut store_rpc::Client,
+    container_id: ContainerId,
+    storm_app: Option<StormApp>,
+    remote_id: Option<NodeId>,
+) -> Result<(), E>
+where
+    E: From<ServerError<store_rpc::FailureCode>> + From<strict_encoding::Error>,
+{
+    if retrieve_live(store, DB_TABLE_CONTAINER_ORIGINS, container_id)?.is_some() {
+        return Ok(());
+    }
+    let origin = ContainerOrigin {
+        storm_app,
+        remote_id,
+        stored_at: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_secs())
+            .unwrap_or_default(),
+    };
+    let chunk = Chunk::try_from(origin.strict_serialize()?)?;
+    store.store(DB_TABLE_CONTAINER_ORIGINS, container_id, &chunk)?;
+    Ok(())
+}
+
+/// Returns information about a container kept in the Store database.
+pub fn container_summary(
+    store: &mut store_rpc::Client,
+    container_id: ContainerId,
+) -> Result<ContainerSummary, GcError> {
+    let chunk = retrieve_live(store, DB_TABLE_CONTAINERS, container_id)?
+        .ok_or(GcError::UnknownContainer(container_id))?;
+    let container = Container::strict_deserialize(chunk.as_slice())?;
+    summarize(store, container_id, container)
+}
+
+/// Lists containers kept in the Store database which match the filter.
+pub fn list_containers(
+    store: &mut store_rpc::Client,
+    filter: &ContainerFilter,
+) -> Result<Vec<ContainerSummary>, GcError> {
+    let mut summaries = vec![];
+    for (container_id, container) in gc::live_containers(store)? {
+        let summary = summarize(store, container_id, container)?;
+        if filter.matches(&summary) {
+            summaries.push(summary);
+        }
+    }
+    Ok(summaries)
+}
+
+/// Accounts storage used by the live containers received from remote peers. Container with
+/// `exclude` id is not accounted, which allows to check quotas for a container which is already
+/// (partially) stored.
+pub fn storage_usage(
+    store: &mut store_rpc::Client,
+    exclude: Option<ContainerId>,
+) -> Result<StorageUsage, GcError> {
+    let mut usage = StorageUsage::default();
+    for (container_id, container) in gc::live_containers(store)? {
+        if Some(container_id) == exclude {
+            continue;
+        }
+        let origin = match origin(store, container_id)? {
+            Some(origin) => origin,
+            None => continue,
+        };
+        usage.add(origin.storm_app, origin.remote_id, container.header.size);
+    }
+    Ok(usage)
+}
+
+fn summarize(
+    store: &mut store_rpc::Client,
+    container_id: ContainerId,
+    container: Container,
+) -> Result<ContainerSummary, GcError> {
+    let missing = gc::missing_chunks(store, container.chunks.iter().copied().collect())?;
+    let present_chunks =
+        container.chunks.iter().filter(|chunk_id| !missing.contains(*chunk_id)).count();
+    Ok(ContainerSummary {
+        container_id,
+        chunks: container.chunks.len() as u32,
+        present_chunks: present_chunks as u32,
+        origin: origin(store, container_id)?,
+        header: container.header,
+    })
+}
+
+/// Returns origin of a container, if it is recorded.
+pub fn origin(
+    store: &mut store_rpc::Client,
+    container_id: ContainerId,
+) -> Result<Option<ContainerOrigin>, ServerError<store_rpc::FailureCode>> {
+    Ok(retrieve_live(store, DB_TABLE_CONTAINER_ORIGINS, container_id)?
+        .and_then(|chunk| ContainerOrigin::strict_deserialize(chunk.as_slice()).ok()))
+}
@@ -24,22 +24,71 @@ extern crate internet2;
 extern crate log;
 
 #[cfg(feature = "serde")]
+#[macro_use]
 extern crate serde_crate as serde;
 
+#[cfg(feature = "tokio")]
+pub mod async_client;
+pub mod auth;
+pub mod chunking;
 pub mod client;
+pub mod compression;
+pub mod container;
+pub mod directory;
+pub mod encryption;
+pub mod fsck;
+pub mod gc;
+pub mod inventory;
+pub mod progress;
 mod error;
 mod messages;
+#[cfg(feature = "serde")]
+mod serde_helpers;
 mod service_id;
+#[cfg(test)]
+mod test_store;
 
-pub use client::Client;
-pub use error::{Error, FailureCode};
+#[cfg(feature = "tokio")]
+pub use async_client::{AsyncClient, ChatStream, ProgressStream};
+pub use auth::{AuthToken, Role, RPC_COOKIE_FILE, RPC_TOKENS_FILE};
+pub use chunking::{Chunking, ChunkingError};
+pub use client::{Client, Timeouts};
+pub use compression::{Compression, CompressionError};
+pub use container::{
+    assemble, assemble_file, containerize, containerize_file, has_mime, mime_base, AssembleError,
+    ContainerizeError, ContainerizeOptions, CHUNK_SIZE,
+};
+pub use directory::{assemble_dir, containerize_dir, is_directory, DIRECTORY_MIME};
+pub use encryption::{
+    is_encrypted, read_node_key, read_or_create_node_key, shared_key, EncryptionError,
+};
+pub use error::{Error, FailureCode, FAILURE_CODES_VERSION};
+pub use fsck::{DamagedContainer, FsckIssue, FsckReport};
+pub use gc::{GcError, GcReport};
+pub use inventory::{
+    ContainerFilter, ContainerOrigin, ContainerSummary, QuotaScope, QuotaUsage, StorageUsage,
+};
 pub(crate) use messages::BusMsg;
-pub use messages::{AddressedMsg, AppContainer, RadioMsg, RpcMsg};
-pub use service_id::ServiceId;
+pub use messages::{
+    AddressedMsg, AppContainer, CatalogEntry, CatalogFilter, CatalogStatus,
+    CatalogStatusParseError, ChatDirection, ChatMsg, ChatProtection, ChatRecord, ClientRequest,
+    PeerInfo, PendingContainer, PolicyViolation, RadioMsg, RequestId, RpcEnvelope, RpcMsg,
+    StormSupport,
+};
+pub use progress::ProgressEvent;
+pub use service_id::{ChatTopic, ServiceId};
 
 pub const STORM_NODE_RPC_ENDPOINT: &str = "0.0.0.0:64964";
-pub const CHATD_RPC_ENDPOINT: &str = "0.0.0.0:40940";
+/// Chat PUB socket does not authenticate subscribers, so it is bound to the local interface only;
+/// see [`auth`].
+pub const CHATD_RPC_ENDPOINT: &str = "127.0.0.1:40940";
 
-pub const DB_TABLE_CONTAINER_HEADERS: &'static str = "container_headers";
-pub const DB_TABLE_CONTAINERS: &'static str = "containers";
-pub const DB_TABLE_CHUNKS: &'static str = "chunks";
+pub const DB_TABLE_CONTAINER_HEADERS: &str = "container_headers";
+pub const DB_TABLE_CONTAINERS: &str = "containers";
+pub const DB_TABLE_CHUNKS: &str = "chunks";
+pub const DB_TABLE_DOWNPOUR_CATALOG: &str = "downpour_catalog";
+pub const DB_TABLE_PINNED_CONTAINERS: &str = "pinned_containers";
+pub const DB_TABLE_RECLAIMED_CHUNKS: &str = "reclaimed_chunks";
+pub const DB_TABLE_CONTAINER_ORIGINS: &str = "container_origins";
+pub const DB_TABLE_CHAT_HISTORY: &str = "chat_history";
+pub const DB_TABLE_CHAT_KEYS: &str = "chat_keys";
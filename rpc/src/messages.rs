@@ -8,17 +8,23 @@
 // You should have received a copy of the MIT License along with this software.
 // If not, see <https://opensource.org/licenses/MIT>.
 
+use std::collections::BTreeSet;
 use std::fmt::{self, Display, Formatter};
+use std::str::FromStr;
 
-use internet2::addr::NodeId;
+use internet2::addr::{InetSocketAddr, NodeAddr, NodeId};
 use internet2::presentation;
+use microservices::esb::ClientId;
 use microservices::rpc;
 use microservices::util::OptionDetails;
 use storm::p2p::AppMsg;
-use storm::{ContainerFullId, StormApp};
+use storm::{ContainerFullId, ContainerId, ContainerInfo, StormApp};
 use strict_encoding::{StrictDecode, StrictEncode};
 
-use crate::FailureCode;
+use crate::{
+    has_mime, AuthToken, ContainerFilter, ContainerSummary, FailureCode, FsckReport, GcReport,
+    ProgressEvent, QuotaUsage,
+};
 
 /// We need this wrapper type to be compatible with Storm Node having multiple message buses
 #[derive(Clone, Debug, Display, From, Api)]
@@ -28,7 +34,7 @@ pub(crate) enum BusMsg {
     #[api(type = 4)]
     #[display(inner)]
     #[from]
-    Rpc(RpcMsg),
+    Rpc(RpcEnvelope),
 
     /// Chat SUB/PUB requests
     #[api(type = 0x81)]
@@ -43,48 +49,448 @@ impl rpc::Request for BusMsg {}
 #[derive(NetworkEncode, NetworkDecode)]
 #[display(inner)]
 pub enum RpcMsg {
-    /* This will require LNP Node internals refactoring, in turn requiring microservice lib
-    refactoring to allow multiple ids on the same controller (one per message bus).
-
     /// Connect to a remote peer over Bifrost protocol and ensure that the peer
     /// supports Storm protocol.
-    #[display("connect{0}")]
+    #[display("connect({0})")]
     Connect(NodeAddr),
 
     /// Disconnect from a remote peer.
+    #[display("disconnect({0})")]
     Disconnect(NodeId),
-     */
+
+    /// List remote Bifrost peers connected to the node.
+    #[display("list_peers")]
+    ListPeers,
+
     /// Send a chat message to the remote peer. The peer must be connected.
     #[display("send_chat({0})")]
     SendChat(AddressedMsg<String>),
 
+    /// List chat messages sent to and received from the remote peer, kept by the chat daemon.
+    #[display("chat_history({0})")]
+    ChatHistory(NodeId),
+
     #[display("send({0})")]
     SendContainer(AddressedMsg<AppContainer>),
 
     #[display("receive({0})")]
     GetContainer(AddressedMsg<AppContainer>),
 
+    /// List container announcements which were not accepted automatically by the downpour policy
+    /// and are waiting for a user decision.
+    #[display("list_pending()")]
+    ListPending,
+
+    /// Accept pending container announcement and download the container.
+    #[display("accept({0})")]
+    AcceptContainer(ContainerId),
+
+    /// Decline pending container announcement, removing it from the pending list.
+    #[display("decline({0})")]
+    DeclineContainer(ContainerId),
+
+    /// Delete container from the Store database. Pinned containers can't be deleted.
+    #[display("delete({0})")]
+    DeleteContainer(ContainerId),
+
+    /// Protect container from deletion.
+    #[display("pin({0})")]
+    PinContainer(ContainerId),
+
+    /// Remove protection from deletion set with [`RpcMsg::PinContainer`].
+    #[display("unpin({0})")]
+    UnpinContainer(ContainerId),
+
+    /// Reclaim space taken by chunks not referenced by any of containers. If the flag is set,
+    /// only reports what would be reclaimed.
+    #[display("gc(dry_run: {0})")]
+    CollectGarbage(bool),
+
+    /// Check integrity of all containers and chunks from the Store database. If the peer is
+    /// given, damaged containers are re-fetched from it.
+    #[display("fsck(...)")]
+    Fsck(Option<NodeId>),
+
+    /// List containers kept in the Store database matching the filter.
+    #[display("list_containers({0})")]
+    ListContainers(ContainerFilter),
+
+    /// Request information about a container kept in the Store database.
+    #[display("container_info({0})")]
+    ContainerInfo(ContainerId),
+
+    /// Report storage used by the containers received from remote peers, per app and per peer,
+    /// together with the configured quotas.
+    #[display("list_quotas")]
+    ListQuotas,
+
+    /// Announce locally present container to the downpour seed peers.
+    #[display("seed({0})")]
+    SeedContainer(ContainerId),
+
+    /// List containers from the downpour catalog matching the filter.
+    #[display("list_catalog({0})")]
+    ListCatalog(CatalogFilter),
+
     // Responses to CLI
     // ----------------
-    #[display("progress(\"{0}\")")]
+    #[display("progress({0})")]
     #[from]
-    Progress(String),
+    Progress(ProgressEvent),
 
     #[display("success{0}")]
     Success(OptionDetails),
 
+    #[display("pending_containers(...)")]
+    PendingContainers(Vec<PendingContainer>),
+
+    #[display("catalog(...)")]
+    Catalog(Vec<CatalogEntry>),
+
+    #[display("gc_report({0})")]
+    GcReport(GcReport),
+
+    #[display("fsck_report({0})")]
+    FsckReport(FsckReport),
+
+    #[display("containers(...)")]
+    Containers(Vec<ContainerSummary>),
+
+    #[display("container_summary({0})")]
+    ContainerSummary(ContainerSummary),
+
+    #[display("quotas(...)")]
+    Quotas(Vec<QuotaUsage>),
+
+    #[display("peers(...)")]
+    Peers(Vec<PeerInfo>),
+
+    #[display("chat_records(...)")]
+    ChatRecords(Vec<ChatRecord>),
+
     #[display("failure({0:#})")]
     #[from]
     Failure(rpc::Failure<FailureCode>),
 }
 
+/// Identifier of an RPC request, unique within the client which made it
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, Default, Display, From)]
+#[derive(NetworkEncode, NetworkDecode)]
+#[display("#{0}")]
+pub struct RequestId(u64);
+
+impl RequestId {
+    /// Returns id of the request following the current one.
+    pub fn next(self) -> RequestId { RequestId(self.0.wrapping_add(1)) }
+}
+
+/// RPC message together with the id of the request it belongs to.
+///
+/// Clients put a new id into each request; the node echoes it in all progress reports and replies
+/// to the request, allowing a client to run several requests at the same time.
+#[derive(Clone, Eq, PartialEq, Hash, Debug, Display)]
+#[derive(NetworkEncode, NetworkDecode)]
+#[display("{msg} {request_id}")]
+pub struct RpcEnvelope {
+    pub request_id: RequestId,
+    /// Access token authenticating the client. Present only in requests; the node does not put
+    /// it into replies.
+    pub auth: Option<AuthToken>,
+    pub msg: RpcMsg,
+}
+
+/// Request of a specific RPC client, to which the node replies
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, Display)]
+#[derive(NetworkEncode, NetworkDecode)]
+#[display("{client_id}{request_id}")]
+pub struct ClientRequest {
+    pub client_id: ClientId,
+    pub request_id: RequestId,
+}
+
 #[derive(Clone, Eq, PartialEq, Hash, Debug, Display, From)]
 #[derive(NetworkEncode, NetworkDecode)]
 #[display(inner)]
 pub enum RadioMsg {
     #[display("recv_chat({0})")]
     #[from]
-    Received(AddressedMsg<String>),
+    Received(AddressedMsg<ChatMsg>),
+}
+
+/// Chat message received from a remote peer
+#[derive(Clone, Eq, PartialEq, Hash, Debug, Display)]
+#[derive(NetworkEncode, NetworkDecode)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(crate = "serde_crate"))]
+#[display("{protection} {text}")]
+pub struct ChatMsg {
+    /// Message text. Empty for [`ChatProtection::Unverifiable`] messages.
+    pub text: String,
+
+    /// End-to-end protection of the message.
+    pub protection: ChatProtection,
+}
+
+/// Chat message kept in the chat history
+#[derive(Clone, Eq, PartialEq, Hash, Debug, Display)]
+#[derive(NetworkEncode, NetworkDecode)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(crate = "serde_crate"))]
+#[display("{direction} {msg}")]
+pub struct ChatRecord {
+    /// Time when the message was sent or received by the node, as a UNIX timestamp.
+    pub timestamp: u64,
+
+    /// Whether the message was received from the remote peer or sent to it.
+    pub direction: ChatDirection,
+
+    /// Message text and its end-to-end protection.
+    pub msg: ChatMsg,
+}
+
+/// Direction of a chat message relative to the local node
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, Display)]
+#[derive(NetworkEncode, NetworkDecode)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", rename_all = "snake_case")
+)]
+#[network_encoding(by_value, repr = u8)]
+#[repr(u8)]
+pub enum ChatDirection {
+    /// Message was received from the remote peer.
+    #[display("<")]
+    Incoming = 0,
+
+    /// Message was sent to the remote peer by the local node.
+    #[display(">")]
+    Outgoing = 1,
+}
+
+/// End-to-end protection status of a chat message received from a remote peer
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, Display)]
+#[derive(NetworkEncode, NetworkDecode)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", rename_all = "snake_case")
+)]
+#[network_encoding(by_value, repr = u8)]
+#[repr(u8)]
+pub enum ChatProtection {
+    /// Message was encrypted by the sender to the local node key and was successfully decrypted
+    /// and authenticated.
+    #[display("[e2e]")]
+    Encrypted = 1,
+
+    /// Message was sent by the remote peer as a plain text.
+    #[display("[plain]")]
+    Plaintext = 0,
+
+    /// Message was encrypted, but the local node was unable to decrypt it or to authenticate its
+    /// origin.
+    #[display("[unverifiable]")]
+    Unverifiable = 0xFF,
+}
+
+/// Remote Bifrost peer connected to the node
+#[derive(Clone, Eq, PartialEq, Hash, Debug, Display)]
+#[derive(NetworkEncode, NetworkDecode)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(crate = "serde_crate"))]
+#[display("{remote_id} ({storm})")]
+pub struct PeerInfo {
+    /// Remote node id (public key).
+    pub remote_id: NodeId,
+
+    /// Address the node has used to connect the peer; unknown for the peers connected by the
+    /// LNP node directly or by the remote side.
+    pub addr: Option<InetSocketAddr>,
+
+    /// Support of Storm protocol by the peer.
+    pub storm: StormSupport,
+}
+
+/// Support of Storm protocol by a remote peer
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+#[derive(NetworkEncode, NetworkDecode)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", rename_all = "snake_case")
+)]
+pub enum StormSupport {
+    /// The peer has not replied to the request for the list of its Storm apps yet.
+    Unconfirmed,
+
+    /// The peer supports Storm protocol and runs the listed apps.
+    Apps(BTreeSet<StormApp>),
+}
+
+impl Display for StormSupport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            StormSupport::Unconfirmed => f.write_str("storm support unconfirmed"),
+            StormSupport::Apps(apps) => {
+                f.write_str("storm apps:")?;
+                for app in apps {
+                    write!(f, " {}", app)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Container announcement put on hold by the downpour policy
+#[derive(Clone, Eq, PartialEq, Hash, Debug, Display)]
+#[derive(NetworkEncode, NetworkDecode)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(crate = "serde_crate"))]
+#[display("{info}: {violation}")]
+pub struct PendingContainer {
+    /// Information about the announced container.
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::container_info"))]
+    pub info: ContainerInfo,
+
+    /// Remote peers which have announced the container.
+    pub remote_ids: BTreeSet<NodeId>,
+
+    /// Downpour policy rule which prevented automatic download of the container.
+    pub violation: PolicyViolation,
+}
+
+/// Reason for which the downpour policy has not accepted container announcement automatically
+#[derive(Clone, Eq, PartialEq, Hash, Debug, Display)]
+#[derive(NetworkEncode, NetworkDecode)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", rename_all = "snake_case")
+)]
+#[display(doc_comments)]
+pub enum PolicyViolation {
+    /// container size of {size} bytes exceeds {max} bytes allowed for automatic download
+    SizeExceeded { size: u64, max: u64 },
+
+    /// container MIME type `{0}` is not allowed for automatic download
+    MimeNotAllowed(String),
+
+    /// container was announced by a peer which is not trusted for automatic download
+    UntrustedPeer,
+
+    /// download would leave {available} bytes of free disk space, while at least {required} bytes
+    /// must remain
+    LowDiskSpace { available: u64, required: u64 },
+
+    /// previous download of the container has failed
+    RetrievalFailed,
+}
+
+/// Record about a container announced by remote peers, kept in the downpour catalog
+#[derive(Clone, Eq, PartialEq, Hash, Debug, Display)]
+#[derive(NetworkEncode, NetworkDecode)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(crate = "serde_crate"))]
+#[display("{info} ({status})")]
+pub struct CatalogEntry {
+    /// Information about the announced container.
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::container_info"))]
+    pub info: ContainerInfo,
+
+    /// Remote peers which have announced the container.
+    pub remote_ids: BTreeSet<NodeId>,
+
+    /// Time when the container was announced for the first time, as a UNIX timestamp.
+    pub first_seen: u64,
+
+    /// Status of the container download.
+    pub status: CatalogStatus,
+}
+
+/// Download status of a container in the downpour catalog
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, Display)]
+#[derive(NetworkEncode, NetworkDecode)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", rename_all = "snake_case")
+)]
+#[network_encoding(by_value, repr = u8)]
+#[repr(u8)]
+pub enum CatalogStatus {
+    /// Container announcement waits for a user decision.
+    #[display("pending")]
+    Pending = 0,
+
+    /// Container is being downloaded.
+    #[display("downloading")]
+    Downloading = 1,
+
+    /// Container is fully downloaded and present in the local store.
+    #[display("complete")]
+    Complete = 2,
+
+    /// Container announcement was declined by the user.
+    #[display("declined")]
+    Declined = 3,
+}
+
+/// Error parsing [`CatalogStatus`] from a string
+#[derive(Clone, Eq, PartialEq, Hash, Debug, Display, Error)]
+#[display(
+    "unknown catalog status `{0}`; possible values are pending, downloading, complete and declined"
+)]
+pub struct CatalogStatusParseError(String);
+
+impl FromStr for CatalogStatus {
+    type Err = CatalogStatusParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s.to_lowercase().as_str() {
+            "pending" => CatalogStatus::Pending,
+            "downloading" => CatalogStatus::Downloading,
+            "complete" => CatalogStatus::Complete,
+            "declined" => CatalogStatus::Declined,
+            other => return Err(CatalogStatusParseError(other.to_owned())),
+        })
+    }
+}
+
+/// Filter for the downpour catalog queries. Filter fields set to `None` match any entry.
+#[derive(Clone, Eq, PartialEq, Hash, Debug, Default)]
+#[derive(NetworkEncode, NetworkDecode)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(crate = "serde_crate"))]
+pub struct CatalogFilter {
+    /// Match only containers with the given status.
+    pub status: Option<CatalogStatus>,
+
+    /// Match only containers announced by the given peer.
+    pub remote_id: Option<NodeId>,
+
+    /// Match only containers of the given MIME type.
+    pub mime: Option<String>,
+}
+
+impl Display for CatalogFilter {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let mut filters = vec![];
+        if let Some(status) = self.status {
+            filters.push(format!("status={}", status));
+        }
+        if let Some(remote_id) = self.remote_id {
+            filters.push(format!("peer={}", remote_id));
+        }
+        if let Some(mime) = &self.mime {
+            filters.push(format!("mime={}", mime));
+        }
+        f.write_str(&filters.join(", "))
+    }
+}
+
+impl CatalogFilter {
+    /// Checks whether a catalog entry matches the filter.
+    pub fn matches(&self, entry: &CatalogEntry) -> bool {
+        self.status.map(|status| status == entry.status).unwrap_or(true)
+            && self.remote_id.map(|remote_id| entry.remote_ids.contains(&remote_id)).unwrap_or(true)
+            && self.mime.as_ref().map(|mime| has_mime(&entry.info.header, mime)).unwrap_or(true)
+    }
 }
 
 #[derive(Copy, Clone, PartialOrd, Ord, PartialEq, Eq, Hash, Debug, NetworkEncode, NetworkDecode)]
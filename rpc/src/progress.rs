@@ -0,0 +1,99 @@
+// Storm node providing distributed storage & messaging for lightning network.
+//
+// Written in 2022 by
+//     Dr. Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2022 by LNP/BP Standards Association, Switzerland.
+//
+// You should have received a copy of the MIT License along with this software.
+// If not, see <https://opensource.org/licenses/MIT>.
+
+//! Progress of the requests which take a while to complete, like container transfers.
+
+use microservices::rpc;
+use microservices::util::OptionDetails;
+use storm::ContainerId;
+
+use crate::FailureCode;
+
+/// Progress event reported by the node while processing a request.
+///
+/// [`ProgressEvent::Completed`] and [`ProgressEvent::Failed`] are not sent by the node: they are
+/// reported by the [`crate::Client`] once the final reply to the request is received.
+#[derive(Clone, Eq, PartialEq, Hash, Debug, Display)]
+#[derive(NetworkEncode, NetworkDecode)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", rename_all = "snake_case")
+)]
+#[non_exhaustive]
+pub enum ProgressEvent {
+    /// Request waits for a new transfer service instance to start
+    #[display("queued until a new transfer service instance starts")]
+    Queued,
+
+    /// Request is forwarded to a running transfer service
+    #[display("assigned to transfer service")]
+    Assigned,
+
+    /// Container is requested from the remote peer
+    #[display("requested container {0}")]
+    ContainerRequested(ContainerId),
+
+    /// Container is received from the remote peer; its chunks which are not yet present locally
+    /// are requested
+    #[display("received container {container_id}, retrieving {missing_chunks} new chunks")]
+    ContainerReceived {
+        container_id: ContainerId,
+        missing_chunks: u64,
+    },
+
+    /// Container chunk is received from the remote peer
+    #[display("received chunk {done} of {total} ({bytes} bytes)")]
+    ChunkReceived {
+        /// Number of chunks received so far
+        done: u64,
+        /// Number of chunks to receive
+        total: u64,
+        /// Size of the chunks received so far
+        bytes: u64,
+    },
+
+    /// Container or its announcement is sent to the remote peer
+    #[display("sent to the remote peer")]
+    UploadSent,
+
+    /// Information which does not fit other events
+    #[display(inner)]
+    Notice(String),
+
+    /// Request has completed
+    #[display("completed{0}")]
+    Completed(
+        #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::option_details"))]
+        OptionDetails,
+    ),
+
+    /// Request has failed
+    #[display("failed with code {0}")]
+    Failed(
+        #[cfg_attr(feature = "serde", serde(with = "crate::serde_helpers::failure_code"))]
+        rpc::FailureCode<FailureCode>,
+    ),
+}
+
+impl ProgressEvent {
+    /// Returns fraction of the work done, if known.
+    pub fn fraction(&self) -> Option<f64> {
+        match self {
+            ProgressEvent::ContainerReceived {
+                missing_chunks: 0, ..
+            } => Some(1.0),
+            ProgressEvent::ChunkReceived { total: 0, .. } => Some(1.0),
+            ProgressEvent::ChunkReceived { done, total, .. } => Some(*done as f64 / *total as f64),
+            ProgressEvent::Completed(_) => Some(1.0),
+            _ => None,
+        }
+    }
+}
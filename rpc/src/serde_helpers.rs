@@ -0,0 +1,87 @@
+// Storm node providing distributed storage & messaging for lightning network.
+//
+// Written in 2022 by
+//     Dr. Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2022 by LNP/BP Standards Association, Switzerland.
+//
+// You should have received a copy of the MIT License along with this software.
+// If not, see <https://opensource.org/licenses/MIT>.
+
+//! Serde adaptors for the foreign types which do not implement serde traits.
+
+/// Serializes [`microservices::util::OptionDetails`] as an optional string.
+pub(crate) mod option_details {
+    use microservices::util::OptionDetails;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<S>(details: &OptionDetails, serializer: S) -> Result<S::Ok, S::Error>
+    where S: Serializer {
+        details.0.serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<OptionDetails, D::Error>
+    where D: Deserializer<'de> {
+        Option::<String>::deserialize(deserializer).map(OptionDetails)
+    }
+}
+
+/// Serializes RPC failure code as its numeric value.
+pub(crate) mod failure_code {
+    use microservices::rpc;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    use crate::FailureCode;
+
+    pub fn serialize<S>(
+        code: &rpc::FailureCode<FailureCode>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        u16::from(*code).serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<rpc::FailureCode<FailureCode>, D::Error>
+    where D: Deserializer<'de> {
+        u16::deserialize(deserializer).map(rpc::FailureCode::from)
+    }
+}
+
+/// Serializes [`storm::ContainerInfo`] as a structure with `header` and `id` fields.
+pub(crate) mod container_info {
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+    use storm::{ContainerFullId, ContainerHeader, ContainerInfo};
+
+    #[derive(Serialize)]
+    #[serde(crate = "serde_crate")]
+    struct InfoRef<'a> {
+        header: &'a ContainerHeader,
+        id: &'a ContainerFullId,
+    }
+
+    #[derive(Deserialize)]
+    #[serde(crate = "serde_crate")]
+    struct Info {
+        header: ContainerHeader,
+        id: ContainerFullId,
+    }
+
+    pub fn serialize<S>(info: &ContainerInfo, serializer: S) -> Result<S::Ok, S::Error>
+    where S: Serializer {
+        InfoRef {
+            header: &info.header,
+            id: &info.id,
+        }
+        .serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<ContainerInfo, D::Error>
+    where D: Deserializer<'de> {
+        Info::deserialize(deserializer).map(|info| ContainerInfo {
+            header: info.header,
+            id: info.id,
+        })
+    }
+}
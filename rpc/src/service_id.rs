@@ -8,14 +8,17 @@
 // You should have received a copy of the MIT License along with this software.
 // If not, see <https://opensource.org/licenses/MIT>.
 
+use std::fmt::{self, Display, Formatter};
+use std::io;
 use std::str::FromStr;
 
+use amplify::Wrapper;
 use internet2::addr::NodeId;
 use lnp2p::bifrost::BifrostApp;
 use microservices::esb;
 use microservices::esb::{ClientId, ServiceName};
-use storm::StormApp;
-use strict_encoding::{strict_deserialize, strict_serialize};
+use storm::{MesgId, StormApp};
+use strict_encoding::{strict_deserialize, strict_serialize, StrictDecode, StrictEncode};
 
 pub type DaemonId = u64;
 
@@ -53,6 +56,12 @@ pub enum ServiceId {
     #[strict_encoding(value = 0x42)]
     Transfer(DaemonId),
 
+    /// Topic of the chat daemon PUB/SUB socket. Used as a destination for the messages published
+    /// by the chat daemon, such that the clients may subscribe only to a part of them.
+    #[display("chat<{0}>")]
+    #[strict_encoding(value = 0x63)] // This must be an ASCII char, see `ChatTopic` docs
+    ChatTopic(ChatTopic),
+
     #[display("other<{0}>")]
     #[strict_encoding(value = 0xFF)]
     Other(ServiceName),
@@ -66,6 +75,99 @@ impl ServiceId {
 
 impl esb::ServiceAddress for ServiceId {}
 
+/// Topic under which chat daemon publishes messages received from a remote peer.
+///
+/// ZMQ SUB sockets filter messages by a prefix of their first frame, which is the strict-encoded
+/// destination [`ServiceId`]. Since the subscription topic must be a string, chat topic is encoded
+/// as a fixed-length ASCII string, starting with the remote peer node id, optionally followed by
+/// the room (parent message or topic id). This allows subscription to all messages from a given
+/// peer using [`ChatTopic::peer_subscription`] and to messages within a specific room using
+/// [`ChatTopic::subscription`].
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub struct ChatTopic {
+    pub remote_id: NodeId,
+    pub room: Option<MesgId>,
+}
+
+impl ChatTopic {
+    const NODE_ID_LEN: usize = 66;
+    const ROOM_LEN: usize = 64;
+    const NO_ROOM: u8 = b'.';
+    const ROOM_SEPARATOR: u8 = b'/';
+
+    pub fn with(remote_id: NodeId, room: Option<MesgId>) -> Self { ChatTopic { remote_id, room } }
+
+    /// Returns subscription string matching only messages published under this topic.
+    pub fn subscription(self) -> String {
+        String::from_utf8(strict_serialize(&ServiceId::ChatTopic(self)).expect("memory encoder"))
+            .expect("chat topic is always encoded as an ASCII string")
+    }
+
+    /// Returns subscription string matching all messages from the remote peer, independently from
+    /// the room.
+    pub fn peer_subscription(remote_id: NodeId) -> String {
+        let mut subscription = ChatTopic::with(remote_id, None).subscription();
+        // Removing no-room marker
+        subscription.pop();
+        subscription
+    }
+}
+
+impl Display for ChatTopic {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        Display::fmt(&self.remote_id, f)?;
+        if let Some(room) = self.room {
+            write!(f, "/{}", room)?;
+        }
+        Ok(())
+    }
+}
+
+impl StrictEncode for ChatTopic {
+    fn strict_encode<E: io::Write>(&self, mut e: E) -> Result<usize, strict_encoding::Error> {
+        let mut data = self.remote_id.to_string().into_bytes();
+        match self.room {
+            None => data.push(Self::NO_ROOM),
+            Some(room) => {
+                data.push(Self::ROOM_SEPARATOR);
+                data.extend(room.to_string().into_bytes());
+            }
+        }
+        e.write_all(&data)?;
+        Ok(data.len())
+    }
+}
+
+impl StrictDecode for ChatTopic {
+    fn strict_decode<D: io::Read>(mut d: D) -> Result<Self, strict_encoding::Error> {
+        let mut node_id = [0u8; Self::NODE_ID_LEN];
+        d.read_exact(&mut node_id)?;
+        let remote_id = std::str::from_utf8(&node_id)?.parse().map_err(|_| {
+            strict_encoding::Error::DataIntegrityError(s!("invalid node id in chat topic"))
+        })?;
+        let mut marker = [0u8; 1];
+        d.read_exact(&mut marker)?;
+        let room = match marker[0] {
+            Self::NO_ROOM => None,
+            Self::ROOM_SEPARATOR => {
+                let mut room = [0u8; Self::ROOM_LEN];
+                d.read_exact(&mut room)?;
+                let room = std::str::from_utf8(&room)?.parse().map_err(|_| {
+                    strict_encoding::Error::DataIntegrityError(s!("invalid room id in chat topic"))
+                })?;
+                Some(MesgId::from_inner(room))
+            }
+            wrong => {
+                return Err(strict_encoding::Error::DataIntegrityError(format!(
+                    "invalid chat topic room marker {:#04x}",
+                    wrong
+                )))
+            }
+        };
+        Ok(ChatTopic { remote_id, room })
+    }
+}
+
 impl From<ServiceId> for Vec<u8> {
     fn from(daemon_id: ServiceId) -> Self {
         strict_serialize(&daemon_id).expect("Memory-based encoding does not fail")
@@ -82,3 +184,48 @@ impl From<Vec<u8>> for ServiceId {
         })
     }
 }
+
+#[cfg(test)]
+mod test {
+    use bitcoin_hashes::Hash;
+
+    use super::*;
+
+    fn node_id() -> NodeId {
+        "0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798".parse().unwrap()
+    }
+
+    fn room() -> MesgId { MesgId::from_inner(Hash::hash(b"room")) }
+
+    #[test]
+    fn chat_topic_roundtrip() {
+        for topic in [ChatTopic::with(node_id(), None), ChatTopic::with(node_id(), Some(room()))] {
+            let service_id = ServiceId::ChatTopic(topic);
+            let data: Vec<u8> = service_id.clone().into();
+            assert_eq!(ServiceId::from(data), service_id);
+        }
+    }
+
+    #[test]
+    fn chat_topic_subscription() {
+        let peer = ChatTopic::peer_subscription(node_id());
+        let no_room = ChatTopic::with(node_id(), None).subscription();
+        let in_room = ChatTopic::with(node_id(), Some(room())).subscription();
+
+        assert!(peer.is_ascii() && no_room.is_ascii() && in_room.is_ascii());
+        assert_eq!(peer.len(), 1 + ChatTopic::NODE_ID_LEN);
+        assert_eq!(no_room.len(), peer.len() + 1);
+        assert_eq!(in_room.len(), peer.len() + 1 + ChatTopic::ROOM_LEN);
+        assert!(no_room.starts_with(&peer));
+        assert!(in_room.starts_with(&peer));
+        assert!(!in_room.starts_with(&no_room));
+    }
+
+    #[test]
+    fn chat_topic_invalid_marker() {
+        let mut data = ChatTopic::with(node_id(), None).subscription().into_bytes();
+        *data.last_mut().unwrap() = b'?';
+        assert!(ChatTopic::strict_deserialize(&data[1..]).is_err());
+        assert!(matches!(ServiceId::from(data), ServiceId::Other(_)));
+    }
+}
@@ -0,0 +1,94 @@
+// Storm node providing distributed storage & messaging for lightning network.
+//
+// Written in 2022 by
+//     Dr. Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2022 by LNP/BP Standards Association, Switzerland.
+//
+// You should have received a copy of the MIT License along with this software.
+// If not, see <https://opensource.org/licenses/MIT>.
+
+//! In-memory Store daemon used by the unit tests.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc;
+use std::thread;
+
+use amplify::{Slice32, Wrapper};
+use bitcoin_hashes::Hash;
+use internet2::addr::ServiceAddr;
+use internet2::session::LocalSession;
+use internet2::{CreateUnmarshaller, SendRecvMessage, TypedEnum, Unmarshall, ZmqSocketType};
+use microservices::ZMQ_CONTEXT;
+use store_rpc::{Reply, Request};
+use storm::{Chunk, ChunkId};
+
+static NEXT_STORE: AtomicUsize = AtomicUsize::new(0);
+
+#[derive(Default)]
+struct Tables(BTreeMap<String, BTreeMap<Slice32, Chunk>>);
+
+impl Tables {
+    fn process(&mut self, request: Request) -> Reply {
+        match request {
+            Request::Use(table) => {
+                self.0.entry(table).or_default();
+                Reply::Success
+            }
+            Request::Tables => Reply::Tables(self.0.keys().cloned().collect()),
+            Request::Count(table) => Reply::Count(self.table(table).len() as u64),
+            Request::Store(req) => {
+                let chunk_id = req.chunk.chunk_id();
+                self.table(req.table).insert(req.key, req.chunk);
+                Reply::ChunkId(chunk_id)
+            }
+            Request::Retrieve(req) => match self.table(req.table).get(&req.key) {
+                Some(chunk) => Reply::Chunk(chunk.clone()),
+                None => Reply::KeyAbsent(req.key),
+            },
+            Request::Insert(_) => Reply::Success,
+            Request::ListIds(table) => Reply::Ids(
+                self.table(table).keys().map(|key| ChunkId::from_inner(key.into_inner())).collect(),
+            ),
+            Request::CheckUnknown(req) => {
+                let table = self.table(req.table);
+                Reply::Ids(
+                    req.ids
+                        .into_iter()
+                        .filter(|id| !table.contains_key(&Slice32::from(id.into_inner())))
+                        .collect::<BTreeSet<_>>(),
+                )
+            }
+        }
+    }
+
+    fn table(&mut self, table: String) -> &mut BTreeMap<Slice32, Chunk> {
+        self.0.entry(table).or_default()
+    }
+}
+
+/// Launches a new empty in-memory Store daemon in a background thread and connects to it.
+pub(crate) fn client() -> store_rpc::Client {
+    let addr =
+        ServiceAddr::Inproc(format!("test-store-{}", NEXT_STORE.fetch_add(1, Ordering::SeqCst)));
+    let (ready_tx, ready_rx) = mpsc::channel();
+    let server_addr = addr.clone();
+    thread::spawn(move || {
+        let mut session =
+            LocalSession::connect(ZmqSocketType::Rep, &server_addr, None, None, &ZMQ_CONTEXT)
+                .expect("unable to bind test store");
+        ready_tx.send(()).expect("test waits for the store");
+        let unmarshaller = Request::create_unmarshaller();
+        let mut tables = Tables::default();
+        while let Ok(raw) = session.recv_raw_message() {
+            let request = unmarshaller.unmarshall(raw.as_slice()).expect("invalid request");
+            let reply = tables.process((*request).clone());
+            if session.send_raw_message(&reply.serialize()).is_err() {
+                break;
+            }
+        }
+    });
+    ready_rx.recv().expect("test store failed to start");
+    store_rpc::Client::with(&addr).expect("unable to connect to test store")
+}
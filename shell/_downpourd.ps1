@@ -37,6 +37,11 @@ Register-ArgumentCompleter -Native -CommandName 'downpourd' -ScriptBlock {
             [CompletionResult]::new('--store-endpoint', 'store-endpoint', [CompletionResultType]::ParameterName, 'ZMQ socket for connecting storage daemon')
             [CompletionResult]::new('-C', 'C', [CompletionResultType]::ParameterName, 'ZMQ socket for chat daemon PUB/SUB API')
             [CompletionResult]::new('--chat-endpoint', 'chat-endpoint', [CompletionResultType]::ParameterName, 'ZMQ socket for chat daemon PUB/SUB API')
+            [CompletionResult]::new('--downpour-max-size', 'downpour-max-size', [CompletionResultType]::ParameterName, 'Maximal size of a container (in bytes) which can be downloaded automatically')
+            [CompletionResult]::new('--downpour-mime', 'downpour-mime', [CompletionResultType]::ParameterName, 'MIME type of containers which can be downloaded automatically')
+            [CompletionResult]::new('--downpour-trust', 'downpour-trust', [CompletionResultType]::ParameterName, 'Peer which container announcements can be downloaded automatically')
+            [CompletionResult]::new('--downpour-min-free', 'downpour-min-free', [CompletionResultType]::ParameterName, 'Minimal free disk space (in bytes) which must remain in the data directory after an automatic download')
+            [CompletionResult]::new('--downpour-seed', 'downpour-seed', [CompletionResultType]::ParameterName, 'Peer to which completed and locally seeded containers are announced')
             [CompletionResult]::new('-h', 'h', [CompletionResultType]::ParameterName, 'Print help information')
             [CompletionResult]::new('--help', 'help', [CompletionResultType]::ParameterName, 'Print help information')
             [CompletionResult]::new('-V', 'V', [CompletionResultType]::ParameterName, 'Print version information')
@@ -23,35 +23,128 @@ Register-ArgumentCompleter -Native -CommandName 'storm-cli' -ScriptBlock {
         'storm-cli' {
             [CompletionResult]::new('-S', 'S', [CompletionResultType]::ParameterName, 'ZMQ socket for connecting Storm node RPC interface')
             [CompletionResult]::new('--storm', 'storm', [CompletionResultType]::ParameterName, 'ZMQ socket for connecting Storm node RPC interface')
+            [CompletionResult]::new('-d', 'd', [CompletionResultType]::ParameterName, 'Node data directory path')
+            [CompletionResult]::new('--data-dir', 'data-dir', [CompletionResultType]::ParameterName, 'Node data directory path')
+            [CompletionResult]::new('--token', 'token', [CompletionResultType]::ParameterName, 'Access token for the node RPC interface')
             [CompletionResult]::new('--store', 'store', [CompletionResultType]::ParameterName, 'ZMQ socket for connecting storage daemon')
             [CompletionResult]::new('-C', 'C', [CompletionResultType]::ParameterName, 'ZMQ socket for chat daemon PUB/SUB API')
             [CompletionResult]::new('--chat', 'chat', [CompletionResultType]::ParameterName, 'ZMQ socket for chat daemon PUB/SUB API')
-            [CompletionResult]::new('-L', 'L', [CompletionResultType]::ParameterName, 'ZMQ socket for connecting LNP node RPC interface')
-            [CompletionResult]::new('--lnp', 'lnp', [CompletionResultType]::ParameterName, 'ZMQ socket for connecting LNP node RPC interface')
+            [CompletionResult]::new('--timeout', 'timeout', [CompletionResultType]::ParameterName, 'Maximal time (in seconds) to wait for a reply or a progress report from the node')
+            [CompletionResult]::new('--format', 'format', [CompletionResultType]::ParameterName, 'Format of the command output: human-readable `text`, or machine-readable `json` or `yaml`')
             [CompletionResult]::new('-h', 'h', [CompletionResultType]::ParameterName, 'Print help information')
             [CompletionResult]::new('--help', 'help', [CompletionResultType]::ParameterName, 'Print help information')
             [CompletionResult]::new('-V', 'V', [CompletionResultType]::ParameterName, 'Print version information')
             [CompletionResult]::new('--version', 'version', [CompletionResultType]::ParameterName, 'Print version information')
             [CompletionResult]::new('-v', 'v', [CompletionResultType]::ParameterName, 'Set verbosity level')
             [CompletionResult]::new('--verbose', 'verbose', [CompletionResultType]::ParameterName, 'Set verbosity level')
+            [CompletionResult]::new('peers', 'peers', [CompletionResultType]::ParameterValue, 'List remote peers connected to the node')
+            [CompletionResult]::new('connect', 'connect', [CompletionResultType]::ParameterValue, 'Connect to a remote peer over Bifrost protocol')
+            [CompletionResult]::new('disconnect', 'disconnect', [CompletionResultType]::ParameterValue, 'Disconnect from a remote peer')
+            [CompletionResult]::new('chat', 'chat', [CompletionResultType]::ParameterValue, 'Interactive chat session with a remote peer')
             [CompletionResult]::new('chat-listen', 'chat-listen', [CompletionResultType]::ParameterValue, 'Listen for the incoming chat messages from a remote peer')
             [CompletionResult]::new('chat-send', 'chat-send', [CompletionResultType]::ParameterValue, 'Send typed-in messages to another peer')
-            [CompletionResult]::new('containerize', 'containerize', [CompletionResultType]::ParameterValue, 'Convert on-disk file into a container in the Store database')
-            [CompletionResult]::new('assemble', 'assemble', [CompletionResultType]::ParameterValue, 'Assemble a file from a Store database-present container and save as a file')
+            [CompletionResult]::new('containerize', 'containerize', [CompletionResultType]::ParameterValue, 'Convert on-disk file or directory into a container in the Store database')
+            [CompletionResult]::new('assemble', 'assemble', [CompletionResultType]::ParameterValue, 'Assemble a file or a directory from a Store database-present container and save it')
             [CompletionResult]::new('upload', 'upload', [CompletionResultType]::ParameterValue, 'Upload given container to the remote peer')
             [CompletionResult]::new('download', 'download', [CompletionResultType]::ParameterValue, 'Download the container from the remote peer')
+            [CompletionResult]::new('ls', 'ls', [CompletionResultType]::ParameterValue, 'List containers kept in the Store database')
+            [CompletionResult]::new('inspect', 'inspect', [CompletionResultType]::ParameterValue, 'Show information about a container kept in the Store database')
+            [CompletionResult]::new('quotas', 'quotas', [CompletionResultType]::ParameterValue, 'Show storage used by the containers received from remote peers, per app and per peer, together with the configured quotas')
+            [CompletionResult]::new('delete', 'delete', [CompletionResultType]::ParameterValue, 'Delete a container from the Store database. Its chunks are reclaimed by the next garbage collection')
+            [CompletionResult]::new('pin', 'pin', [CompletionResultType]::ParameterValue, 'Protect a container from deletion')
+            [CompletionResult]::new('unpin', 'unpin', [CompletionResultType]::ParameterValue, 'Remove protection from deletion from a container')
+            [CompletionResult]::new('gc', 'gc', [CompletionResultType]::ParameterValue, 'Reclaim space taken by chunks which are not used by any of containers')
+            [CompletionResult]::new('fsck', 'fsck', [CompletionResultType]::ParameterValue, 'Check integrity of the containers and chunks kept in the Store database')
+            [CompletionResult]::new('seed', 'seed', [CompletionResultType]::ParameterValue, 'Announce a container from the Store database to the downpour seed peers')
+            [CompletionResult]::new('catalog', 'catalog', [CompletionResultType]::ParameterValue, 'Browse catalog of containers announced by remote peers')
+            [CompletionResult]::new('pending', 'pending', [CompletionResultType]::ParameterValue, 'List container announcements which were not accepted automatically by the downpour policy and are waiting for a decision')
+            [CompletionResult]::new('accept', 'accept', [CompletionResultType]::ParameterValue, 'Accept pending container announcement and download the container')
+            [CompletionResult]::new('decline', 'decline', [CompletionResultType]::ParameterValue, 'Decline pending container announcement')
             [CompletionResult]::new('help', 'help', [CompletionResultType]::ParameterValue, 'Print this message or the help of the given subcommand(s)')
             break
         }
+        'storm-cli;peers' {
+            [CompletionResult]::new('-S', 'S', [CompletionResultType]::ParameterName, 'ZMQ socket for connecting Storm node RPC interface')
+            [CompletionResult]::new('--storm', 'storm', [CompletionResultType]::ParameterName, 'ZMQ socket for connecting Storm node RPC interface')
+            [CompletionResult]::new('-d', 'd', [CompletionResultType]::ParameterName, 'Node data directory path')
+            [CompletionResult]::new('--data-dir', 'data-dir', [CompletionResultType]::ParameterName, 'Node data directory path')
+            [CompletionResult]::new('--token', 'token', [CompletionResultType]::ParameterName, 'Access token for the node RPC interface')
+            [CompletionResult]::new('--store', 'store', [CompletionResultType]::ParameterName, 'ZMQ socket for connecting storage daemon')
+            [CompletionResult]::new('-C', 'C', [CompletionResultType]::ParameterName, 'ZMQ socket for chat daemon PUB/SUB API')
+            [CompletionResult]::new('--chat', 'chat', [CompletionResultType]::ParameterName, 'ZMQ socket for chat daemon PUB/SUB API')
+            [CompletionResult]::new('--timeout', 'timeout', [CompletionResultType]::ParameterName, 'Maximal time (in seconds) to wait for a reply or a progress report from the node')
+            [CompletionResult]::new('--format', 'format', [CompletionResultType]::ParameterName, 'Format of the command output: human-readable `text`, or machine-readable `json` or `yaml`')
+            [CompletionResult]::new('-h', 'h', [CompletionResultType]::ParameterName, 'Print help information')
+            [CompletionResult]::new('--help', 'help', [CompletionResultType]::ParameterName, 'Print help information')
+            [CompletionResult]::new('-v', 'v', [CompletionResultType]::ParameterName, 'Set verbosity level')
+            [CompletionResult]::new('--verbose', 'verbose', [CompletionResultType]::ParameterName, 'Set verbosity level')
+            break
+        }
+        'storm-cli;connect' {
+            [CompletionResult]::new('-S', 'S', [CompletionResultType]::ParameterName, 'ZMQ socket for connecting Storm node RPC interface')
+            [CompletionResult]::new('--storm', 'storm', [CompletionResultType]::ParameterName, 'ZMQ socket for connecting Storm node RPC interface')
+            [CompletionResult]::new('-d', 'd', [CompletionResultType]::ParameterName, 'Node data directory path')
+            [CompletionResult]::new('--data-dir', 'data-dir', [CompletionResultType]::ParameterName, 'Node data directory path')
+            [CompletionResult]::new('--token', 'token', [CompletionResultType]::ParameterName, 'Access token for the node RPC interface')
+            [CompletionResult]::new('--store', 'store', [CompletionResultType]::ParameterName, 'ZMQ socket for connecting storage daemon')
+            [CompletionResult]::new('-C', 'C', [CompletionResultType]::ParameterName, 'ZMQ socket for chat daemon PUB/SUB API')
+            [CompletionResult]::new('--chat', 'chat', [CompletionResultType]::ParameterName, 'ZMQ socket for chat daemon PUB/SUB API')
+            [CompletionResult]::new('--timeout', 'timeout', [CompletionResultType]::ParameterName, 'Maximal time (in seconds) to wait for a reply or a progress report from the node')
+            [CompletionResult]::new('--format', 'format', [CompletionResultType]::ParameterName, 'Format of the command output: human-readable `text`, or machine-readable `json` or `yaml`')
+            [CompletionResult]::new('-h', 'h', [CompletionResultType]::ParameterName, 'Print help information')
+            [CompletionResult]::new('--help', 'help', [CompletionResultType]::ParameterName, 'Print help information')
+            [CompletionResult]::new('-v', 'v', [CompletionResultType]::ParameterName, 'Set verbosity level')
+            [CompletionResult]::new('--verbose', 'verbose', [CompletionResultType]::ParameterName, 'Set verbosity level')
+            break
+        }
+        'storm-cli;disconnect' {
+            [CompletionResult]::new('-S', 'S', [CompletionResultType]::ParameterName, 'ZMQ socket for connecting Storm node RPC interface')
+            [CompletionResult]::new('--storm', 'storm', [CompletionResultType]::ParameterName, 'ZMQ socket for connecting Storm node RPC interface')
+            [CompletionResult]::new('-d', 'd', [CompletionResultType]::ParameterName, 'Node data directory path')
+            [CompletionResult]::new('--data-dir', 'data-dir', [CompletionResultType]::ParameterName, 'Node data directory path')
+            [CompletionResult]::new('--token', 'token', [CompletionResultType]::ParameterName, 'Access token for the node RPC interface')
+            [CompletionResult]::new('--store', 'store', [CompletionResultType]::ParameterName, 'ZMQ socket for connecting storage daemon')
+            [CompletionResult]::new('-C', 'C', [CompletionResultType]::ParameterName, 'ZMQ socket for chat daemon PUB/SUB API')
+            [CompletionResult]::new('--chat', 'chat', [CompletionResultType]::ParameterName, 'ZMQ socket for chat daemon PUB/SUB API')
+            [CompletionResult]::new('--timeout', 'timeout', [CompletionResultType]::ParameterName, 'Maximal time (in seconds) to wait for a reply or a progress report from the node')
+            [CompletionResult]::new('--format', 'format', [CompletionResultType]::ParameterName, 'Format of the command output: human-readable `text`, or machine-readable `json` or `yaml`')
+            [CompletionResult]::new('-h', 'h', [CompletionResultType]::ParameterName, 'Print help information')
+            [CompletionResult]::new('--help', 'help', [CompletionResultType]::ParameterName, 'Print help information')
+            [CompletionResult]::new('-v', 'v', [CompletionResultType]::ParameterName, 'Set verbosity level')
+            [CompletionResult]::new('--verbose', 'verbose', [CompletionResultType]::ParameterName, 'Set verbosity level')
+            break
+        }
+        'storm-cli;chat' {
+            [CompletionResult]::new('--connect', 'connect', [CompletionResultType]::ParameterName, 'Remote node address to force connection (re)establishment')
+            [CompletionResult]::new('--history', 'history', [CompletionResultType]::ParameterName, 'Number of the latest messages from the chat history to show')
+            [CompletionResult]::new('-S', 'S', [CompletionResultType]::ParameterName, 'ZMQ socket for connecting Storm node RPC interface')
+            [CompletionResult]::new('--storm', 'storm', [CompletionResultType]::ParameterName, 'ZMQ socket for connecting Storm node RPC interface')
+            [CompletionResult]::new('-d', 'd', [CompletionResultType]::ParameterName, 'Node data directory path')
+            [CompletionResult]::new('--data-dir', 'data-dir', [CompletionResultType]::ParameterName, 'Node data directory path')
+            [CompletionResult]::new('--token', 'token', [CompletionResultType]::ParameterName, 'Access token for the node RPC interface')
+            [CompletionResult]::new('--store', 'store', [CompletionResultType]::ParameterName, 'ZMQ socket for connecting storage daemon')
+            [CompletionResult]::new('-C', 'C', [CompletionResultType]::ParameterName, 'ZMQ socket for chat daemon PUB/SUB API')
+            [CompletionResult]::new('--chat', 'chat', [CompletionResultType]::ParameterName, 'ZMQ socket for chat daemon PUB/SUB API')
+            [CompletionResult]::new('--timeout', 'timeout', [CompletionResultType]::ParameterName, 'Maximal time (in seconds) to wait for a reply or a progress report from the node')
+            [CompletionResult]::new('--format', 'format', [CompletionResultType]::ParameterName, 'Format of the command output: human-readable `text`, or machine-readable `json` or `yaml`')
+            [CompletionResult]::new('-h', 'h', [CompletionResultType]::ParameterName, 'Print help information')
+            [CompletionResult]::new('--help', 'help', [CompletionResultType]::ParameterName, 'Print help information')
+            [CompletionResult]::new('-v', 'v', [CompletionResultType]::ParameterName, 'Set verbosity level')
+            [CompletionResult]::new('--verbose', 'verbose', [CompletionResultType]::ParameterName, 'Set verbosity level')
+            break
+        }
         'storm-cli;chat-listen' {
             [CompletionResult]::new('--connect', 'connect', [CompletionResultType]::ParameterName, 'Remote node address to force connection (re)establishment')
             [CompletionResult]::new('-S', 'S', [CompletionResultType]::ParameterName, 'ZMQ socket for connecting Storm node RPC interface')
             [CompletionResult]::new('--storm', 'storm', [CompletionResultType]::ParameterName, 'ZMQ socket for connecting Storm node RPC interface')
+            [CompletionResult]::new('-d', 'd', [CompletionResultType]::ParameterName, 'Node data directory path')
+            [CompletionResult]::new('--data-dir', 'data-dir', [CompletionResultType]::ParameterName, 'Node data directory path')
+            [CompletionResult]::new('--token', 'token', [CompletionResultType]::ParameterName, 'Access token for the node RPC interface')
             [CompletionResult]::new('--store', 'store', [CompletionResultType]::ParameterName, 'ZMQ socket for connecting storage daemon')
             [CompletionResult]::new('-C', 'C', [CompletionResultType]::ParameterName, 'ZMQ socket for chat daemon PUB/SUB API')
             [CompletionResult]::new('--chat', 'chat', [CompletionResultType]::ParameterName, 'ZMQ socket for chat daemon PUB/SUB API')
-            [CompletionResult]::new('-L', 'L', [CompletionResultType]::ParameterName, 'ZMQ socket for connecting LNP node RPC interface')
-            [CompletionResult]::new('--lnp', 'lnp', [CompletionResultType]::ParameterName, 'ZMQ socket for connecting LNP node RPC interface')
+            [CompletionResult]::new('--timeout', 'timeout', [CompletionResultType]::ParameterName, 'Maximal time (in seconds) to wait for a reply or a progress report from the node')
+            [CompletionResult]::new('--format', 'format', [CompletionResultType]::ParameterName, 'Format of the command output: human-readable `text`, or machine-readable `json` or `yaml`')
             [CompletionResult]::new('-h', 'h', [CompletionResultType]::ParameterName, 'Print help information')
             [CompletionResult]::new('--help', 'help', [CompletionResultType]::ParameterName, 'Print help information')
             [CompletionResult]::new('-v', 'v', [CompletionResultType]::ParameterName, 'Set verbosity level')
@@ -62,11 +155,14 @@ Register-ArgumentCompleter -Native -CommandName 'storm-cli' -ScriptBlock {
             [CompletionResult]::new('--connect', 'connect', [CompletionResultType]::ParameterName, 'Remote node address to force connection (re)establishment')
             [CompletionResult]::new('-S', 'S', [CompletionResultType]::ParameterName, 'ZMQ socket for connecting Storm node RPC interface')
             [CompletionResult]::new('--storm', 'storm', [CompletionResultType]::ParameterName, 'ZMQ socket for connecting Storm node RPC interface')
+            [CompletionResult]::new('-d', 'd', [CompletionResultType]::ParameterName, 'Node data directory path')
+            [CompletionResult]::new('--data-dir', 'data-dir', [CompletionResultType]::ParameterName, 'Node data directory path')
+            [CompletionResult]::new('--token', 'token', [CompletionResultType]::ParameterName, 'Access token for the node RPC interface')
             [CompletionResult]::new('--store', 'store', [CompletionResultType]::ParameterName, 'ZMQ socket for connecting storage daemon')
             [CompletionResult]::new('-C', 'C', [CompletionResultType]::ParameterName, 'ZMQ socket for chat daemon PUB/SUB API')
             [CompletionResult]::new('--chat', 'chat', [CompletionResultType]::ParameterName, 'ZMQ socket for chat daemon PUB/SUB API')
-            [CompletionResult]::new('-L', 'L', [CompletionResultType]::ParameterName, 'ZMQ socket for connecting LNP node RPC interface')
-            [CompletionResult]::new('--lnp', 'lnp', [CompletionResultType]::ParameterName, 'ZMQ socket for connecting LNP node RPC interface')
+            [CompletionResult]::new('--timeout', 'timeout', [CompletionResultType]::ParameterName, 'Maximal time (in seconds) to wait for a reply or a progress report from the node')
+            [CompletionResult]::new('--format', 'format', [CompletionResultType]::ParameterName, 'Format of the command output: human-readable `text`, or machine-readable `json` or `yaml`')
             [CompletionResult]::new('-h', 'h', [CompletionResultType]::ParameterName, 'Print help information')
             [CompletionResult]::new('--help', 'help', [CompletionResultType]::ParameterName, 'Print help information')
             [CompletionResult]::new('-v', 'v', [CompletionResultType]::ParameterName, 'Set verbosity level')
@@ -74,15 +170,24 @@ Register-ArgumentCompleter -Native -CommandName 'storm-cli' -ScriptBlock {
             break
         }
         'storm-cli;containerize' {
-            [CompletionResult]::new('-m', 'm', [CompletionResultType]::ParameterName, 'MIME file type')
-            [CompletionResult]::new('--mime', 'mime', [CompletionResultType]::ParameterName, 'MIME file type')
+            [CompletionResult]::new('-m', 'm', [CompletionResultType]::ParameterName, 'MIME file type. Ignored for directories')
+            [CompletionResult]::new('--mime', 'mime', [CompletionResultType]::ParameterName, 'MIME file type. Ignored for directories')
+            [CompletionResult]::new('-c', 'c', [CompletionResultType]::ParameterName, 'Chunking strategy: `fixed`, or content-defined `cdc` with default chunk sizes, or `cdc-MIN-AVG-MAX` with sizes given in bytes')
+            [CompletionResult]::new('--chunking', 'chunking', [CompletionResultType]::ParameterName, 'Chunking strategy: `fixed`, or content-defined `cdc` with default chunk sizes, or `cdc-MIN-AVG-MAX` with sizes given in bytes')
+            [CompletionResult]::new('-z', 'z', [CompletionResultType]::ParameterName, 'Compression of the chunk data: `none` or `deflate`')
+            [CompletionResult]::new('--compression', 'compression', [CompletionResultType]::ParameterName, 'Compression of the chunk data: `none` or `deflate`')
+            [CompletionResult]::new('--encrypt-to', 'encrypt-to', [CompletionResultType]::ParameterName, 'Encrypt the container to the given node; may be repeated to encrypt to multiple nodes')
             [CompletionResult]::new('-S', 'S', [CompletionResultType]::ParameterName, 'ZMQ socket for connecting Storm node RPC interface')
             [CompletionResult]::new('--storm', 'storm', [CompletionResultType]::ParameterName, 'ZMQ socket for connecting Storm node RPC interface')
+            [CompletionResult]::new('-d', 'd', [CompletionResultType]::ParameterName, 'Node data directory path')
+            [CompletionResult]::new('--data-dir', 'data-dir', [CompletionResultType]::ParameterName, 'Node data directory path')
+            [CompletionResult]::new('--token', 'token', [CompletionResultType]::ParameterName, 'Access token for the node RPC interface')
             [CompletionResult]::new('--store', 'store', [CompletionResultType]::ParameterName, 'ZMQ socket for connecting storage daemon')
             [CompletionResult]::new('-C', 'C', [CompletionResultType]::ParameterName, 'ZMQ socket for chat daemon PUB/SUB API')
             [CompletionResult]::new('--chat', 'chat', [CompletionResultType]::ParameterName, 'ZMQ socket for chat daemon PUB/SUB API')
-            [CompletionResult]::new('-L', 'L', [CompletionResultType]::ParameterName, 'ZMQ socket for connecting LNP node RPC interface')
-            [CompletionResult]::new('--lnp', 'lnp', [CompletionResultType]::ParameterName, 'ZMQ socket for connecting LNP node RPC interface')
+            [CompletionResult]::new('--timeout', 'timeout', [CompletionResultType]::ParameterName, 'Maximal time (in seconds) to wait for a reply or a progress report from the node')
+            [CompletionResult]::new('--format', 'format', [CompletionResultType]::ParameterName, 'Format of the command output: human-readable `text`, or machine-readable `json` or `yaml`')
+            [CompletionResult]::new('--seed', 'seed', [CompletionResultType]::ParameterName, 'Announce the container to the downpour seed peers once containerized')
             [CompletionResult]::new('-h', 'h', [CompletionResultType]::ParameterName, 'Print help information')
             [CompletionResult]::new('--help', 'help', [CompletionResultType]::ParameterName, 'Print help information')
             [CompletionResult]::new('-v', 'v', [CompletionResultType]::ParameterName, 'Set verbosity level')
@@ -90,13 +195,18 @@ Register-ArgumentCompleter -Native -CommandName 'storm-cli' -ScriptBlock {
             break
         }
         'storm-cli;assemble' {
+            [CompletionResult]::new('-k', 'k', [CompletionResultType]::ParameterName, 'File with the node private key, required for assembling encrypted containers')
+            [CompletionResult]::new('--node-key', 'node-key', [CompletionResultType]::ParameterName, 'File with the node private key, required for assembling encrypted containers')
             [CompletionResult]::new('-S', 'S', [CompletionResultType]::ParameterName, 'ZMQ socket for connecting Storm node RPC interface')
             [CompletionResult]::new('--storm', 'storm', [CompletionResultType]::ParameterName, 'ZMQ socket for connecting Storm node RPC interface')
+            [CompletionResult]::new('-d', 'd', [CompletionResultType]::ParameterName, 'Node data directory path')
+            [CompletionResult]::new('--data-dir', 'data-dir', [CompletionResultType]::ParameterName, 'Node data directory path')
+            [CompletionResult]::new('--token', 'token', [CompletionResultType]::ParameterName, 'Access token for the node RPC interface')
             [CompletionResult]::new('--store', 'store', [CompletionResultType]::ParameterName, 'ZMQ socket for connecting storage daemon')
             [CompletionResult]::new('-C', 'C', [CompletionResultType]::ParameterName, 'ZMQ socket for chat daemon PUB/SUB API')
             [CompletionResult]::new('--chat', 'chat', [CompletionResultType]::ParameterName, 'ZMQ socket for chat daemon PUB/SUB API')
-            [CompletionResult]::new('-L', 'L', [CompletionResultType]::ParameterName, 'ZMQ socket for connecting LNP node RPC interface')
-            [CompletionResult]::new('--lnp', 'lnp', [CompletionResultType]::ParameterName, 'ZMQ socket for connecting LNP node RPC interface')
+            [CompletionResult]::new('--timeout', 'timeout', [CompletionResultType]::ParameterName, 'Maximal time (in seconds) to wait for a reply or a progress report from the node')
+            [CompletionResult]::new('--format', 'format', [CompletionResultType]::ParameterName, 'Format of the command output: human-readable `text`, or machine-readable `json` or `yaml`')
             [CompletionResult]::new('-h', 'h', [CompletionResultType]::ParameterName, 'Print help information')
             [CompletionResult]::new('--help', 'help', [CompletionResultType]::ParameterName, 'Print help information')
             [CompletionResult]::new('-v', 'v', [CompletionResultType]::ParameterName, 'Set verbosity level')
@@ -107,11 +217,14 @@ Register-ArgumentCompleter -Native -CommandName 'storm-cli' -ScriptBlock {
             [CompletionResult]::new('--connect', 'connect', [CompletionResultType]::ParameterName, 'Remote node address to force connection (re)establishment')
             [CompletionResult]::new('-S', 'S', [CompletionResultType]::ParameterName, 'ZMQ socket for connecting Storm node RPC interface')
             [CompletionResult]::new('--storm', 'storm', [CompletionResultType]::ParameterName, 'ZMQ socket for connecting Storm node RPC interface')
+            [CompletionResult]::new('-d', 'd', [CompletionResultType]::ParameterName, 'Node data directory path')
+            [CompletionResult]::new('--data-dir', 'data-dir', [CompletionResultType]::ParameterName, 'Node data directory path')
+            [CompletionResult]::new('--token', 'token', [CompletionResultType]::ParameterName, 'Access token for the node RPC interface')
             [CompletionResult]::new('--store', 'store', [CompletionResultType]::ParameterName, 'ZMQ socket for connecting storage daemon')
             [CompletionResult]::new('-C', 'C', [CompletionResultType]::ParameterName, 'ZMQ socket for chat daemon PUB/SUB API')
             [CompletionResult]::new('--chat', 'chat', [CompletionResultType]::ParameterName, 'ZMQ socket for chat daemon PUB/SUB API')
-            [CompletionResult]::new('-L', 'L', [CompletionResultType]::ParameterName, 'ZMQ socket for connecting LNP node RPC interface')
-            [CompletionResult]::new('--lnp', 'lnp', [CompletionResultType]::ParameterName, 'ZMQ socket for connecting LNP node RPC interface')
+            [CompletionResult]::new('--timeout', 'timeout', [CompletionResultType]::ParameterName, 'Maximal time (in seconds) to wait for a reply or a progress report from the node')
+            [CompletionResult]::new('--format', 'format', [CompletionResultType]::ParameterName, 'Format of the command output: human-readable `text`, or machine-readable `json` or `yaml`')
             [CompletionResult]::new('-h', 'h', [CompletionResultType]::ParameterName, 'Print help information')
             [CompletionResult]::new('--help', 'help', [CompletionResultType]::ParameterName, 'Print help information')
             [CompletionResult]::new('-v', 'v', [CompletionResultType]::ParameterName, 'Set verbosity level')
@@ -122,11 +235,250 @@ Register-ArgumentCompleter -Native -CommandName 'storm-cli' -ScriptBlock {
             [CompletionResult]::new('--connect', 'connect', [CompletionResultType]::ParameterName, 'Remote node address to force connection (re)establishment')
             [CompletionResult]::new('-S', 'S', [CompletionResultType]::ParameterName, 'ZMQ socket for connecting Storm node RPC interface')
             [CompletionResult]::new('--storm', 'storm', [CompletionResultType]::ParameterName, 'ZMQ socket for connecting Storm node RPC interface')
+            [CompletionResult]::new('-d', 'd', [CompletionResultType]::ParameterName, 'Node data directory path')
+            [CompletionResult]::new('--data-dir', 'data-dir', [CompletionResultType]::ParameterName, 'Node data directory path')
+            [CompletionResult]::new('--token', 'token', [CompletionResultType]::ParameterName, 'Access token for the node RPC interface')
+            [CompletionResult]::new('--store', 'store', [CompletionResultType]::ParameterName, 'ZMQ socket for connecting storage daemon')
+            [CompletionResult]::new('-C', 'C', [CompletionResultType]::ParameterName, 'ZMQ socket for chat daemon PUB/SUB API')
+            [CompletionResult]::new('--chat', 'chat', [CompletionResultType]::ParameterName, 'ZMQ socket for chat daemon PUB/SUB API')
+            [CompletionResult]::new('--timeout', 'timeout', [CompletionResultType]::ParameterName, 'Maximal time (in seconds) to wait for a reply or a progress report from the node')
+            [CompletionResult]::new('--format', 'format', [CompletionResultType]::ParameterName, 'Format of the command output: human-readable `text`, or machine-readable `json` or `yaml`')
+            [CompletionResult]::new('-h', 'h', [CompletionResultType]::ParameterName, 'Print help information')
+            [CompletionResult]::new('--help', 'help', [CompletionResultType]::ParameterName, 'Print help information')
+            [CompletionResult]::new('-v', 'v', [CompletionResultType]::ParameterName, 'Set verbosity level')
+            [CompletionResult]::new('--verbose', 'verbose', [CompletionResultType]::ParameterName, 'Set verbosity level')
+            break
+        }
+        'storm-cli;ls' {
+            [CompletionResult]::new('-m', 'm', [CompletionResultType]::ParameterName, 'Show only containers of the given MIME type')
+            [CompletionResult]::new('--mime', 'mime', [CompletionResultType]::ParameterName, 'Show only containers of the given MIME type')
+            [CompletionResult]::new('--min-size', 'min-size', [CompletionResultType]::ParameterName, 'Show only containers of at least the given size, in bytes')
+            [CompletionResult]::new('--max-size', 'max-size', [CompletionResultType]::ParameterName, 'Show only containers of at most the given size, in bytes')
+            [CompletionResult]::new('--since', 'since', [CompletionResultType]::ParameterName, 'Show only containers stored at or after the given UNIX time')
+            [CompletionResult]::new('--until', 'until', [CompletionResultType]::ParameterName, 'Show only containers stored before the given UNIX time')
+            [CompletionResult]::new('-S', 'S', [CompletionResultType]::ParameterName, 'ZMQ socket for connecting Storm node RPC interface')
+            [CompletionResult]::new('--storm', 'storm', [CompletionResultType]::ParameterName, 'ZMQ socket for connecting Storm node RPC interface')
+            [CompletionResult]::new('-d', 'd', [CompletionResultType]::ParameterName, 'Node data directory path')
+            [CompletionResult]::new('--data-dir', 'data-dir', [CompletionResultType]::ParameterName, 'Node data directory path')
+            [CompletionResult]::new('--token', 'token', [CompletionResultType]::ParameterName, 'Access token for the node RPC interface')
+            [CompletionResult]::new('--store', 'store', [CompletionResultType]::ParameterName, 'ZMQ socket for connecting storage daemon')
+            [CompletionResult]::new('-C', 'C', [CompletionResultType]::ParameterName, 'ZMQ socket for chat daemon PUB/SUB API')
+            [CompletionResult]::new('--chat', 'chat', [CompletionResultType]::ParameterName, 'ZMQ socket for chat daemon PUB/SUB API')
+            [CompletionResult]::new('--timeout', 'timeout', [CompletionResultType]::ParameterName, 'Maximal time (in seconds) to wait for a reply or a progress report from the node')
+            [CompletionResult]::new('--format', 'format', [CompletionResultType]::ParameterName, 'Format of the command output: human-readable `text`, or machine-readable `json` or `yaml`')
+            [CompletionResult]::new('-h', 'h', [CompletionResultType]::ParameterName, 'Print help information')
+            [CompletionResult]::new('--help', 'help', [CompletionResultType]::ParameterName, 'Print help information')
+            [CompletionResult]::new('-v', 'v', [CompletionResultType]::ParameterName, 'Set verbosity level')
+            [CompletionResult]::new('--verbose', 'verbose', [CompletionResultType]::ParameterName, 'Set verbosity level')
+            break
+        }
+        'storm-cli;inspect' {
+            [CompletionResult]::new('-S', 'S', [CompletionResultType]::ParameterName, 'ZMQ socket for connecting Storm node RPC interface')
+            [CompletionResult]::new('--storm', 'storm', [CompletionResultType]::ParameterName, 'ZMQ socket for connecting Storm node RPC interface')
+            [CompletionResult]::new('-d', 'd', [CompletionResultType]::ParameterName, 'Node data directory path')
+            [CompletionResult]::new('--data-dir', 'data-dir', [CompletionResultType]::ParameterName, 'Node data directory path')
+            [CompletionResult]::new('--token', 'token', [CompletionResultType]::ParameterName, 'Access token for the node RPC interface')
+            [CompletionResult]::new('--store', 'store', [CompletionResultType]::ParameterName, 'ZMQ socket for connecting storage daemon')
+            [CompletionResult]::new('-C', 'C', [CompletionResultType]::ParameterName, 'ZMQ socket for chat daemon PUB/SUB API')
+            [CompletionResult]::new('--chat', 'chat', [CompletionResultType]::ParameterName, 'ZMQ socket for chat daemon PUB/SUB API')
+            [CompletionResult]::new('--timeout', 'timeout', [CompletionResultType]::ParameterName, 'Maximal time (in seconds) to wait for a reply or a progress report from the node')
+            [CompletionResult]::new('--format', 'format', [CompletionResultType]::ParameterName, 'Format of the command output: human-readable `text`, or machine-readable `json` or `yaml`')
+            [CompletionResult]::new('-h', 'h', [CompletionResultType]::ParameterName, 'Print help information')
+            [CompletionResult]::new('--help', 'help', [CompletionResultType]::ParameterName, 'Print help information')
+            [CompletionResult]::new('-v', 'v', [CompletionResultType]::ParameterName, 'Set verbosity level')
+            [CompletionResult]::new('--verbose', 'verbose', [CompletionResultType]::ParameterName, 'Set verbosity level')
+            break
+        }
+        'storm-cli;quotas' {
+            [CompletionResult]::new('-S', 'S', [CompletionResultType]::ParameterName, 'ZMQ socket for connecting Storm node RPC interface')
+            [CompletionResult]::new('--storm', 'storm', [CompletionResultType]::ParameterName, 'ZMQ socket for connecting Storm node RPC interface')
+            [CompletionResult]::new('-d', 'd', [CompletionResultType]::ParameterName, 'Node data directory path')
+            [CompletionResult]::new('--data-dir', 'data-dir', [CompletionResultType]::ParameterName, 'Node data directory path')
+            [CompletionResult]::new('--token', 'token', [CompletionResultType]::ParameterName, 'Access token for the node RPC interface')
+            [CompletionResult]::new('--store', 'store', [CompletionResultType]::ParameterName, 'ZMQ socket for connecting storage daemon')
+            [CompletionResult]::new('-C', 'C', [CompletionResultType]::ParameterName, 'ZMQ socket for chat daemon PUB/SUB API')
+            [CompletionResult]::new('--chat', 'chat', [CompletionResultType]::ParameterName, 'ZMQ socket for chat daemon PUB/SUB API')
+            [CompletionResult]::new('--timeout', 'timeout', [CompletionResultType]::ParameterName, 'Maximal time (in seconds) to wait for a reply or a progress report from the node')
+            [CompletionResult]::new('--format', 'format', [CompletionResultType]::ParameterName, 'Format of the command output: human-readable `text`, or machine-readable `json` or `yaml`')
+            [CompletionResult]::new('-h', 'h', [CompletionResultType]::ParameterName, 'Print help information')
+            [CompletionResult]::new('--help', 'help', [CompletionResultType]::ParameterName, 'Print help information')
+            [CompletionResult]::new('-v', 'v', [CompletionResultType]::ParameterName, 'Set verbosity level')
+            [CompletionResult]::new('--verbose', 'verbose', [CompletionResultType]::ParameterName, 'Set verbosity level')
+            break
+        }
+        'storm-cli;delete' {
+            [CompletionResult]::new('-S', 'S', [CompletionResultType]::ParameterName, 'ZMQ socket for connecting Storm node RPC interface')
+            [CompletionResult]::new('--storm', 'storm', [CompletionResultType]::ParameterName, 'ZMQ socket for connecting Storm node RPC interface')
+            [CompletionResult]::new('-d', 'd', [CompletionResultType]::ParameterName, 'Node data directory path')
+            [CompletionResult]::new('--data-dir', 'data-dir', [CompletionResultType]::ParameterName, 'Node data directory path')
+            [CompletionResult]::new('--token', 'token', [CompletionResultType]::ParameterName, 'Access token for the node RPC interface')
+            [CompletionResult]::new('--store', 'store', [CompletionResultType]::ParameterName, 'ZMQ socket for connecting storage daemon')
+            [CompletionResult]::new('-C', 'C', [CompletionResultType]::ParameterName, 'ZMQ socket for chat daemon PUB/SUB API')
+            [CompletionResult]::new('--chat', 'chat', [CompletionResultType]::ParameterName, 'ZMQ socket for chat daemon PUB/SUB API')
+            [CompletionResult]::new('--timeout', 'timeout', [CompletionResultType]::ParameterName, 'Maximal time (in seconds) to wait for a reply or a progress report from the node')
+            [CompletionResult]::new('--format', 'format', [CompletionResultType]::ParameterName, 'Format of the command output: human-readable `text`, or machine-readable `json` or `yaml`')
+            [CompletionResult]::new('-h', 'h', [CompletionResultType]::ParameterName, 'Print help information')
+            [CompletionResult]::new('--help', 'help', [CompletionResultType]::ParameterName, 'Print help information')
+            [CompletionResult]::new('-v', 'v', [CompletionResultType]::ParameterName, 'Set verbosity level')
+            [CompletionResult]::new('--verbose', 'verbose', [CompletionResultType]::ParameterName, 'Set verbosity level')
+            break
+        }
+        'storm-cli;pin' {
+            [CompletionResult]::new('-S', 'S', [CompletionResultType]::ParameterName, 'ZMQ socket for connecting Storm node RPC interface')
+            [CompletionResult]::new('--storm', 'storm', [CompletionResultType]::ParameterName, 'ZMQ socket for connecting Storm node RPC interface')
+            [CompletionResult]::new('-d', 'd', [CompletionResultType]::ParameterName, 'Node data directory path')
+            [CompletionResult]::new('--data-dir', 'data-dir', [CompletionResultType]::ParameterName, 'Node data directory path')
+            [CompletionResult]::new('--token', 'token', [CompletionResultType]::ParameterName, 'Access token for the node RPC interface')
+            [CompletionResult]::new('--store', 'store', [CompletionResultType]::ParameterName, 'ZMQ socket for connecting storage daemon')
+            [CompletionResult]::new('-C', 'C', [CompletionResultType]::ParameterName, 'ZMQ socket for chat daemon PUB/SUB API')
+            [CompletionResult]::new('--chat', 'chat', [CompletionResultType]::ParameterName, 'ZMQ socket for chat daemon PUB/SUB API')
+            [CompletionResult]::new('--timeout', 'timeout', [CompletionResultType]::ParameterName, 'Maximal time (in seconds) to wait for a reply or a progress report from the node')
+            [CompletionResult]::new('--format', 'format', [CompletionResultType]::ParameterName, 'Format of the command output: human-readable `text`, or machine-readable `json` or `yaml`')
+            [CompletionResult]::new('-h', 'h', [CompletionResultType]::ParameterName, 'Print help information')
+            [CompletionResult]::new('--help', 'help', [CompletionResultType]::ParameterName, 'Print help information')
+            [CompletionResult]::new('-v', 'v', [CompletionResultType]::ParameterName, 'Set verbosity level')
+            [CompletionResult]::new('--verbose', 'verbose', [CompletionResultType]::ParameterName, 'Set verbosity level')
+            break
+        }
+        'storm-cli;unpin' {
+            [CompletionResult]::new('-S', 'S', [CompletionResultType]::ParameterName, 'ZMQ socket for connecting Storm node RPC interface')
+            [CompletionResult]::new('--storm', 'storm', [CompletionResultType]::ParameterName, 'ZMQ socket for connecting Storm node RPC interface')
+            [CompletionResult]::new('-d', 'd', [CompletionResultType]::ParameterName, 'Node data directory path')
+            [CompletionResult]::new('--data-dir', 'data-dir', [CompletionResultType]::ParameterName, 'Node data directory path')
+            [CompletionResult]::new('--token', 'token', [CompletionResultType]::ParameterName, 'Access token for the node RPC interface')
+            [CompletionResult]::new('--store', 'store', [CompletionResultType]::ParameterName, 'ZMQ socket for connecting storage daemon')
+            [CompletionResult]::new('-C', 'C', [CompletionResultType]::ParameterName, 'ZMQ socket for chat daemon PUB/SUB API')
+            [CompletionResult]::new('--chat', 'chat', [CompletionResultType]::ParameterName, 'ZMQ socket for chat daemon PUB/SUB API')
+            [CompletionResult]::new('--timeout', 'timeout', [CompletionResultType]::ParameterName, 'Maximal time (in seconds) to wait for a reply or a progress report from the node')
+            [CompletionResult]::new('--format', 'format', [CompletionResultType]::ParameterName, 'Format of the command output: human-readable `text`, or machine-readable `json` or `yaml`')
+            [CompletionResult]::new('-h', 'h', [CompletionResultType]::ParameterName, 'Print help information')
+            [CompletionResult]::new('--help', 'help', [CompletionResultType]::ParameterName, 'Print help information')
+            [CompletionResult]::new('-v', 'v', [CompletionResultType]::ParameterName, 'Set verbosity level')
+            [CompletionResult]::new('--verbose', 'verbose', [CompletionResultType]::ParameterName, 'Set verbosity level')
+            break
+        }
+        'storm-cli;gc' {
+            [CompletionResult]::new('-S', 'S', [CompletionResultType]::ParameterName, 'ZMQ socket for connecting Storm node RPC interface')
+            [CompletionResult]::new('--storm', 'storm', [CompletionResultType]::ParameterName, 'ZMQ socket for connecting Storm node RPC interface')
+            [CompletionResult]::new('-d', 'd', [CompletionResultType]::ParameterName, 'Node data directory path')
+            [CompletionResult]::new('--data-dir', 'data-dir', [CompletionResultType]::ParameterName, 'Node data directory path')
+            [CompletionResult]::new('--token', 'token', [CompletionResultType]::ParameterName, 'Access token for the node RPC interface')
+            [CompletionResult]::new('--store', 'store', [CompletionResultType]::ParameterName, 'ZMQ socket for connecting storage daemon')
+            [CompletionResult]::new('-C', 'C', [CompletionResultType]::ParameterName, 'ZMQ socket for chat daemon PUB/SUB API')
+            [CompletionResult]::new('--chat', 'chat', [CompletionResultType]::ParameterName, 'ZMQ socket for chat daemon PUB/SUB API')
+            [CompletionResult]::new('--timeout', 'timeout', [CompletionResultType]::ParameterName, 'Maximal time (in seconds) to wait for a reply or a progress report from the node')
+            [CompletionResult]::new('--format', 'format', [CompletionResultType]::ParameterName, 'Format of the command output: human-readable `text`, or machine-readable `json` or `yaml`')
+            [CompletionResult]::new('--dry-run', 'dry-run', [CompletionResultType]::ParameterName, 'Only show what would be reclaimed, without reclaiming anything')
+            [CompletionResult]::new('-h', 'h', [CompletionResultType]::ParameterName, 'Print help information')
+            [CompletionResult]::new('--help', 'help', [CompletionResultType]::ParameterName, 'Print help information')
+            [CompletionResult]::new('-v', 'v', [CompletionResultType]::ParameterName, 'Set verbosity level')
+            [CompletionResult]::new('--verbose', 'verbose', [CompletionResultType]::ParameterName, 'Set verbosity level')
+            break
+        }
+        'storm-cli;fsck' {
+            [CompletionResult]::new('-r', 'r', [CompletionResultType]::ParameterName, 'Re-fetch damaged containers from the given peer')
+            [CompletionResult]::new('--repair-from', 'repair-from', [CompletionResultType]::ParameterName, 'Re-fetch damaged containers from the given peer')
+            [CompletionResult]::new('-S', 'S', [CompletionResultType]::ParameterName, 'ZMQ socket for connecting Storm node RPC interface')
+            [CompletionResult]::new('--storm', 'storm', [CompletionResultType]::ParameterName, 'ZMQ socket for connecting Storm node RPC interface')
+            [CompletionResult]::new('-d', 'd', [CompletionResultType]::ParameterName, 'Node data directory path')
+            [CompletionResult]::new('--data-dir', 'data-dir', [CompletionResultType]::ParameterName, 'Node data directory path')
+            [CompletionResult]::new('--token', 'token', [CompletionResultType]::ParameterName, 'Access token for the node RPC interface')
+            [CompletionResult]::new('--store', 'store', [CompletionResultType]::ParameterName, 'ZMQ socket for connecting storage daemon')
+            [CompletionResult]::new('-C', 'C', [CompletionResultType]::ParameterName, 'ZMQ socket for chat daemon PUB/SUB API')
+            [CompletionResult]::new('--chat', 'chat', [CompletionResultType]::ParameterName, 'ZMQ socket for chat daemon PUB/SUB API')
+            [CompletionResult]::new('--timeout', 'timeout', [CompletionResultType]::ParameterName, 'Maximal time (in seconds) to wait for a reply or a progress report from the node')
+            [CompletionResult]::new('--format', 'format', [CompletionResultType]::ParameterName, 'Format of the command output: human-readable `text`, or machine-readable `json` or `yaml`')
+            [CompletionResult]::new('-h', 'h', [CompletionResultType]::ParameterName, 'Print help information')
+            [CompletionResult]::new('--help', 'help', [CompletionResultType]::ParameterName, 'Print help information')
+            [CompletionResult]::new('-v', 'v', [CompletionResultType]::ParameterName, 'Set verbosity level')
+            [CompletionResult]::new('--verbose', 'verbose', [CompletionResultType]::ParameterName, 'Set verbosity level')
+            break
+        }
+        'storm-cli;seed' {
+            [CompletionResult]::new('-S', 'S', [CompletionResultType]::ParameterName, 'ZMQ socket for connecting Storm node RPC interface')
+            [CompletionResult]::new('--storm', 'storm', [CompletionResultType]::ParameterName, 'ZMQ socket for connecting Storm node RPC interface')
+            [CompletionResult]::new('-d', 'd', [CompletionResultType]::ParameterName, 'Node data directory path')
+            [CompletionResult]::new('--data-dir', 'data-dir', [CompletionResultType]::ParameterName, 'Node data directory path')
+            [CompletionResult]::new('--token', 'token', [CompletionResultType]::ParameterName, 'Access token for the node RPC interface')
+            [CompletionResult]::new('--store', 'store', [CompletionResultType]::ParameterName, 'ZMQ socket for connecting storage daemon')
+            [CompletionResult]::new('-C', 'C', [CompletionResultType]::ParameterName, 'ZMQ socket for chat daemon PUB/SUB API')
+            [CompletionResult]::new('--chat', 'chat', [CompletionResultType]::ParameterName, 'ZMQ socket for chat daemon PUB/SUB API')
+            [CompletionResult]::new('--timeout', 'timeout', [CompletionResultType]::ParameterName, 'Maximal time (in seconds) to wait for a reply or a progress report from the node')
+            [CompletionResult]::new('--format', 'format', [CompletionResultType]::ParameterName, 'Format of the command output: human-readable `text`, or machine-readable `json` or `yaml`')
+            [CompletionResult]::new('-h', 'h', [CompletionResultType]::ParameterName, 'Print help information')
+            [CompletionResult]::new('--help', 'help', [CompletionResultType]::ParameterName, 'Print help information')
+            [CompletionResult]::new('-v', 'v', [CompletionResultType]::ParameterName, 'Set verbosity level')
+            [CompletionResult]::new('--verbose', 'verbose', [CompletionResultType]::ParameterName, 'Set verbosity level')
+            break
+        }
+        'storm-cli;catalog' {
+            [CompletionResult]::new('-s', 's', [CompletionResultType]::ParameterName, 'Show only containers with the given status (pending, downloading, complete or declined)')
+            [CompletionResult]::new('--status', 'status', [CompletionResultType]::ParameterName, 'Show only containers with the given status (pending, downloading, complete or declined)')
+            [CompletionResult]::new('-p', 'p', [CompletionResultType]::ParameterName, 'Show only containers announced by the given peer')
+            [CompletionResult]::new('--peer', 'peer', [CompletionResultType]::ParameterName, 'Show only containers announced by the given peer')
+            [CompletionResult]::new('-m', 'm', [CompletionResultType]::ParameterName, 'Show only containers of the given MIME type')
+            [CompletionResult]::new('--mime', 'mime', [CompletionResultType]::ParameterName, 'Show only containers of the given MIME type')
+            [CompletionResult]::new('-S', 'S', [CompletionResultType]::ParameterName, 'ZMQ socket for connecting Storm node RPC interface')
+            [CompletionResult]::new('--storm', 'storm', [CompletionResultType]::ParameterName, 'ZMQ socket for connecting Storm node RPC interface')
+            [CompletionResult]::new('-d', 'd', [CompletionResultType]::ParameterName, 'Node data directory path')
+            [CompletionResult]::new('--data-dir', 'data-dir', [CompletionResultType]::ParameterName, 'Node data directory path')
+            [CompletionResult]::new('--token', 'token', [CompletionResultType]::ParameterName, 'Access token for the node RPC interface')
+            [CompletionResult]::new('--store', 'store', [CompletionResultType]::ParameterName, 'ZMQ socket for connecting storage daemon')
+            [CompletionResult]::new('-C', 'C', [CompletionResultType]::ParameterName, 'ZMQ socket for chat daemon PUB/SUB API')
+            [CompletionResult]::new('--chat', 'chat', [CompletionResultType]::ParameterName, 'ZMQ socket for chat daemon PUB/SUB API')
+            [CompletionResult]::new('--timeout', 'timeout', [CompletionResultType]::ParameterName, 'Maximal time (in seconds) to wait for a reply or a progress report from the node')
+            [CompletionResult]::new('--format', 'format', [CompletionResultType]::ParameterName, 'Format of the command output: human-readable `text`, or machine-readable `json` or `yaml`')
+            [CompletionResult]::new('-h', 'h', [CompletionResultType]::ParameterName, 'Print help information')
+            [CompletionResult]::new('--help', 'help', [CompletionResultType]::ParameterName, 'Print help information')
+            [CompletionResult]::new('-v', 'v', [CompletionResultType]::ParameterName, 'Set verbosity level')
+            [CompletionResult]::new('--verbose', 'verbose', [CompletionResultType]::ParameterName, 'Set verbosity level')
+            break
+        }
+        'storm-cli;pending' {
+            [CompletionResult]::new('-S', 'S', [CompletionResultType]::ParameterName, 'ZMQ socket for connecting Storm node RPC interface')
+            [CompletionResult]::new('--storm', 'storm', [CompletionResultType]::ParameterName, 'ZMQ socket for connecting Storm node RPC interface')
+            [CompletionResult]::new('-d', 'd', [CompletionResultType]::ParameterName, 'Node data directory path')
+            [CompletionResult]::new('--data-dir', 'data-dir', [CompletionResultType]::ParameterName, 'Node data directory path')
+            [CompletionResult]::new('--token', 'token', [CompletionResultType]::ParameterName, 'Access token for the node RPC interface')
+            [CompletionResult]::new('--store', 'store', [CompletionResultType]::ParameterName, 'ZMQ socket for connecting storage daemon')
+            [CompletionResult]::new('-C', 'C', [CompletionResultType]::ParameterName, 'ZMQ socket for chat daemon PUB/SUB API')
+            [CompletionResult]::new('--chat', 'chat', [CompletionResultType]::ParameterName, 'ZMQ socket for chat daemon PUB/SUB API')
+            [CompletionResult]::new('--timeout', 'timeout', [CompletionResultType]::ParameterName, 'Maximal time (in seconds) to wait for a reply or a progress report from the node')
+            [CompletionResult]::new('--format', 'format', [CompletionResultType]::ParameterName, 'Format of the command output: human-readable `text`, or machine-readable `json` or `yaml`')
+            [CompletionResult]::new('-h', 'h', [CompletionResultType]::ParameterName, 'Print help information')
+            [CompletionResult]::new('--help', 'help', [CompletionResultType]::ParameterName, 'Print help information')
+            [CompletionResult]::new('-v', 'v', [CompletionResultType]::ParameterName, 'Set verbosity level')
+            [CompletionResult]::new('--verbose', 'verbose', [CompletionResultType]::ParameterName, 'Set verbosity level')
+            break
+        }
+        'storm-cli;accept' {
+            [CompletionResult]::new('-S', 'S', [CompletionResultType]::ParameterName, 'ZMQ socket for connecting Storm node RPC interface')
+            [CompletionResult]::new('--storm', 'storm', [CompletionResultType]::ParameterName, 'ZMQ socket for connecting Storm node RPC interface')
+            [CompletionResult]::new('-d', 'd', [CompletionResultType]::ParameterName, 'Node data directory path')
+            [CompletionResult]::new('--data-dir', 'data-dir', [CompletionResultType]::ParameterName, 'Node data directory path')
+            [CompletionResult]::new('--token', 'token', [CompletionResultType]::ParameterName, 'Access token for the node RPC interface')
+            [CompletionResult]::new('--store', 'store', [CompletionResultType]::ParameterName, 'ZMQ socket for connecting storage daemon')
+            [CompletionResult]::new('-C', 'C', [CompletionResultType]::ParameterName, 'ZMQ socket for chat daemon PUB/SUB API')
+            [CompletionResult]::new('--chat', 'chat', [CompletionResultType]::ParameterName, 'ZMQ socket for chat daemon PUB/SUB API')
+            [CompletionResult]::new('--timeout', 'timeout', [CompletionResultType]::ParameterName, 'Maximal time (in seconds) to wait for a reply or a progress report from the node')
+            [CompletionResult]::new('--format', 'format', [CompletionResultType]::ParameterName, 'Format of the command output: human-readable `text`, or machine-readable `json` or `yaml`')
+            [CompletionResult]::new('-h', 'h', [CompletionResultType]::ParameterName, 'Print help information')
+            [CompletionResult]::new('--help', 'help', [CompletionResultType]::ParameterName, 'Print help information')
+            [CompletionResult]::new('-v', 'v', [CompletionResultType]::ParameterName, 'Set verbosity level')
+            [CompletionResult]::new('--verbose', 'verbose', [CompletionResultType]::ParameterName, 'Set verbosity level')
+            break
+        }
+        'storm-cli;decline' {
+            [CompletionResult]::new('-S', 'S', [CompletionResultType]::ParameterName, 'ZMQ socket for connecting Storm node RPC interface')
+            [CompletionResult]::new('--storm', 'storm', [CompletionResultType]::ParameterName, 'ZMQ socket for connecting Storm node RPC interface')
+            [CompletionResult]::new('-d', 'd', [CompletionResultType]::ParameterName, 'Node data directory path')
+            [CompletionResult]::new('--data-dir', 'data-dir', [CompletionResultType]::ParameterName, 'Node data directory path')
+            [CompletionResult]::new('--token', 'token', [CompletionResultType]::ParameterName, 'Access token for the node RPC interface')
             [CompletionResult]::new('--store', 'store', [CompletionResultType]::ParameterName, 'ZMQ socket for connecting storage daemon')
             [CompletionResult]::new('-C', 'C', [CompletionResultType]::ParameterName, 'ZMQ socket for chat daemon PUB/SUB API')
             [CompletionResult]::new('--chat', 'chat', [CompletionResultType]::ParameterName, 'ZMQ socket for chat daemon PUB/SUB API')
-            [CompletionResult]::new('-L', 'L', [CompletionResultType]::ParameterName, 'ZMQ socket for connecting LNP node RPC interface')
-            [CompletionResult]::new('--lnp', 'lnp', [CompletionResultType]::ParameterName, 'ZMQ socket for connecting LNP node RPC interface')
+            [CompletionResult]::new('--timeout', 'timeout', [CompletionResultType]::ParameterName, 'Maximal time (in seconds) to wait for a reply or a progress report from the node')
+            [CompletionResult]::new('--format', 'format', [CompletionResultType]::ParameterName, 'Format of the command output: human-readable `text`, or machine-readable `json` or `yaml`')
             [CompletionResult]::new('-h', 'h', [CompletionResultType]::ParameterName, 'Print help information')
             [CompletionResult]::new('--help', 'help', [CompletionResultType]::ParameterName, 'Print help information')
             [CompletionResult]::new('-v', 'v', [CompletionResultType]::ParameterName, 'Set verbosity level')
@@ -136,11 +488,14 @@ Register-ArgumentCompleter -Native -CommandName 'storm-cli' -ScriptBlock {
         'storm-cli;help' {
             [CompletionResult]::new('-S', 'S', [CompletionResultType]::ParameterName, 'ZMQ socket for connecting Storm node RPC interface')
             [CompletionResult]::new('--storm', 'storm', [CompletionResultType]::ParameterName, 'ZMQ socket for connecting Storm node RPC interface')
+            [CompletionResult]::new('-d', 'd', [CompletionResultType]::ParameterName, 'Node data directory path')
+            [CompletionResult]::new('--data-dir', 'data-dir', [CompletionResultType]::ParameterName, 'Node data directory path')
+            [CompletionResult]::new('--token', 'token', [CompletionResultType]::ParameterName, 'Access token for the node RPC interface')
             [CompletionResult]::new('--store', 'store', [CompletionResultType]::ParameterName, 'ZMQ socket for connecting storage daemon')
             [CompletionResult]::new('-C', 'C', [CompletionResultType]::ParameterName, 'ZMQ socket for chat daemon PUB/SUB API')
             [CompletionResult]::new('--chat', 'chat', [CompletionResultType]::ParameterName, 'ZMQ socket for chat daemon PUB/SUB API')
-            [CompletionResult]::new('-L', 'L', [CompletionResultType]::ParameterName, 'ZMQ socket for connecting LNP node RPC interface')
-            [CompletionResult]::new('--lnp', 'lnp', [CompletionResultType]::ParameterName, 'ZMQ socket for connecting LNP node RPC interface')
+            [CompletionResult]::new('--timeout', 'timeout', [CompletionResultType]::ParameterName, 'Maximal time (in seconds) to wait for a reply or a progress report from the node')
+            [CompletionResult]::new('--format', 'format', [CompletionResultType]::ParameterName, 'Format of the command output: human-readable `text`, or machine-readable `json` or `yaml`')
             [CompletionResult]::new('-v', 'v', [CompletionResultType]::ParameterName, 'Set verbosity level')
             [CompletionResult]::new('--verbose', 'verbose', [CompletionResultType]::ParameterName, 'Set verbosity level')
             break
@@ -37,6 +37,16 @@ Register-ArgumentCompleter -Native -CommandName 'stormd' -ScriptBlock {
             [CompletionResult]::new('--store-endpoint', 'store-endpoint', [CompletionResultType]::ParameterName, 'ZMQ socket for connecting storage daemon')
             [CompletionResult]::new('-C', 'C', [CompletionResultType]::ParameterName, 'ZMQ socket for chat daemon PUB/SUB API')
             [CompletionResult]::new('--chat-endpoint', 'chat-endpoint', [CompletionResultType]::ParameterName, 'ZMQ socket for chat daemon PUB/SUB API')
+            [CompletionResult]::new('--downpour-max-size', 'downpour-max-size', [CompletionResultType]::ParameterName, 'Maximal size of a container (in bytes) which can be downloaded automatically')
+            [CompletionResult]::new('--downpour-mime', 'downpour-mime', [CompletionResultType]::ParameterName, 'MIME type of containers which can be downloaded automatically')
+            [CompletionResult]::new('--downpour-trust', 'downpour-trust', [CompletionResultType]::ParameterName, 'Peer which container announcements can be downloaded automatically')
+            [CompletionResult]::new('--downpour-min-free', 'downpour-min-free', [CompletionResultType]::ParameterName, 'Minimal free disk space (in bytes) which must remain in the data directory after an automatic download')
+            [CompletionResult]::new('--downpour-seed', 'downpour-seed', [CompletionResultType]::ParameterName, 'Peer to which completed and locally seeded containers are announced')
+            [CompletionResult]::new('--quota-app', 'quota-app', [CompletionResultType]::ParameterName, 'Quota on the total size of containers requested by an app, in form of `APP:BYTES`')
+            [CompletionResult]::new('--quota-peer-default', 'quota-peer-default', [CompletionResultType]::ParameterName, 'Quota on the total size of containers received from a single peer')
+            [CompletionResult]::new('--quota-peer', 'quota-peer', [CompletionResultType]::ParameterName, 'Quota on the total size of containers received from a specific peer, in form of `NODE_ID:BYTES`')
+            [CompletionResult]::new('--lnp', 'lnp', [CompletionResultType]::ParameterName, 'ZMQ socket for connecting LNP node RPC interface, used to manage connections to remote peers')
+            [CompletionResult]::new('--node-key', 'node-key', [CompletionResultType]::ParameterName, 'File with the node private key, used by the integrity check to decrypt data of the containers encrypted to the node. Without the key only presence and hashes of their chunks are checked')
             [CompletionResult]::new('-h', 'h', [CompletionResultType]::ParameterName, 'Print help information')
             [CompletionResult]::new('--help', 'help', [CompletionResultType]::ParameterName, 'Print help information')
             [CompletionResult]::new('-V', 'V', [CompletionResultType]::ParameterName, 'Print version information')
@@ -19,7 +19,7 @@ _downpourd() {
 
     case "${cmd}" in
         downpourd)
-            opts="-h -V -v -d -c -M -X -R -E -S -C --help --version --verbose --data-dir --config --msg --ctl --rpc-endpoint --ext-endpoint --store-endpoint --chat-endpoint"
+            opts="-h -V -v -d -c -M -X -R -E -S -C --help --version --verbose --data-dir --config --msg --ctl --rpc-endpoint --ext-endpoint --store-endpoint --chat-endpoint --downpour-max-size --downpour-mime --downpour-trust --downpour-min-free --downpour-seed"
             if [[ ${cur} == -* || ${COMP_CWORD} -eq 1 ]] ; then
                 COMPREPLY=( $(compgen -W "${opts}" -- "${cur}") )
                 return 0
@@ -89,6 +89,26 @@ _downpourd() {
                     COMPREPLY=($(compgen -f "${cur}"))
                     return 0
                     ;;
+                --downpour-max-size)
+                    COMPREPLY=($(compgen -f "${cur}"))
+                    return 0
+                    ;;
+                --downpour-mime)
+                    COMPREPLY=($(compgen -f "${cur}"))
+                    return 0
+                    ;;
+                --downpour-trust)
+                    COMPREPLY=($(compgen -f "${cur}"))
+                    return 0
+                    ;;
+                --downpour-min-free)
+                    COMPREPLY=($(compgen -f "${cur}"))
+                    return 0
+                    ;;
+                --downpour-seed)
+                    COMPREPLY=($(compgen -f "${cur}"))
+                    return 0
+                    ;;
                 *)
                     COMPREPLY=()
                     ;;
@@ -0,0 +1,159 @@
+// Storm node providing distributed storage & messaging for lightning network.
+//
+// Written in 2022 by
+//     Dr. Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2022 by LNP/BP Standards Association, Switzerland.
+//
+// You should have received a copy of the MIT License along with this software.
+// If not, see <https://opensource.org/licenses/MIT>.
+
+//! Authentication and authorization of RPC client requests; see [`storm_rpc::auth`] for the
+//! description of access tokens and client roles.
+//!
+//! Requests are checked by each daemon serving RPC clients, since the requests addressed to chatd
+//! and downpourd are routed by the stormd message bus without being processed by stormd itself.
+
+use std::path::Path;
+use std::{fs, io};
+
+use storm_rpc::{AuthToken, Role, RpcEnvelope, RPC_COOKIE_FILE, RPC_TOKENS_FILE};
+
+use crate::{DaemonError, LaunchError};
+
+/// Access tokens accepted by the daemon, together with the roles they grant
+#[derive(Clone, Debug)]
+pub(crate) struct RpcAuth {
+    tokens: Vec<(AuthToken, Role)>,
+}
+
+impl RpcAuth {
+    /// Loads access tokens from the node data directory. The cookie is generated if it does not
+    /// exist yet.
+    pub fn load(data_dir: &Path) -> Result<Self, LaunchError> {
+        let cookie_file = data_dir.join(RPC_COOKIE_FILE);
+        let cookie = AuthToken::read_or_create(&cookie_file).map_err(|err| {
+            LaunchError::RpcAuth(format!("cookie file {}: {}", cookie_file.display(), err))
+        })?;
+        let mut tokens = vec![(cookie, Role::Full)];
+
+        let tokens_file = data_dir.join(RPC_TOKENS_FILE);
+        if tokens_file.exists() {
+            debug!("Reading RPC access tokens from {}", tokens_file.display());
+            let preshared = read_tokens(&tokens_file).map_err(|err| {
+                LaunchError::RpcAuth(format!("tokens file {}: {}", tokens_file.display(), err))
+            })?;
+            info!("Using {} preshared RPC access tokens", preshared.len());
+            tokens.extend(preshared);
+        }
+
+        Ok(RpcAuth { tokens })
+    }
+
+    /// Checks that the request has a valid access token and that the role granted by the token
+    /// permits the request.
+    pub fn check(&self, envelope: &RpcEnvelope) -> Result<Role, DaemonError> {
+        let role = envelope
+            .auth
+            .as_ref()
+            .and_then(|token| {
+                self.tokens.iter().find(|(known, _)| known == token).map(|(_, role)| *role)
+            })
+            .ok_or(DaemonError::Unauthenticated)?;
+        if !role.permits(&envelope.msg) {
+            return Err(DaemonError::PermissionDenied(role, envelope.msg.to_string()));
+        }
+        Ok(role)
+    }
+}
+
+/// Reads preshared tokens listed as `<role> <token>` lines.
+fn read_tokens(path: &Path) -> Result<Vec<(AuthToken, Role)>, io::Error> {
+    let invalid = |line_no: usize, details: String| {
+        io::Error::new(io::ErrorKind::InvalidData, format!("line {}: {}", line_no + 1, details))
+    };
+    let mut tokens = vec![];
+    for (line_no, line) in fs::read_to_string(path)?.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let (role, token) = line
+            .split_once(char::is_whitespace)
+            .ok_or_else(|| invalid(line_no, s!("expected `<role> <token>`")))?;
+        let role = role.parse().map_err(|err| invalid(line_no, format!("{}", err)))?;
+        let token = token.parse().map_err(|err| invalid(line_no, format!("{}", err)))?;
+        tokens.push((token, role));
+    }
+    Ok(tokens)
+}
+
+#[cfg(test)]
+mod test {
+    use storm_rpc::{RequestId, RpcMsg};
+
+    use super::*;
+
+    fn temp_dir(name: &str) -> std::path::PathBuf {
+        let dir = std::env::temp_dir().join(format!("storm-auth-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn tokens_file() {
+        let dir = temp_dir("tokens");
+        let path = dir.join(RPC_TOKENS_FILE);
+        fs::write(&path, "# comment\n\nread-only  abc\n  chat\tdef  \nfull ghi\n").unwrap();
+        assert_eq!(read_tokens(&path).unwrap(), vec![
+            ("abc".parse().unwrap(), Role::ReadOnly),
+            ("def".parse().unwrap(), Role::Chat),
+            ("ghi".parse().unwrap(), Role::Full),
+        ]);
+
+        for (content, line) in [("full abc\nabc\n", 2), ("admin abc\n", 1), ("# full\nfull \n", 2)]
+        {
+            fs::write(&path, content).unwrap();
+            let err = read_tokens(&path).unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+            assert!(err.to_string().starts_with(&format!("line {}:", line)), "{}", err);
+        }
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn check() {
+        let dir = temp_dir("check");
+        fs::write(dir.join(RPC_TOKENS_FILE), "read-only abc\n").unwrap();
+        let auth = RpcAuth::load(&dir).unwrap();
+        let cookie = AuthToken::read(dir.join(RPC_COOKIE_FILE)).unwrap();
+
+        let envelope = |auth: Option<&str>, msg| RpcEnvelope {
+            request_id: RequestId::default(),
+            auth: auth.map(|token| token.parse().unwrap()),
+            msg,
+        };
+        assert_eq!(
+            auth.check(&envelope(Some(cookie.as_str()), RpcMsg::Fsck(None))).ok(),
+            Some(Role::Full)
+        );
+        assert_eq!(
+            auth.check(&envelope(Some("abc"), RpcMsg::ListPeers)).ok(),
+            Some(Role::ReadOnly)
+        );
+        assert!(matches!(
+            auth.check(&envelope(Some("abc"), RpcMsg::Fsck(None))),
+            Err(DaemonError::PermissionDenied(Role::ReadOnly, _))
+        ));
+        assert!(matches!(
+            auth.check(&envelope(Some("abd"), RpcMsg::ListPeers)),
+            Err(DaemonError::Unauthenticated)
+        ));
+        assert!(matches!(
+            auth.check(&envelope(None, RpcMsg::ListPeers)),
+            Err(DaemonError::Unauthenticated)
+        ));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -28,7 +28,7 @@ fn main() -> Result<(), BootstrapError<LaunchError>> {
     opts.process();
     trace!("Processed arguments: {:?}", opts);
 
-    let config: Config = opts.clone().into();
+    let config: Config<downpourd::Config> = opts.clone().into();
     trace!("Daemon configuration: {:?}", config);
     debug!("CTL socket {}", config.ctl_endpoint);
     debug!("RPC socket {}", config.rpc_endpoint);
@@ -11,11 +11,10 @@
 use std::collections::BTreeSet;
 use std::fmt::{self, Display, Formatter};
 
-use internet2::addr::NodeId;
-use microservices::esb::ClientId;
+use internet2::addr::{NodeAddr, NodeId};
 use storm::p2p::AppMsg;
 use storm::{Chunk, ChunkId, Container, ContainerId, StormApp};
-use storm_rpc::{AddressedMsg, AppContainer};
+use storm_rpc::{AddressedMsg, AppContainer, ClientRequest, QuotaUsage};
 use strict_encoding::{StrictDecode, StrictEncode};
 
 /// RPC API requests over CTL message bus between RGB Node daemons.
@@ -38,6 +37,9 @@ pub enum CtlMsg {
     #[display("process_container(...)")]
     ProcessContainer(Container),
 
+    #[display("reject_container({0})")]
+    RejectContainer(QuotaRejection),
+
     #[display("send_chunks({0})")]
     SendChunks(AddressedMsg<ChunkSend>),
 
@@ -49,6 +51,9 @@ pub enum CtlMsg {
 
     #[display("processing_failed()")]
     ProcessingFailed,
+
+    #[display("lnp_reply({0})")]
+    LnpReply(LnpReply),
 }
 
 #[derive(Clone, PartialOrd, Ord, PartialEq, Eq, Hash, Debug, Display)]
@@ -60,12 +65,23 @@ pub struct ChunkSend {
     pub chunk_ids: BTreeSet<ChunkId>,
 }
 
+/// Container received from a remote peer which can't be stored since it exceeds storage quota
+#[derive(Copy, Clone, PartialOrd, Ord, PartialEq, Eq, Hash, Debug, Display)]
+#[derive(NetworkEncode, NetworkDecode)]
+#[display("{size}, {quota}")]
+pub struct QuotaRejection {
+    /// Size of the container
+    pub size: u64,
+    /// Usage of the exceeded quota
+    pub quota: QuotaUsage,
+}
+
 #[derive(Copy, Clone, PartialOrd, Ord, PartialEq, Eq, Hash, Debug, NetworkEncode, NetworkDecode)]
 pub struct AddressedClientMsg<T>
 where T: StrictEncode + StrictDecode
 {
     pub remote_id: NodeId,
-    pub client_id: Option<ClientId>,
+    pub client: Option<ClientRequest>,
     pub data: T,
 }
 
@@ -80,11 +96,53 @@ where T: Display + StrictEncode + StrictDecode
 impl<T> AddressedClientMsg<T>
 where T: StrictEncode + StrictDecode
 {
-    pub fn with(app_msg: AppMsg<T>, remote_peer: NodeId, client_id: ClientId) -> Self {
+    pub fn with(app_msg: AppMsg<T>, remote_peer: NodeId, client: ClientRequest) -> Self {
         AddressedClientMsg {
-            client_id: Some(client_id),
+            client: Some(client),
             remote_id: remote_peer,
             data: app_msg.data,
         }
     }
 }
+
+/// Peer management request forwarded by stormd to LNP node
+#[derive(Copy, Clone, PartialOrd, Ord, PartialEq, Eq, Hash, Debug, Display)]
+#[derive(NetworkEncode, NetworkDecode)]
+pub enum PeerRequest {
+    #[display("connect({0})")]
+    Connect(NodeAddr),
+
+    #[display("disconnect({0})")]
+    Disconnect(NodeId),
+
+    #[display("list_peers()")]
+    ListPeers,
+}
+
+/// Reply of LNP node to a [`PeerRequest`]
+#[derive(Clone, PartialOrd, Ord, PartialEq, Eq, Hash, Debug, Display)]
+#[derive(NetworkEncode, NetworkDecode)]
+pub enum PeerReply {
+    #[display("success()")]
+    Success,
+
+    #[display("peers(...)")]
+    Peers(BTreeSet<NodeId>),
+
+    /// LNP node has processed the request but was unable to fulfill it
+    #[display("rejected({0})")]
+    Rejected(String),
+
+    /// Request has not reached LNP node or the node has failed to reply
+    #[display("failure({0})")]
+    Failure(String),
+}
+
+#[derive(Clone, PartialOrd, Ord, PartialEq, Eq, Hash, Debug, Display)]
+#[derive(NetworkEncode, NetworkDecode)]
+#[display("{client}, {request}, {reply}")]
+pub struct LnpReply {
+    pub client: ClientRequest,
+    pub request: PeerRequest,
+    pub reply: PeerReply,
+}
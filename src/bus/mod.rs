@@ -14,9 +14,11 @@ mod ctl;
 use lnp2p::bifrost;
 use microservices::rpc;
 use storm_ext::ExtMsg;
-use storm_rpc::{RadioMsg, RpcMsg};
+use storm_rpc::{RadioMsg, RpcEnvelope};
 
-pub use self::ctl::{AddressedClientMsg, ChunkSend, CtlMsg};
+pub use self::ctl::{
+    AddressedClientMsg, ChunkSend, CtlMsg, LnpReply, PeerReply, PeerRequest, QuotaRejection,
+};
 pub(crate) use self::services::{DaemonId, Endpoints, Responder, ServiceBus};
 
 /// Service controller messages
@@ -33,7 +35,7 @@ pub(crate) enum BusMsg {
     #[api(type = 4)]
     #[display(inner)]
     #[from]
-    Rpc(RpcMsg),
+    Rpc(RpcEnvelope),
 
     /// Storm node <-> application extensions messaging
     #[api(type = 5)]
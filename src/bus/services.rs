@@ -12,13 +12,13 @@ use internet2::addr::NodeId;
 use internet2::TypedEnum;
 use lnp2p::bifrost;
 use lnp2p::bifrost::BifrostApp;
-use microservices::esb::ClientId;
-use microservices::{esb, rpc};
+use microservices::esb;
 use storm::{p2p, StormApp};
 use storm_ext::ExtMsg;
-use storm_rpc::{RadioMsg, RpcMsg, ServiceId};
+use storm_rpc::{ChatTopic, ClientRequest, RadioMsg, RpcEnvelope, RpcMsg, ServiceId};
 
 use crate::bus::{BusMsg, CtlMsg};
+use crate::DaemonError;
 
 pub(crate) type Endpoints = esb::EndpointList<ServiceBus>;
 
@@ -61,37 +61,32 @@ where
     fn send_p2p_reporting_client(
         &self,
         endpoints: &mut Endpoints,
-        client_id: Option<ClientId>,
-        client_message: Option<&str>,
+        client: Option<ClientRequest>,
+        replies: &[RpcMsg],
         remote_id: NodeId,
         message: impl Into<p2p::Messages>,
     ) {
         // We have nobody to report the failure to
         let _ = match self.send_p2p(endpoints, remote_id, message) {
             Ok(_) => {
-                if let Some(client_id) = client_id {
-                    let reply = match client_message {
-                        None => RpcMsg::Success(None.into()),
-                        Some(report) => RpcMsg::Progress(report.to_owned()),
-                    };
-                    self.send_rpc(endpoints, client_id, reply)
+                if let Some(client) = client {
+                    replies
+                        .iter()
+                        .try_for_each(|reply| self.send_rpc(endpoints, client, reply.clone()))
                 } else {
                     Ok(())
                 }
             }
             Err(err) => {
-                if let Some(client_id) = client_id {
-                    let failure = rpc::Failure {
-                        code: rpc::FailureCode::Transport,
-                        info: format!("{}", err),
-                    };
-                    self.send_rpc(endpoints, client_id, failure)
+                if let Some(client) = client {
+                    let failure = RpcMsg::from(DaemonError::PeerDisconnected(remote_id, err));
+                    self.send_rpc(endpoints, client, failure)
                 } else {
                     Ok(())
                 }
             }
         }
-        .map_err(|_| warn!("client {} is disconnected", client_id.expect("only RPC send errors")));
+        .map_err(|_| warn!("client {} is disconnected", client.expect("only RPC send errors")));
     }
 
     #[inline]
@@ -113,14 +108,18 @@ where
     fn send_rpc(
         &self,
         endpoints: &mut Endpoints,
-        client_id: ClientId,
+        client: ClientRequest,
         message: impl Into<RpcMsg>,
     ) -> Result<(), esb::Error<ServiceId>> {
         endpoints.send_to(
             ServiceBus::Rpc,
             self.identity(),
-            ServiceId::Client(client_id),
-            BusMsg::Rpc(message.into()),
+            ServiceId::Client(client.client_id),
+            BusMsg::Rpc(RpcEnvelope {
+                request_id: client.request_id,
+                auth: None,
+                msg: message.into(),
+            }),
         )
     }
 
@@ -153,13 +152,14 @@ where
     fn send_radio(
         &self,
         endpoints: &mut Endpoints,
+        topic: ChatTopic,
         message: impl Into<RadioMsg>,
     ) -> Result<(), esb::Error<ServiceId>> {
         endpoints.send_to(
             ServiceBus::Chat,
             self.identity(),
-            // Not sure this will work though
-            ServiceId::stormd(),
+            // Topic is the first frame of PUB message, which is used by subscribers for filtering
+            ServiceId::ChatTopic(topic),
             BusMsg::Chat(message.into()),
         )
     }
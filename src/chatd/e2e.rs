@@ -0,0 +1,342 @@
+// Storm node providing distributed storage & messaging for lightning network.
+//
+// Written in 2022 by
+//     Dr. Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2022 by LNP/BP Standards Association, Switzerland.
+//
+// You should have received a copy of the MIT License along with this software.
+// If not, see <https://opensource.org/licenses/MIT>.
+
+//! End-to-end encryption of chat message bodies.
+//!
+//! Each chat daemon has its own chat key, which is generated on the first start and kept in
+//! [`CHAT_KEY_FILE`]. Chat keys are unrelated to the Bifrost node identity, so before encrypting
+//! the daemons exchange their public chat keys with [`KeyAnnouncement`] messages. Announcements
+//! are sent over the Bifrost session with the remote peer, which authenticates their origin.
+//! Messages to a peer which has not announced its key yet are sent in plain text.
+//!
+//! Message bodies are encrypted with ChaCha20-Poly1305 under a per-direction key, which is derived
+//! from the ECDH shared secret between the chat keys together with the sender and the receiver
+//! keys. Associated data bind the ciphertext to the room (parent message id). Nonces are strictly
+//! increasing within a direction, so the receiver rejects replayed messages by keeping the last
+//! accepted nonce. The sender keeps the last used nonce in the Store database, such that a key and
+//! nonce pair is never reused after a restart, even if the clock was set back.
+
+use std::cmp;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use internet2::addr::LocalNode;
+use internet2::session::noise::chacha;
+use secp256k1::PublicKey;
+use storm::MesgId;
+use storm_rpc::{shared_key, ChatMsg, ChatProtection};
+use strict_encoding::{StrictDecode, StrictEncode};
+
+/// Name of the file inside the data directory keeping the chat private key.
+pub const CHAT_KEY_FILE: &str = "chat.key";
+
+/// Prefix distinguishing encrypted message bodies from the plain text ones.
+const E2E_MAGIC: [u8; 4] = [0x00, b'E', b'2', b'E'];
+
+/// Prefix distinguishing chat key announcements from the messages.
+const KEY_MAGIC: [u8; 4] = [0x00, b'E', b'2', b'K'];
+
+/// Tag used in derivation of the conversation key from ECDH shared secret.
+const E2E_KEY_TAG: &[u8] = b"storm:chat:e2e";
+
+#[derive(Clone, Eq, PartialEq, Debug)]
+#[derive(StrictEncode, StrictDecode)]
+struct Envelope {
+    nonce: u64,
+    ciphertext: Vec<u8>,
+}
+
+/// Public chat key of a node, sent to the remote peer to enable encryption of the messages.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+#[derive(StrictEncode, StrictDecode)]
+pub struct KeyAnnouncement {
+    pub key: PublicKey,
+    /// Whether the announcement responds to the announcement of the remote peer, which must not
+    /// be responded again.
+    pub reply: bool,
+}
+
+impl KeyAnnouncement {
+    /// Encodes announcement as a message body.
+    pub fn to_body(self) -> Vec<u8> {
+        let mut body = KEY_MAGIC.to_vec();
+        self.strict_encode(&mut body).expect("memory encoder");
+        body
+    }
+}
+
+/// Message body received from the remote peer
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub enum Incoming {
+    /// Chat message together with the nonce, which is present only for the decrypted messages.
+    Chat(ChatMsg, Option<u64>),
+
+    /// Chat key of the remote peer
+    Key(KeyAnnouncement),
+}
+
+/// Nonces of the sent messages, which are microseconds since the UNIX epoch, incremented if the
+/// clock did not advance since the previous message.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Default)]
+#[derive(StrictEncode, StrictDecode)]
+pub struct NonceSequence(u64);
+
+impl NonceSequence {
+    pub fn next(&mut self) -> u64 {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_micros() as u64)
+            .unwrap_or_default();
+        self.0 = cmp::max(now, self.0 + 1);
+        self.0
+    }
+}
+
+fn direction_key(node: &LocalNode, remote_key: PublicKey, sender: PublicKey) -> [u8; 32] {
+    let local_key = node.node_id().public_key();
+    let receiver = if sender == local_key { remote_key } else { local_key };
+    shared_key(E2E_KEY_TAG, &remote_key, &node.private_key(), &[
+        &sender.serialize(),
+        &receiver.serialize(),
+    ])
+}
+
+fn associated_data(room: MesgId) -> Vec<u8> { room.strict_serialize().expect("memory encoder") }
+
+/// Encrypts chat message text to the remote peer with chat key `remote_key`, returning message
+/// body.
+pub fn encrypt(
+    node: &LocalNode,
+    remote_key: PublicKey,
+    room: MesgId,
+    nonce: u64,
+    text: &str,
+) -> Vec<u8> {
+    let key = direction_key(node, remote_key, node.node_id().public_key());
+    let mut ciphertext = vec![0u8; text.len() + chacha::TAG_SIZE];
+    chacha::encrypt(&key, nonce, &associated_data(room), text.as_bytes(), &mut ciphertext)
+        .expect("ciphertext buffer size always matches plaintext");
+
+    let mut body = E2E_MAGIC.to_vec();
+    Envelope { nonce, ciphertext }.strict_encode(&mut body).expect("memory encoder");
+    body
+}
+
+/// Decodes message body received from the remote peer, decrypting and authenticating encrypted
+/// messages.
+///
+/// Plain text bodies are passed as is, flagged with [`ChatProtection::Plaintext`]. Encrypted
+/// bodies which can't be decrypted (including the case when the chat key of the remote peer is
+/// not known) are flagged with [`ChatProtection::Unverifiable`].
+pub fn decode(
+    node: &LocalNode,
+    remote_key: Option<PublicKey>,
+    room: MesgId,
+    body: &[u8],
+) -> Incoming {
+    if let Some(data) = body.strip_prefix(&KEY_MAGIC[..]) {
+        if let Ok(announcement) = KeyAnnouncement::strict_deserialize(data) {
+            return Incoming::Key(announcement);
+        }
+    }
+    let envelope = match body.strip_prefix(&E2E_MAGIC[..]) {
+        None => {
+            let msg = ChatMsg {
+                text: String::from_utf8_lossy(body).to_string(),
+                protection: ChatProtection::Plaintext,
+            };
+            return Incoming::Chat(msg, None);
+        }
+        Some(data) => Envelope::strict_deserialize(data),
+    };
+
+    let unverifiable = Incoming::Chat(
+        ChatMsg {
+            text: s!(""),
+            protection: ChatProtection::Unverifiable,
+        },
+        None,
+    );
+    let (remote_key, envelope) = match (remote_key, envelope) {
+        (Some(remote_key), Ok(envelope)) if envelope.ciphertext.len() >= chacha::TAG_SIZE => {
+            (remote_key, envelope)
+        }
+        _ => return unverifiable,
+    };
+
+    let key = direction_key(node, remote_key, remote_key);
+    let mut plaintext = vec![0u8; envelope.ciphertext.len() - chacha::TAG_SIZE];
+    if chacha::decrypt(
+        &key,
+        envelope.nonce,
+        &associated_data(room),
+        &envelope.ciphertext,
+        &mut plaintext,
+    )
+    .is_err()
+    {
+        return unverifiable;
+    }
+    match String::from_utf8(plaintext) {
+        Ok(text) => Incoming::Chat(
+            ChatMsg {
+                text,
+                protection: ChatProtection::Encrypted,
+            },
+            Some(envelope.nonce),
+        ),
+        Err(_) => unverifiable,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use amplify::Wrapper;
+    use bitcoin_hashes::Hash;
+    use secp256k1::SecretKey;
+
+    use super::*;
+    use crate::chatd::peers::PeerKey;
+
+    fn node(byte: u8) -> LocalNode {
+        LocalNode::with(secp256k1::SECP256K1, SecretKey::from_slice(&[byte; 32]).unwrap())
+    }
+
+    fn key(node: &LocalNode) -> PublicKey { node.node_id().public_key() }
+
+    fn room() -> MesgId { MesgId::from_inner(Hash::hash(b"room")) }
+
+    #[test]
+    fn roundtrip() {
+        let (alice, bob) = (node(1), node(2));
+        let body = encrypt(&alice, key(&bob), room(), 42, "hello");
+        assert_eq!(
+            decode(&bob, Some(key(&alice)), room(), &body),
+            Incoming::Chat(
+                ChatMsg {
+                    text: s!("hello"),
+                    protection: ChatProtection::Encrypted
+                },
+                Some(42)
+            )
+        );
+    }
+
+    #[test]
+    fn directions_use_different_keys() {
+        let (alice, bob) = (node(1), node(2));
+        assert_ne!(
+            direction_key(&alice, key(&bob), key(&alice)),
+            direction_key(&alice, key(&bob), key(&bob))
+        );
+        assert_eq!(
+            direction_key(&alice, key(&bob), key(&alice)),
+            direction_key(&bob, key(&alice), key(&alice))
+        );
+
+        // Message sent by Alice can't be passed back to her as coming from Bob
+        let body = encrypt(&alice, key(&bob), room(), 42, "hello");
+        assert!(matches!(
+            decode(&alice, Some(key(&bob)), room(), &body),
+            Incoming::Chat(
+                ChatMsg {
+                    protection: ChatProtection::Unverifiable,
+                    ..
+                },
+                None
+            )
+        ));
+    }
+
+    #[test]
+    fn unverifiable() {
+        let (alice, bob, eve) = (node(1), node(2), node(3));
+        let body = encrypt(&alice, key(&bob), room(), 42, "hello");
+        let unverifiable = |incoming| {
+            matches!(
+                incoming,
+                Incoming::Chat(
+                    ChatMsg {
+                        protection: ChatProtection::Unverifiable,
+                        ..
+                    },
+                    None
+                )
+            )
+        };
+
+        assert!(unverifiable(decode(&bob, None, room(), &body)));
+        assert!(unverifiable(decode(&bob, Some(key(&eve)), room(), &body)));
+        assert!(unverifiable(decode(&eve, Some(key(&alice)), room(), &body)));
+        assert!(unverifiable(decode(&bob, Some(key(&alice)), zero!(), &body)));
+
+        let mut tampered = body.clone();
+        *tampered.last_mut().unwrap() ^= 1;
+        assert!(unverifiable(decode(&bob, Some(key(&alice)), room(), &tampered)));
+        assert!(unverifiable(decode(&bob, Some(key(&alice)), room(), &body[..body.len() - 1])));
+    }
+
+    #[test]
+    fn plaintext() {
+        assert_eq!(
+            decode(&node(1), Some(key(&node(2))), room(), b"hello"),
+            Incoming::Chat(
+                ChatMsg {
+                    text: s!("hello"),
+                    protection: ChatProtection::Plaintext
+                },
+                None
+            )
+        );
+    }
+
+    #[test]
+    fn key_announcement() {
+        let announcement = KeyAnnouncement {
+            key: key(&node(1)),
+            reply: true,
+        };
+        assert_eq!(
+            decode(&node(2), None, room(), &announcement.to_body()),
+            Incoming::Key(announcement)
+        );
+    }
+
+    #[test]
+    fn nonce_sequence() {
+        let mut nonces = NonceSequence::default();
+        let mut last = nonces.next();
+        for _ in 0..1000 {
+            let nonce = nonces.next();
+            assert!(nonce > last);
+            last = nonce;
+        }
+    }
+
+    #[test]
+    fn restored_nonce_sequence() {
+        let mut nonces = NonceSequence::default();
+        nonces.next();
+        // Peer key saved before the clock was set back by a day
+        let saved = PeerKey {
+            key: key(&node(1)),
+            last_nonce: 0,
+            sent_nonces: NonceSequence(nonces.0 + 86_400_000_000),
+        };
+        let restored = PeerKey::strict_deserialize(saved.strict_serialize().unwrap()).unwrap();
+        assert_eq!(restored, saved);
+        let mut restored = restored.sent_nonces;
+        let mut last = saved.sent_nonces.0;
+        for _ in 0..1000 {
+            let nonce = restored.next();
+            assert!(nonce > last);
+            last = nonce;
+        }
+    }
+}
@@ -0,0 +1,222 @@
+// Storm node providing distributed storage & messaging for lightning network.
+//
+// Written in 2022 by
+//     Dr. Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2022 by LNP/BP Standards Association, Switzerland.
+//
+// You should have received a copy of the MIT License along with this software.
+// If not, see <https://opensource.org/licenses/MIT>.
+
+//! Chat history, kept in the Store database as a single record per remote peer.
+//!
+//! Records are encrypted at rest with ChaCha20-Poly1305, so the Store database never sees message
+//! texts. Each write picks a new random salt, which is kept in front of the ciphertext, and
+//! derives a new key from it together with the chat key (see [`super::CHAT_KEY_FILE`]) and the
+//! remote node id. Since a key is never used twice, the nonce is always zero.
+//!
+//! The record must fit into a single chunk, so besides keeping at most [`CHAT_HISTORY_LIMIT`]
+//! messages the history drops the oldest ones while its encrypted size exceeds [`CHUNK_SIZE`].
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use bitcoin_hashes::{sha256, Hash};
+use internet2::addr::{LocalNode, NodeId};
+use internet2::session::noise::chacha;
+use storm::Chunk;
+use storm_rpc::{
+    shared_key, ChatDirection, ChatMsg, ChatRecord, CHUNK_SIZE, DB_TABLE_CHAT_HISTORY,
+};
+use strict_encoding::{StrictDecode, StrictEncode};
+
+use super::Runtime;
+use crate::DaemonError;
+
+/// Maximal number of the latest messages kept in the history of each peer
+pub const CHAT_HISTORY_LIMIT: usize = 1000;
+
+/// Tag used in derivation of the history key from the chat key.
+const HISTORY_KEY_TAG: &[u8] = b"storm:chat:history";
+
+const SALT_SIZE: usize = 32;
+
+/// Size of the salt and the authentication tag added to the encoded history by encryption.
+const SEAL_OVERHEAD: usize = SALT_SIZE + chacha::TAG_SIZE;
+
+impl Runtime {
+    /// Returns chat history with the remote peer, oldest messages first.
+    pub(super) fn history(&mut self, remote_id: NodeId) -> Result<Vec<ChatRecord>, DaemonError> {
+        let chunk: Option<Chunk> =
+            self.store.retrieve_chunk(DB_TABLE_CHAT_HISTORY, peer_store_key(remote_id)?)?;
+        let chunk = match chunk {
+            None => return Ok(vec![]),
+            Some(chunk) => chunk,
+        };
+        let data = open(&self.node, remote_id, chunk.as_slice())?;
+        Ok(Vec::<ChatRecord>::strict_deserialize(data)?)
+    }
+
+    /// Appends message to the chat history with the remote peer, dropping the oldest messages
+    /// above [`CHAT_HISTORY_LIMIT`] or [`CHUNK_SIZE`].
+    pub(super) fn history_append(
+        &mut self,
+        remote_id: NodeId,
+        direction: ChatDirection,
+        msg: ChatMsg,
+    ) -> Result<(), DaemonError> {
+        let mut records = self.history(remote_id)?;
+        records.push(ChatRecord {
+            timestamp: unix_time(),
+            direction,
+            msg,
+        });
+        let data = truncate(records, CHUNK_SIZE - SEAL_OVERHEAD)?;
+        let chunk = Chunk::try_from(seal(&self.node, remote_id, &data)?)?;
+        self.store.store(DB_TABLE_CHAT_HISTORY, peer_store_key(remote_id)?, &chunk)?;
+        Ok(())
+    }
+}
+
+/// Drops the oldest records so that at most [`CHAT_HISTORY_LIMIT`] of them remain and their
+/// encoding doesn't exceed `max_size`, returning the encoded history.
+fn truncate(mut records: Vec<ChatRecord>, max_size: usize) -> Result<Vec<u8>, DaemonError> {
+    if records.len() > CHAT_HISTORY_LIMIT {
+        records.drain(..records.len() - CHAT_HISTORY_LIMIT);
+    }
+    // Vectors are encoded as a 16-bit item count followed by the items
+    let mut size = 2;
+    let mut keep = 0;
+    for record in records.iter().rev() {
+        let len = record.strict_serialize()?.len();
+        if size + len > max_size {
+            break;
+        }
+        size += len;
+        keep += 1;
+    }
+    records.drain(..records.len() - keep);
+    Ok(records.strict_serialize()?)
+}
+
+fn history_key(node: &LocalNode, remote_id: NodeId, salt: &[u8]) -> Result<[u8; 32], DaemonError> {
+    let public_key = node.node_id().public_key();
+    Ok(shared_key(HISTORY_KEY_TAG, &public_key, &node.private_key(), &[
+        &remote_id.strict_serialize()?,
+        salt,
+    ]))
+}
+
+/// Encrypts encoded history with the remote peer.
+fn seal(node: &LocalNode, remote_id: NodeId, data: &[u8]) -> Result<Vec<u8>, DaemonError> {
+    let salt: [u8; SALT_SIZE] = rand::random();
+    let key = history_key(node, remote_id, &salt)?;
+    let mut sealed = vec![0u8; SEAL_OVERHEAD + data.len()];
+    sealed[..SALT_SIZE].copy_from_slice(&salt);
+    chacha::encrypt(&key, 0, &[], data, &mut sealed[SALT_SIZE..])
+        .expect("ciphertext buffer size always matches plaintext");
+    Ok(sealed)
+}
+
+/// Decrypts history with the remote peer, failing if it was not encrypted by the local chat key.
+fn open(node: &LocalNode, remote_id: NodeId, sealed: &[u8]) -> Result<Vec<u8>, DaemonError> {
+    let corrupted = || {
+        strict_encoding::Error::DataIntegrityError(format!(
+            "chat history with {} can't be decrypted",
+            remote_id
+        ))
+    };
+    if sealed.len() < SEAL_OVERHEAD {
+        return Err(corrupted().into());
+    }
+    let (salt, ciphertext) = sealed.split_at(SALT_SIZE);
+    let key = history_key(node, remote_id, salt)?;
+    let mut data = vec![0u8; ciphertext.len() - chacha::TAG_SIZE];
+    chacha::decrypt(&key, 0, &[], ciphertext, &mut data).map_err(|_| corrupted())?;
+    Ok(data)
+}
+
+/// Store keys are 32 bytes long, while node ids are 33-byte public keys, so they are hashed.
+pub(super) fn peer_store_key(remote_id: NodeId) -> Result<sha256::Hash, DaemonError> {
+    Ok(sha256::Hash::hash(&remote_id.strict_serialize()?))
+}
+
+fn unix_time() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod test {
+    use secp256k1::SecretKey;
+    use storm_rpc::ChatProtection;
+
+    use super::*;
+
+    fn node(byte: u8) -> LocalNode {
+        LocalNode::with(secp256k1::SECP256K1, SecretKey::from_slice(&[byte; 32]).unwrap())
+    }
+
+    fn record(timestamp: u64, len: usize) -> ChatRecord {
+        ChatRecord {
+            timestamp,
+            direction: ChatDirection::Incoming,
+            msg: ChatMsg {
+                text: "x".repeat(len),
+                protection: ChatProtection::Plaintext,
+            },
+        }
+    }
+
+    fn decode(data: Vec<u8>) -> Vec<ChatRecord> {
+        Vec::<ChatRecord>::strict_deserialize(data).unwrap()
+    }
+
+    #[test]
+    fn count_limit() {
+        let records = (0..CHAT_HISTORY_LIMIT as u64 + 10).map(|ts| record(ts, 1)).collect();
+        let history = decode(truncate(records, CHUNK_SIZE).unwrap());
+        assert_eq!(history.len(), CHAT_HISTORY_LIMIT);
+        assert_eq!(history[0].timestamp, 10);
+    }
+
+    #[test]
+    fn size_limit() {
+        let records: Vec<_> = (0..500).map(|ts| record(ts, 60_000)).collect();
+        let data = truncate(records, CHUNK_SIZE - SEAL_OVERHEAD).unwrap();
+        let sealed = seal(&node(1), node(2).node_id(), &data).unwrap();
+        assert!(Chunk::try_from(sealed).is_ok());
+
+        let history = decode(data);
+        assert!(history.len() < 500);
+        let record_size = history[0].strict_serialize().unwrap().len();
+        assert_eq!(history.len(), (CHUNK_SIZE - SEAL_OVERHEAD - 2) / record_size);
+        assert_eq!(history.last().unwrap().timestamp, 499);
+    }
+
+    #[test]
+    fn small_history() {
+        let records = vec![record(1, 10), record(2, 20)];
+        assert_eq!(decode(truncate(records.clone(), CHUNK_SIZE).unwrap()), records);
+    }
+
+    #[test]
+    fn encryption() {
+        let (alice, bob, carol) = (node(1), node(2), node(3));
+        let data = truncate(vec![record(1, 10)], CHUNK_SIZE).unwrap();
+        let sealed = seal(&alice, bob.node_id(), &data).unwrap();
+        assert_eq!(sealed.len(), data.len() + SEAL_OVERHEAD);
+        assert!(!sealed.windows(10).any(|window| window == b"xxxxxxxxxx"));
+        // Each write uses a fresh key
+        assert_ne!(sealed, seal(&alice, bob.node_id(), &data).unwrap());
+
+        assert_eq!(open(&alice, bob.node_id(), &sealed).unwrap(), data);
+        assert!(open(&bob, bob.node_id(), &sealed).is_err());
+        assert!(open(&alice, carol.node_id(), &sealed).is_err());
+        assert!(open(&alice, bob.node_id(), &sealed[..SEAL_OVERHEAD - 1]).is_err());
+        let mut tampered = sealed.clone();
+        *tampered.last_mut().unwrap() ^= 1;
+        assert!(open(&alice, bob.node_id(), &tampered).is_err());
+    }
+}
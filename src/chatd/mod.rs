@@ -11,7 +11,12 @@
 mod service;
 #[cfg(feature = "server")]
 mod opts;
+mod e2e;
+mod history;
+mod peers;
 
+pub use e2e::CHAT_KEY_FILE;
+pub use history::CHAT_HISTORY_LIMIT;
 #[cfg(feature = "server")]
 pub use opts::Opts;
 pub use service::{run, Runtime};
@@ -0,0 +1,62 @@
+// Storm node providing distributed storage & messaging for lightning network.
+//
+// Written in 2022 by
+//     Dr. Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2022 by LNP/BP Standards Association, Switzerland.
+//
+// You should have received a copy of the MIT License along with this software.
+// If not, see <https://opensource.org/licenses/MIT>.
+
+//! Chat keys announced by the remote peers, kept in the Store database and cached in memory.
+
+use internet2::addr::NodeId;
+use secp256k1::PublicKey;
+use storm::Chunk;
+use storm_rpc::DB_TABLE_CHAT_KEYS;
+use strict_encoding::{StrictDecode, StrictEncode};
+
+use super::e2e::NonceSequence;
+use super::history::peer_store_key;
+use super::Runtime;
+use crate::DaemonError;
+
+/// Chat key of a remote peer
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+#[derive(StrictEncode, StrictDecode)]
+pub(super) struct PeerKey {
+    pub key: PublicKey,
+    /// Nonce of the last message accepted from the peer; messages with the same or lower nonces
+    /// are replays.
+    pub last_nonce: u64,
+    /// Nonces of the messages sent to the peer, kept such that they are not reused after restart.
+    pub sent_nonces: NonceSequence,
+}
+
+impl Runtime {
+    /// Returns chat key of the remote peer, if the peer has announced it.
+    pub(super) fn peer_key(&mut self, remote_id: NodeId) -> Result<Option<PeerKey>, DaemonError> {
+        if let Some(peer_key) = self.peer_keys.get(&remote_id) {
+            return Ok(Some(*peer_key));
+        }
+        let chunk: Option<Chunk> =
+            self.store.retrieve_chunk(DB_TABLE_CHAT_KEYS, peer_store_key(remote_id)?)?;
+        let peer_key =
+            chunk.map(|chunk| PeerKey::strict_deserialize(chunk.as_slice())).transpose()?;
+        if let Some(peer_key) = peer_key {
+            self.peer_keys.insert(remote_id, peer_key);
+        }
+        Ok(peer_key)
+    }
+
+    pub(super) fn save_peer_key(
+        &mut self,
+        remote_id: NodeId,
+        peer_key: PeerKey,
+    ) -> Result<(), DaemonError> {
+        let chunk = Chunk::try_from(peer_key.strict_serialize()?)?;
+        self.store.store(DB_TABLE_CHAT_KEYS, peer_store_key(remote_id)?, &chunk)?;
+        self.peer_keys.insert(remote_id, peer_key);
+        Ok(())
+    }
+}
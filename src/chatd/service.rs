@@ -8,19 +8,26 @@
 // You should have received a copy of the MIT License along with this software.
 // If not, see <https://opensource.org/licenses/MIT>.
 
+use std::collections::{HashMap, HashSet};
 use std::thread;
 use std::time::Duration;
 
+use internet2::addr::{LocalNode, NodeId};
 use internet2::ZmqSocketType;
 use microservices::error::BootstrapError;
-use microservices::esb::{self, ClientId, EndpointList, Error};
+use microservices::esb::{self, EndpointList, Error};
 use microservices::node::TryService;
 use storm::Mesg;
 use storm_ext::ExtMsg;
-use storm_rpc::{AddressedMsg, RpcMsg, ServiceId};
+use storm_rpc::{
+    AddressedMsg, ChatDirection, ChatMsg, ChatProtection, ChatTopic, ClientRequest, RpcMsg,
+    ServiceId, DB_TABLE_CHAT_HISTORY, DB_TABLE_CHAT_KEYS,
+};
 
+use super::e2e::{self, Incoming, KeyAnnouncement, CHAT_KEY_FILE};
+use super::peers::PeerKey;
 use crate::bus::{BusMsg, CtlMsg, Endpoints, Responder, ServiceBus};
-use crate::{Config, DaemonError, LaunchError};
+use crate::{Config, DaemonError, LaunchError, RpcAuth};
 
 pub fn run(config: Config) -> Result<(), BootstrapError<LaunchError>> {
     let rpc_endpoint = config.rpc_endpoint.clone();
@@ -66,19 +73,47 @@ pub fn run(config: Config) -> Result<(), BootstrapError<LaunchError>> {
 }
 
 pub struct Runtime {
-    #[allow(dead_code)]
     pub(super) store: store_rpc::Client,
+
+    /// Local chat key used for end-to-end message encryption
+    pub(super) node: LocalNode,
+
+    /// Cache of the chat keys announced by the remote peers
+    pub(super) peer_keys: HashMap<NodeId, PeerKey>,
+
+    /// Peers to which the local chat key was announced since the daemon start
+    announced: HashSet<NodeId>,
+
+    /// Access tokens of RPC clients
+    rpc_auth: RpcAuth,
 }
 
 impl Runtime {
     pub fn init(config: Config) -> Result<Self, BootstrapError<LaunchError>> {
         debug!("Connecting to store service at {}", config.store_endpoint);
 
-        let store = store_rpc::Client::with(&config.store_endpoint).map_err(LaunchError::from)?;
+        let mut store =
+            store_rpc::Client::with(&config.store_endpoint).map_err(LaunchError::from)?;
+        store.use_table(DB_TABLE_CHAT_HISTORY.to_owned()).map_err(LaunchError::from)?;
+
+        store.use_table(DB_TABLE_CHAT_KEYS.to_owned()).map_err(LaunchError::from)?;
+
+        let key_file = config.data_dir.join(CHAT_KEY_FILE);
+        debug!("Reading chat key from {}", key_file.display());
+        let node = storm_rpc::read_or_create_node_key(&key_file).map_err(LaunchError::from)?;
+        info!("Using chat key {} for end-to-end encryption of chat messages", node.node_id());
+
+        let rpc_auth = RpcAuth::load(&config.data_dir)?;
 
         info!("Chat runtime started successfully");
 
-        Ok(Self { store })
+        Ok(Self {
+            store,
+            node,
+            peer_keys: empty!(),
+            announced: empty!(),
+            rpc_auth,
+        })
     }
 }
 
@@ -109,8 +144,22 @@ impl esb::Handler<ServiceBus> for Runtime {
             {
                 self.handle_storm(endpoints, msg)
             }
-            (ServiceBus::Rpc, BusMsg::Rpc(msg), ServiceId::Client(client_id)) => {
-                self.handle_rpc(endpoints, client_id, msg)
+            (ServiceBus::Rpc, BusMsg::Rpc(envelope), ServiceId::Client(client_id)) => {
+                let client = ClientRequest {
+                    client_id,
+                    request_id: envelope.request_id,
+                };
+                match self.rpc_auth.check(&envelope) {
+                    Ok(role) => {
+                        trace!("Client {} is authenticated as {}", client_id, role);
+                        self.handle_rpc(endpoints, client, envelope.msg)
+                    }
+                    Err(err) => {
+                        warn!("Rejecting request from client {}: {}", client, err);
+                        self.send_rpc(endpoints, client, err)?;
+                        Ok(())
+                    }
+                }
             }
             (ServiceBus::Ctl, BusMsg::Ctl(msg), source) => self.handle_ctl(endpoints, source, msg),
             (bus, msg, _) => Err(DaemonError::wrong_esb_msg(bus, &msg)),
@@ -137,11 +186,46 @@ impl Runtime {
     ) -> Result<(), DaemonError> {
         match message {
             ExtMsg::Post(AddressedMsg { remote_id, data }) => {
+                let room = if data.parent_id == zero!() { None } else { Some(data.parent_id) };
+                let peer_key = self.peer_key(remote_id)?;
+                let msg = match e2e::decode(
+                    &self.node,
+                    peer_key.map(|peer_key| peer_key.key),
+                    data.parent_id,
+                    &data.body,
+                ) {
+                    Incoming::Key(announcement) => {
+                        return self.accept_key(endpoints, remote_id, announcement)
+                    }
+                    Incoming::Chat(msg, Some(nonce)) => {
+                        let mut peer_key = peer_key.expect("message is decrypted with peer key");
+                        if nonce <= peer_key.last_nonce {
+                            warn!("Ignoring replayed chat message from {}", remote_id);
+                            return Ok(());
+                        }
+                        peer_key.last_nonce = nonce;
+                        self.save_peer_key(remote_id, peer_key)?;
+                        msg
+                    }
+                    Incoming::Chat(msg, None) => msg,
+                };
+                if msg.protection == ChatProtection::Unverifiable {
+                    warn!("Unable to decrypt or authenticate chat message from {}", remote_id);
+                    // The peer may have lost our key or changed its own one
+                    if !self.announced.contains(&remote_id) {
+                        self.announce_key(endpoints, remote_id, false)?;
+                    }
+                }
                 let chat_msg = AddressedMsg {
                     remote_id,
-                    data: String::from_utf8_lossy(&data.body).to_string(),
+                    data: msg,
                 };
-                self.send_radio(endpoints, chat_msg)?;
+                if let Err(err) =
+                    self.history_append(remote_id, ChatDirection::Incoming, chat_msg.data.clone())
+                {
+                    warn!("Unable to save chat message from {} to the history: {}", remote_id, err);
+                }
+                self.send_radio(endpoints, ChatTopic::with(remote_id, room), chat_msg)?;
             }
             wrong_msg => {
                 error!("Request is not supported by the Storm interface");
@@ -155,20 +239,63 @@ impl Runtime {
     fn handle_rpc(
         &mut self,
         endpoints: &mut Endpoints,
-        _client_id: ClientId,
+        client: ClientRequest,
         message: RpcMsg,
     ) -> Result<(), DaemonError> {
         match message {
             RpcMsg::SendChat(AddressedMsg { remote_id, data }) => {
+                let parent_id = none!();
+                let peer_key = match self.peer_key(remote_id) {
+                    Ok(peer_key) => peer_key,
+                    Err(err) => {
+                        self.send_rpc(endpoints, client, err)?;
+                        return Ok(());
+                    }
+                };
+                let (body, protection) = match peer_key {
+                    Some(mut peer_key) => {
+                        // Nonce is saved before use, so it is never reused after restart
+                        let nonce = peer_key.sent_nonces.next();
+                        if let Err(err) = self.save_peer_key(remote_id, peer_key) {
+                            self.send_rpc(endpoints, client, err)?;
+                            return Ok(());
+                        }
+                        let body = e2e::encrypt(&self.node, peer_key.key, parent_id, nonce, &data);
+                        (body, ChatProtection::Encrypted)
+                    }
+                    None => {
+                        // Messages are sent in plain text until the peer announces its key
+                        if !self.announced.contains(&remote_id) {
+                            self.announce_key(endpoints, remote_id, false)?;
+                        }
+                        (data.as_bytes().to_vec(), ChatProtection::Plaintext)
+                    }
+                };
                 let addressed_msg = AddressedMsg {
                     remote_id,
                     data: Mesg {
-                        parent_id: none!(),
-                        body: data.into_bytes(),
+                        parent_id,
+                        body,
                         container_ids: empty!(),
                     },
                 };
                 self.send_ext(endpoints, None, ExtMsg::Post(addressed_msg))?;
+                let msg = ChatMsg {
+                    text: data,
+                    protection,
+                };
+                if let Err(err) = self.history_append(remote_id, ChatDirection::Outgoing, msg) {
+                    warn!("Unable to save chat message to {} to the history: {}", remote_id, err);
+                }
+                self.send_rpc(endpoints, client, RpcMsg::Success(None.into()))?;
+            }
+
+            RpcMsg::ChatHistory(remote_id) => {
+                let reply = match self.history(remote_id) {
+                    Ok(records) => RpcMsg::ChatRecords(records),
+                    Err(err) => err.into(),
+                };
+                self.send_rpc(endpoints, client, reply)?;
             }
 
             wrong_msg => {
@@ -180,6 +307,61 @@ impl Runtime {
         Ok(())
     }
 
+    /// Saves chat key announced by the remote peer, responding with the local key.
+    fn accept_key(
+        &mut self,
+        endpoints: &mut Endpoints,
+        remote_id: NodeId,
+        announcement: KeyAnnouncement,
+    ) -> Result<(), DaemonError> {
+        let known = self.peer_key(remote_id)?;
+        let last_nonce = match known {
+            Some(peer_key) if peer_key.key == announcement.key => peer_key.last_nonce,
+            _ => {
+                info!(
+                    "Peer {} announced chat key {}; messages to it will be end-to-end encrypted",
+                    remote_id, announcement.key
+                );
+                0
+            }
+        };
+        self.save_peer_key(remote_id, PeerKey {
+            key: announcement.key,
+            last_nonce,
+            // Sent nonces are never reset, so they are not reused if the peer announces its
+            // previous key again
+            sent_nonces: known.map(|peer_key| peer_key.sent_nonces).unwrap_or_default(),
+        })?;
+        if !announcement.reply {
+            self.announce_key(endpoints, remote_id, true)?;
+        }
+        Ok(())
+    }
+
+    fn announce_key(
+        &mut self,
+        endpoints: &mut Endpoints,
+        remote_id: NodeId,
+        reply: bool,
+    ) -> Result<(), DaemonError> {
+        debug!("Announcing chat key to {}", remote_id);
+        let announcement = KeyAnnouncement {
+            key: self.node.node_id().public_key(),
+            reply,
+        };
+        let addressed_msg = AddressedMsg {
+            remote_id,
+            data: Mesg {
+                parent_id: zero!(),
+                body: announcement.to_body(),
+                container_ids: empty!(),
+            },
+        };
+        self.send_ext(endpoints, None, ExtMsg::Post(addressed_msg))?;
+        self.announced.insert(remote_id);
+        Ok(())
+    }
+
     fn handle_ctl(
         &mut self,
         _endpoints: &mut Endpoints,
@@ -0,0 +1,120 @@
+// Storm node providing distributed storage & messaging for lightning network.
+//
+// Written in 2022 by
+//     Dr. Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2022 by LNP/BP Standards Association, Switzerland.
+//
+// You should have received a copy of the MIT License along with this software.
+// If not, see <https://opensource.org/licenses/MIT>.
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use internet2::addr::NodeId;
+use storm::{Chunk, ContainerId, ContainerInfo};
+use storm_rpc::{gc, CatalogEntry, CatalogFilter, CatalogStatus, DB_TABLE_DOWNPOUR_CATALOG};
+use strict_encoding::{StrictDecode, StrictEncode};
+
+use super::Runtime;
+use crate::DaemonError;
+
+impl Runtime {
+    pub(super) fn catalog_entry(
+        &mut self,
+        container_id: ContainerId,
+    ) -> Result<Option<CatalogEntry>, DaemonError> {
+        // Entries of deleted containers are emptied by `gc::delete_container`
+        gc::retrieve_live(&mut self.store, DB_TABLE_DOWNPOUR_CATALOG, container_id)?
+            .map(|chunk| CatalogEntry::strict_deserialize(chunk.as_slice()))
+            .transpose()
+            .map_err(DaemonError::from)
+    }
+
+    pub(super) fn catalog_save(&mut self, entry: &CatalogEntry) -> Result<(), DaemonError> {
+        let chunk = Chunk::try_from(entry.strict_serialize()?)?;
+        self.store.store(DB_TABLE_DOWNPOUR_CATALOG, entry.info.id.container_id, &chunk)?;
+        Ok(())
+    }
+
+    /// Registers container announcement in the catalog, returning the updated catalog entry.
+    /// New entries get [`CatalogStatus::Pending`] status.
+    pub(super) fn catalog_announce(
+        &mut self,
+        remote_id: NodeId,
+        info: ContainerInfo,
+    ) -> Result<CatalogEntry, DaemonError> {
+        let entry = match self.catalog_entry(info.id.container_id)? {
+            Some(mut entry) => {
+                entry.remote_ids.insert(remote_id);
+                entry
+            }
+            None => CatalogEntry {
+                info,
+                remote_ids: bset! {remote_id},
+                first_seen: unix_time(),
+                status: CatalogStatus::Pending,
+            },
+        };
+        self.catalog_save(&entry)?;
+        Ok(entry)
+    }
+
+    /// Registers locally present container in the catalog with [`CatalogStatus::Complete`]
+    /// status, returning the updated catalog entry.
+    pub(super) fn catalog_complete(
+        &mut self,
+        info: ContainerInfo,
+    ) -> Result<CatalogEntry, DaemonError> {
+        let mut entry = self.catalog_entry(info.id.container_id)?.unwrap_or(CatalogEntry {
+            info,
+            remote_ids: empty!(),
+            first_seen: unix_time(),
+            status: CatalogStatus::Complete,
+        });
+        entry.status = CatalogStatus::Complete;
+        self.catalog_save(&entry)?;
+        Ok(entry)
+    }
+
+    /// Updates status of a container known to the catalog. Returns `false` if the container is
+    /// not in the catalog.
+    pub(super) fn catalog_set_status(
+        &mut self,
+        container_id: ContainerId,
+        status: CatalogStatus,
+    ) -> Result<bool, DaemonError> {
+        let mut entry = match self.catalog_entry(container_id)? {
+            Some(entry) => entry,
+            None => return Ok(false),
+        };
+        debug!("Catalog status of container {} changes to {}", container_id, status);
+        entry.status = status;
+        self.catalog_save(&entry)?;
+        Ok(true)
+    }
+
+    pub(super) fn catalog_list(
+        &mut self,
+        filter: &CatalogFilter,
+    ) -> Result<Vec<CatalogEntry>, DaemonError> {
+        let mut entries = vec![];
+        for id in self.store.ids(DB_TABLE_DOWNPOUR_CATALOG)? {
+            if let Some(chunk) = gc::retrieve_live(&mut self.store, DB_TABLE_DOWNPOUR_CATALOG, id)?
+            {
+                let entry = CatalogEntry::strict_deserialize(chunk.as_slice())?;
+                if filter.matches(&entry) {
+                    entries.push(entry);
+                }
+            }
+        }
+        entries.sort_by_key(|entry| entry.first_seen);
+        Ok(entries)
+    }
+}
+
+fn unix_time() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or_default()
+}
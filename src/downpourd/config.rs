@@ -0,0 +1,210 @@
+// Storm node providing distributed storage & messaging for lightning network.
+//
+// Written in 2022 by
+//     Dr. Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2022 by LNP/BP Standards Association, Switzerland.
+//
+// You should have received a copy of the MIT License along with this software.
+// If not, see <https://opensource.org/licenses/MIT>.
+
+use internet2::addr::NodeId;
+use storm::ContainerHeader;
+use storm_rpc::{has_mime, mime_base, PolicyViolation};
+
+#[cfg(feature = "server")]
+use super::{Opts, PolicyOpts};
+#[cfg(feature = "server")]
+use crate::opts::Options;
+
+/// Downpour policy for automatic acceptance of container announcements and seeding.
+///
+/// Announcements which do not match the policy are put on hold until the user accepts or
+/// declines them. Completed containers are announced to the seed peers.
+#[derive(Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug)]
+pub struct Config {
+    /// Maximal size of a container (in bytes) which can be downloaded automatically
+    pub max_size: u64,
+
+    /// MIME types of containers which can be downloaded automatically. Empty list allows any
+    /// MIME type.
+    pub mime_types: Vec<String>,
+
+    /// Peers which announcements can be accepted automatically. Empty list allows any peer.
+    pub trusted_peers: Vec<NodeId>,
+
+    /// Minimal free disk space (in bytes) which must remain after automatic download
+    pub min_free_space: u64,
+
+    /// Peers to which completed and locally seeded containers are announced
+    pub seed_peers: Vec<NodeId>,
+}
+
+impl Config {
+    /// Checks announcement of a container with a given header made by a `remote_id` peer against
+    /// the policy. If the free disk space is not known (`None`), the free disk space rule is not
+    /// applied.
+    pub fn check(
+        &self,
+        remote_id: NodeId,
+        header: &ContainerHeader,
+        free_space: Option<u64>,
+    ) -> Result<(), PolicyViolation> {
+        if !self.trusted_peers.is_empty() && !self.trusted_peers.contains(&remote_id) {
+            return Err(PolicyViolation::UntrustedPeer);
+        }
+        if header.size > self.max_size {
+            return Err(PolicyViolation::SizeExceeded {
+                size: header.size,
+                max: self.max_size,
+            });
+        }
+        if !self.mime_types.is_empty() && !self.mime_types.iter().any(|mime| has_mime(header, mime))
+        {
+            return Err(PolicyViolation::MimeNotAllowed(
+                mime_base(header.mime.as_str()).to_owned(),
+            ));
+        }
+        self.check_free_space(header, free_space)
+    }
+
+    /// Checks that download of a container with a given header leaves enough free disk space.
+    /// Unlike other policy rules, this one is also applied to the containers accepted by the user.
+    pub fn check_free_space(
+        &self,
+        header: &ContainerHeader,
+        free_space: Option<u64>,
+    ) -> Result<(), PolicyViolation> {
+        if let Some(free_space) = free_space {
+            let available = free_space.saturating_sub(header.size);
+            if available < self.min_free_space {
+                return Err(PolicyViolation::LowDiskSpace {
+                    available,
+                    required: self.min_free_space,
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "server")]
+impl From<PolicyOpts> for Config {
+    fn from(opts: PolicyOpts) -> Self {
+        Config {
+            max_size: opts.max_size,
+            mime_types: opts.mime_types,
+            trusted_peers: opts.trusted_peers,
+            min_free_space: opts.min_free_space,
+            seed_peers: opts.seed_peers,
+        }
+    }
+}
+
+#[cfg(feature = "server")]
+impl Options for Opts {
+    type Conf = Config;
+
+    fn shared(&self) -> &crate::opts::Opts { &self.shared }
+
+    fn config(&self) -> Self::Conf { self.policy.clone().into() }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn node_id(byte: u8) -> NodeId {
+        format!("02{}", format!("{:02x}", byte).repeat(32)).parse().unwrap()
+    }
+
+    fn header(mime: &str, size: u64) -> ContainerHeader {
+        ContainerHeader {
+            version: 0,
+            mime: mime.parse().unwrap(),
+            info: s!(""),
+            size,
+        }
+    }
+
+    fn config() -> Config {
+        Config {
+            max_size: 1000,
+            mime_types: vec![s!("text/plain")],
+            trusted_peers: vec![node_id(1)],
+            min_free_space: 500,
+            seed_peers: vec![],
+        }
+    }
+
+    #[test]
+    fn accepts_matching() {
+        let config = config();
+        assert_eq!(config.check(node_id(1), &header("text/plain", 1000), Some(1500)), Ok(()));
+        assert_eq!(config.check(node_id(1), &header("text/plain", 10), None), Ok(()));
+    }
+
+    #[test]
+    fn empty_lists_allow_any() {
+        let config = Config {
+            mime_types: vec![],
+            trusted_peers: vec![],
+            ..config()
+        };
+        assert_eq!(config.check(node_id(2), &header("image/png", 10), None), Ok(()));
+    }
+
+    #[test]
+    fn mime_parameters_ignored() {
+        let config = config();
+        let header = header("text/plain; chunking=cdc; compression=deflate; encryption=1", 10);
+        assert_eq!(config.check(node_id(1), &header, None), Ok(()));
+        assert_eq!(
+            config.check(node_id(1), &self::header("image/png; compression=deflate", 10), None),
+            Err(PolicyViolation::MimeNotAllowed(s!("image/png")))
+        );
+    }
+
+    #[test]
+    fn rules_order() {
+        let config = config();
+        // Violating all the rules reports the peer first, then size, MIME type and free space
+        assert_eq!(
+            config.check(node_id(2), &header("image/png", 2000), Some(0)),
+            Err(PolicyViolation::UntrustedPeer)
+        );
+        assert_eq!(
+            config.check(node_id(1), &header("image/png", 2000), Some(0)),
+            Err(PolicyViolation::SizeExceeded {
+                size: 2000,
+                max: 1000
+            })
+        );
+        assert_eq!(
+            config.check(node_id(1), &header("image/png", 1000), Some(0)),
+            Err(PolicyViolation::MimeNotAllowed(s!("image/png")))
+        );
+        assert_eq!(
+            config.check(node_id(1), &header("text/plain", 1000), Some(1200)),
+            Err(PolicyViolation::LowDiskSpace {
+                available: 200,
+                required: 500
+            })
+        );
+    }
+
+    #[test]
+    fn free_space() {
+        let config = config();
+        let header = header("image/png", 2000);
+        assert_eq!(config.check_free_space(&header, None), Ok(()));
+        assert_eq!(config.check_free_space(&header, Some(2500)), Ok(()));
+        assert_eq!(
+            config.check_free_space(&header, Some(1000)),
+            Err(PolicyViolation::LowDiskSpace {
+                available: 0,
+                required: 500
+            })
+        );
+    }
+}
@@ -9,9 +9,12 @@
 // If not, see <https://opensource.org/licenses/MIT>.
 
 mod service;
+mod catalog;
 #[cfg(feature = "server")]
 mod opts;
+mod config;
 
+pub use config::Config;
 #[cfg(feature = "server")]
-pub use opts::Opts;
+pub use opts::{Opts, PolicyOpts};
 pub use service::{run, Runtime};
@@ -8,9 +8,10 @@
 // You should have received a copy of the MIT License along with this software.
 // If not, see <https://opensource.org/licenses/MIT>.
 
-use clap::Parser;
+use clap::{Args, Parser};
+use internet2::addr::NodeId;
 
-use crate::opts::{Options, Opts as SharedOpts};
+use crate::opts::Opts as SharedOpts;
 
 /// Command-line arguments
 #[derive(Parser)]
@@ -21,14 +22,54 @@ pub struct Opts {
     /// command-line args or environment variables
     #[clap(flatten)]
     pub shared: SharedOpts,
+
+    #[clap(flatten)]
+    pub policy: PolicyOpts,
 }
 
-impl Options for Opts {
-    type Conf = ();
+/// Downpour policy for automatic acceptance of container announcements and seeding.
+///
+/// All options require `=` sign between the option name and its value, since they are passed
+/// by stormd to downpourd.
+#[derive(Args)]
+#[derive(Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug)]
+pub struct PolicyOpts {
+    /// Maximal size of a container (in bytes) which can be downloaded automatically.
+    #[clap(
+        long = "downpour-max-size",
+        require_equals = true,
+        default_value = "104857600",
+        value_name = "BYTES"
+    )]
+    pub max_size: u64,
+
+    /// MIME type of containers which can be downloaded automatically.
+    ///
+    /// Can be used multiple times. If not provided, containers of any MIME type are accepted.
+    #[clap(long = "downpour-mime", require_equals = true, value_name = "MIME")]
+    pub mime_types: Vec<String>,
+
+    /// Peer which container announcements can be downloaded automatically.
+    ///
+    /// Can be used multiple times. If not provided, announcements from any peer are accepted.
+    #[clap(long = "downpour-trust", require_equals = true, value_name = "NODE_ID")]
+    pub trusted_peers: Vec<NodeId>,
 
-    fn shared(&self) -> &crate::opts::Opts { &self.shared }
+    /// Minimal free disk space (in bytes) which must remain in the data directory after an
+    /// automatic download.
+    #[clap(
+        long = "downpour-min-free",
+        require_equals = true,
+        default_value = "1073741824",
+        value_name = "BYTES"
+    )]
+    pub min_free_space: u64,
 
-    fn config(&self) -> Self::Conf { () }
+    /// Peer to which completed and locally seeded containers are announced.
+    ///
+    /// Can be used multiple times.
+    #[clap(long = "downpour-seed", require_equals = true, value_name = "NODE_ID")]
+    pub seed_peers: Vec<NodeId>,
 }
 
 #[cfg(feature = "server")]
@@ -8,20 +8,29 @@
 // You should have received a copy of the MIT License along with this software.
 // If not, see <https://opensource.org/licenses/MIT>.
 
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::PathBuf;
 use std::thread;
 use std::time::Duration;
 
+use internet2::addr::NodeId;
 use internet2::ZmqSocketType;
 use microservices::error::BootstrapError;
-use microservices::esb::{self, ClientId, EndpointList, Error};
+use microservices::esb::{self, EndpointList, Error};
 use microservices::node::TryService;
+use nix::sys::statvfs::statvfs;
+use storm::{ContainerFullId, ContainerHeader, ContainerId, ContainerInfo};
 use storm_ext::ExtMsg;
-use storm_rpc::{AddressedMsg, RpcMsg, ServiceId};
+use storm_rpc::{
+    gc, AddressedMsg, CatalogFilter, CatalogStatus, ClientRequest, PendingContainer,
+    PolicyViolation, RpcMsg, ServiceId, DB_TABLE_CONTAINER_HEADERS, DB_TABLE_DOWNPOUR_CATALOG,
+};
+use strict_encoding::StrictDecode;
 
 use crate::bus::{BusMsg, CtlMsg, Endpoints, Responder, ServiceBus};
-use crate::{Config, DaemonError, LaunchError};
+use crate::{Config, DaemonError, LaunchError, RpcAuth};
 
-pub fn run(config: Config) -> Result<(), BootstrapError<LaunchError>> {
+pub fn run(config: Config<super::Config>) -> Result<(), BootstrapError<LaunchError>> {
     let rpc_endpoint = config.rpc_endpoint.clone();
     let ctl_endpoint = config.ctl_endpoint.clone();
     let ext_endpoint = config.ext_endpoint.clone();
@@ -56,19 +65,86 @@ pub fn run(config: Config) -> Result<(), BootstrapError<LaunchError>> {
 }
 
 pub struct Runtime {
-    #[allow(dead_code)]
     pub(super) store: store_rpc::Client,
+
+    pub(super) data_dir: PathBuf,
+
+    pub(super) policy: super::Config,
+
+    /// Container announcements put on hold by the policy, waiting for a user decision
+    pub(super) pending: BTreeMap<ContainerId, PendingContainer>,
+
+    /// Access tokens of RPC clients
+    rpc_auth: RpcAuth,
 }
 
 impl Runtime {
-    pub fn init(config: Config) -> Result<Self, BootstrapError<LaunchError>> {
+    pub fn init(config: Config<super::Config>) -> Result<Self, BootstrapError<LaunchError>> {
         debug!("Connecting to store service at {}", config.store_endpoint);
 
-        let store = store_rpc::Client::with(&config.store_endpoint).map_err(LaunchError::from)?;
+        let mut store =
+            store_rpc::Client::with(&config.store_endpoint).map_err(LaunchError::from)?;
+        for table in [DB_TABLE_CONTAINER_HEADERS, DB_TABLE_DOWNPOUR_CATALOG] {
+            store.use_table(table.to_owned()).map_err(LaunchError::from)?;
+        }
+
+        let rpc_auth = RpcAuth::load(&config.data_dir)?;
 
         info!("Downpour runtime started successfully");
 
-        Ok(Self { store })
+        let mut runtime = Self {
+            store,
+            data_dir: config.data_dir,
+            policy: config.ext,
+            pending: empty!(),
+            rpc_auth,
+        };
+        if let Err(err) = runtime.restore_pending() {
+            warn!("Unable to restore pending container announcements: {}", err);
+        }
+        Ok(runtime)
+    }
+
+    /// Puts containers with [`CatalogStatus::Pending`] catalog status back on hold after the
+    /// daemon restart.
+    fn restore_pending(&mut self) -> Result<(), DaemonError> {
+        let filter = CatalogFilter {
+            status: Some(CatalogStatus::Pending),
+            ..default!()
+        };
+        let free_space = self.free_space();
+        for entry in self.catalog_list(&filter)? {
+            let checks = entry
+                .remote_ids
+                .iter()
+                .map(|remote_id| self.policy.check(*remote_id, &entry.info.header, free_space))
+                .collect::<Vec<_>>();
+            let violation = match checks.iter().find_map(|check| check.clone().err()) {
+                Some(violation) if !checks.iter().any(Result::is_ok) => violation,
+                // Announcements accepted by the policy are put on hold only if their download
+                // has failed
+                _ => PolicyViolation::RetrievalFailed,
+            };
+            self.pending.insert(entry.info.id.container_id, PendingContainer {
+                info: entry.info,
+                remote_ids: entry.remote_ids,
+                violation,
+            });
+        }
+        if !self.pending.is_empty() {
+            info!("{} container announcements are on hold", self.pending.len());
+        }
+        Ok(())
+    }
+
+    fn free_space(&self) -> Option<u64> {
+        match statvfs(&self.data_dir) {
+            Ok(stat) => Some(stat.blocks_available() * stat.fragment_size()),
+            Err(err) => {
+                warn!("Unable to detect free disk space in {}: {}", self.data_dir.display(), err);
+                None
+            }
+        }
     }
 }
 
@@ -99,8 +175,22 @@ impl esb::Handler<ServiceBus> for Runtime {
             {
                 self.handle_storm(endpoints, msg)
             }
-            (ServiceBus::Rpc, BusMsg::Rpc(msg), ServiceId::Client(client_id)) => {
-                self.handle_rpc(endpoints, client_id, msg)
+            (ServiceBus::Rpc, BusMsg::Rpc(envelope), ServiceId::Client(client_id)) => {
+                let client = ClientRequest {
+                    client_id,
+                    request_id: envelope.request_id,
+                };
+                match self.rpc_auth.check(&envelope) {
+                    Ok(role) => {
+                        trace!("Client {} is authenticated as {}", client_id, role);
+                        self.handle_rpc(endpoints, client, envelope.msg)
+                    }
+                    Err(err) => {
+                        warn!("Rejecting request from client {}: {}", client, err);
+                        self.send_rpc(endpoints, client, err)?;
+                        Ok(())
+                    }
+                }
             }
             (ServiceBus::Ctl, BusMsg::Ctl(msg), source) => self.handle_ctl(endpoints, source, msg),
             (bus, msg, _) => Err(DaemonError::wrong_esb_msg(bus, &msg)),
@@ -127,17 +217,17 @@ impl Runtime {
     ) -> Result<(), DaemonError> {
         match message {
             ExtMsg::ContainerAnnouncement(AddressedMsg { remote_id, data }) => {
-                self.send_ext(
-                    endpoints,
-                    None,
-                    ExtMsg::RetrieveContainer(AddressedMsg {
-                        remote_id,
-                        data: data.id,
-                    }),
-                )?;
+                self.handle_announcement(endpoints, remote_id, data)?;
             }
 
             ExtMsg::RetrieveContainer(AddressedMsg { remote_id, data }) => {
+                if self.container_header(data.container_id)?.is_none() {
+                    warn!(
+                        "Peer {} requested container {} which is not present locally",
+                        remote_id, data.container_id
+                    );
+                    return Ok(());
+                }
                 self.send_ext(
                     endpoints,
                     None,
@@ -145,8 +235,40 @@ impl Runtime {
                 )?;
             }
 
-            ExtMsg::ContainerRetrieved(_) => {
-                warn!("No active action is known for this message")
+            ExtMsg::ContainerRetrieved(container_id) => match self.catalog_entry(container_id)? {
+                Some(entry) => {
+                    let entry = self.catalog_complete(entry.info)?;
+                    self.seed(endpoints, entry.info, &entry.remote_ids)?;
+                }
+                None => {
+                    warn!("Retrieved container {} is not known to the catalog", container_id)
+                }
+            },
+
+            ExtMsg::ContainerRetrievalFailed(container_id) => {
+                match self.catalog_entry(container_id)? {
+                    Some(entry) if entry.status == CatalogStatus::Downloading => {
+                        warn!(
+                            "Download of container {} has failed; it is put on hold",
+                            container_id
+                        );
+                        self.catalog_set_status(container_id, CatalogStatus::Pending)?;
+                        self.pending.insert(container_id, PendingContainer {
+                            info: entry.info,
+                            remote_ids: entry.remote_ids,
+                            violation: PolicyViolation::RetrievalFailed,
+                        });
+                    }
+                    Some(entry) => {
+                        debug!(
+                            "Download of container {} has failed while it is {}",
+                            container_id, entry.status
+                        );
+                    }
+                    None => {
+                        warn!("Failed container {} is not known to the catalog", container_id)
+                    }
+                }
             }
 
             wrong_msg => {
@@ -160,16 +282,98 @@ impl Runtime {
 
     fn handle_rpc(
         &mut self,
-        _endpoints: &mut Endpoints,
-        _client_id: ClientId,
+        endpoints: &mut Endpoints,
+        client: ClientRequest,
         message: RpcMsg,
     ) -> Result<(), DaemonError> {
         match message {
+            RpcMsg::ListPending => {
+                let pending = self.pending.values().cloned().collect();
+                self.send_rpc(endpoints, client, RpcMsg::PendingContainers(pending))?;
+            }
+
+            RpcMsg::AcceptContainer(container_id) => {
+                let pending = match self.pending.remove(&container_id) {
+                    Some(pending) => pending,
+                    None => {
+                        let err = DaemonError::UnknownContainer(container_id);
+                        self.send_rpc(endpoints, client, err)?;
+                        return Ok(());
+                    }
+                };
+                if let Err(violation) =
+                    self.policy.check_free_space(&pending.info.header, self.free_space())
+                {
+                    self.pending.insert(container_id, pending);
+                    self.send_rpc(endpoints, client, DaemonError::PolicyRejected(violation))?;
+                    return Ok(());
+                }
+                // Retrieving from any of the peers is enough
+                let remote_id = *pending
+                    .remote_ids
+                    .iter()
+                    .next()
+                    .expect("pending container always has announcing peer");
+                info!("Container {} is accepted by the user", container_id);
+                self.catalog_set_status(container_id, CatalogStatus::Downloading)?;
+                self.retrieve(endpoints, remote_id, pending.info)?;
+                self.send_rpc(
+                    endpoints,
+                    client,
+                    RpcMsg::Success(
+                        Some(format!("Retrieving container from {}", remote_id)).into(),
+                    ),
+                )?;
+            }
+
+            RpcMsg::DeclineContainer(container_id) => {
+                if self.pending.remove(&container_id).is_none() {
+                    let err = DaemonError::UnknownContainer(container_id);
+                    self.send_rpc(endpoints, client, err)?;
+                    return Ok(());
+                }
+                info!("Container {} is declined by the user", container_id);
+                self.catalog_set_status(container_id, CatalogStatus::Declined)?;
+                self.send_rpc(endpoints, client, RpcMsg::Success(None.into()))?;
+            }
+
+            RpcMsg::SeedContainer(container_id) => {
+                let header = match self.container_header(container_id)? {
+                    Some(header) => header,
+                    None => {
+                        let err = DaemonError::UnknownContainer(container_id);
+                        self.send_rpc(endpoints, client, err)?;
+                        return Ok(());
+                    }
+                };
+                let info = ContainerInfo {
+                    header,
+                    id: ContainerFullId {
+                        message_id: zero!(),
+                        container_id,
+                    },
+                };
+                let entry = self.catalog_complete(info)?;
+                let count = self.seed(endpoints, entry.info, &entry.remote_ids)?;
+                self.send_rpc(
+                    endpoints,
+                    client,
+                    RpcMsg::Success(Some(format!("Container announced to {} peers", count)).into()),
+                )?;
+            }
+
+            RpcMsg::ListCatalog(filter) => {
+                let entries = self.catalog_list(&filter)?;
+                self.send_rpc(endpoints, client, RpcMsg::Catalog(entries))?;
+            }
+
             wrong_msg => {
                 error!("Request is not supported by the RPC interface");
                 return Err(DaemonError::wrong_esb_msg(ServiceBus::Rpc, &wrong_msg));
             }
         }
+
+        Ok(())
     }
 
     fn handle_ctl(
@@ -185,4 +389,107 @@ impl Runtime {
             }
         }
     }
+
+    fn handle_announcement(
+        &mut self,
+        endpoints: &mut Endpoints,
+        remote_id: NodeId,
+        info: ContainerInfo,
+    ) -> Result<(), DaemonError> {
+        let container_id = info.id.container_id;
+        let entry = self.catalog_announce(remote_id, info)?;
+
+        if let Some(pending) = self.pending.get_mut(&container_id) {
+            debug!(
+                "Container {} is already pending; adding {} to its peers",
+                container_id, remote_id
+            );
+            pending.remote_ids.insert(remote_id);
+            return Ok(());
+        }
+        if entry.status != CatalogStatus::Pending {
+            debug!(
+                "Container {} announced by {} is already {}",
+                container_id, remote_id, entry.status
+            );
+            return Ok(());
+        }
+
+        match self.policy.check(remote_id, &entry.info.header, self.free_space()) {
+            Ok(()) => {
+                debug!(
+                    "Container {} announced by {} is accepted by policy",
+                    container_id, remote_id
+                );
+                self.catalog_set_status(container_id, CatalogStatus::Downloading)?;
+                self.retrieve(endpoints, remote_id, entry.info)
+            }
+            Err(violation) => {
+                info!(
+                    "Container {} announced by {} is put on hold: {}",
+                    container_id, remote_id, violation
+                );
+                self.pending.insert(container_id, PendingContainer {
+                    info: entry.info,
+                    remote_ids: entry.remote_ids,
+                    violation,
+                });
+                Ok(())
+            }
+        }
+    }
+
+    fn retrieve(
+        &mut self,
+        endpoints: &mut Endpoints,
+        remote_id: NodeId,
+        info: ContainerInfo,
+    ) -> Result<(), DaemonError> {
+        self.send_ext(
+            endpoints,
+            None,
+            ExtMsg::RetrieveContainer(AddressedMsg {
+                remote_id,
+                data: info.id,
+            }),
+        )?;
+        Ok(())
+    }
+
+    /// Announces container to the seed peers, except the ones which are already known to have
+    /// it. Returns number of peers to which the container was announced.
+    fn seed(
+        &mut self,
+        endpoints: &mut Endpoints,
+        info: ContainerInfo,
+        except: &BTreeSet<NodeId>,
+    ) -> Result<usize, DaemonError> {
+        let mut count = 0usize;
+        for remote_id in self.policy.seed_peers.clone() {
+            if except.contains(&remote_id) {
+                continue;
+            }
+            debug!("Announcing container {} to seed peer {}", info.id.container_id, remote_id);
+            self.send_ext(
+                endpoints,
+                None,
+                ExtMsg::ContainerAnnouncement(AddressedMsg {
+                    remote_id,
+                    data: info.clone(),
+                }),
+            )?;
+            count += 1;
+        }
+        Ok(count)
+    }
+
+    fn container_header(
+        &mut self,
+        container_id: ContainerId,
+    ) -> Result<Option<ContainerHeader>, DaemonError> {
+        gc::retrieve_live(&mut self.store, DB_TABLE_CONTAINER_HEADERS, container_id)?
+            .map(|chunk| ContainerHeader::strict_deserialize(chunk.as_slice()))
+            .transpose()
+            .map_err(DaemonError::from)
+    }
 }
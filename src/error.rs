@@ -8,11 +8,12 @@
 // You should have received a copy of the MIT License along with this software.
 // If not, see <https://opensource.org/licenses/MIT>.
 
+use internet2::addr::{NodeAddr, NodeId};
 use internet2::presentation;
 use microservices::rpc::ServerError;
 use microservices::{esb, rpc, LauncherError};
 use storm::ContainerId;
-use storm_rpc::{FailureCode, RpcMsg, ServiceId};
+use storm_rpc::{FailureCode, GcError, PolicyViolation, QuotaUsage, Role, RpcMsg, ServiceId};
 
 use crate::bus::ServiceBus;
 use crate::stormd::Daemon;
@@ -27,6 +28,14 @@ pub enum LaunchError {
     /// can't connect to store service. Details: {0}
     #[from]
     StoreConnection(ServerError<store_rpc::FailureCode>),
+
+    /// Node key can't be loaded
+    #[from]
+    #[display(inner)]
+    NodeKey(storm_rpc::EncryptionError),
+
+    /// RPC access tokens can't be loaded from {0}
+    RpcAuth(String),
 }
 
 impl microservices::error::Error for LaunchError {}
@@ -68,6 +77,38 @@ pub(crate) enum DaemonError {
 
     /// container {0} is not known
     UnknownContainer(ContainerId),
+
+    /// Error during container deletion or garbage collection
+    #[from]
+    #[display(inner)]
+    Gc(storm_rpc::GcError),
+
+    /// container of {size} bytes can't be stored since it exceeds storage quota: {quota}
+    QuotaExceeded { size: u64, quota: QuotaUsage },
+
+    /// peer {0} was not connected by the node
+    UnknownPeer(NodeId),
+
+    /// peer {0} is unreachable: {1}
+    PeerUnreachable(NodeAddr, String),
+
+    /// peer {0} is unreachable over the message bus: {1}
+    PeerDisconnected(NodeId, esb::Error<ServiceId>),
+
+    /// container download is rejected by downpour policy: {0}
+    PolicyRejected(PolicyViolation),
+
+    /// LNP node has failed to process peer request: {0}
+    Lnp(String),
+
+    /// LNP node proxy has terminated
+    LnpProxyTerminated,
+
+    /// request has no valid RPC access token
+    Unauthenticated,
+
+    /// client role `{0}` does not permit request `{1}`
+    PermissionDenied(Role, String),
 }
 
 impl microservices::error::Error for DaemonError {}
@@ -88,6 +129,20 @@ impl From<DaemonError> for RpcMsg {
             DaemonError::DaemonLaunch(_) => FailureCode::Launch,
             DaemonError::Store(_) => FailureCode::Store,
             DaemonError::UnknownContainer(_) => FailureCode::UnknownContainer,
+            DaemonError::Gc(GcError::UnknownContainer(_)) => FailureCode::UnknownContainer,
+            DaemonError::Gc(GcError::PinnedContainer(_)) => FailureCode::PinnedContainer,
+            DaemonError::Gc(GcError::TransfersInProgress(_)) => FailureCode::TransfersInProgress,
+            DaemonError::Gc(GcError::Store(_)) => FailureCode::Store,
+            DaemonError::Gc(GcError::Encoding(_)) => FailureCode::Encoding,
+            DaemonError::QuotaExceeded { .. } => FailureCode::QuotaExceeded,
+            DaemonError::UnknownPeer(_) => FailureCode::UnknownPeer,
+            DaemonError::PeerUnreachable(..) | DaemonError::PeerDisconnected(..) => {
+                FailureCode::PeerUnreachable
+            }
+            DaemonError::PolicyRejected(_) => FailureCode::PolicyRejected,
+            DaemonError::Lnp(_) | DaemonError::LnpProxyTerminated => FailureCode::Lnp,
+            DaemonError::Unauthenticated => FailureCode::Unauthenticated,
+            DaemonError::PermissionDenied(..) => FailureCode::PermissionDenied,
         };
         RpcMsg::Failure(rpc::Failure {
             code: code.into(),
@@ -17,6 +17,7 @@ extern crate log;
 #[macro_use]
 extern crate strict_encoding;
 
+mod auth;
 mod config;
 mod error;
 pub mod stormd;
@@ -27,6 +28,7 @@ pub mod bus;
 #[cfg(feature = "server")]
 pub mod opts;
 
+pub(crate) use auth::RpcAuth;
 pub use config::Config;
 pub(crate) use error::DaemonError;
 pub use error::LaunchError;
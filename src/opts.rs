@@ -157,6 +157,9 @@ pub struct Opts {
     ///
     /// Socket can be either TCP address in form of `<ipv4 | ipv6>:<port>` – or a path
     /// to an IPC file.
+    ///
+    /// Subscribers are not authenticated, so anybody able to connect to the socket receives all
+    /// chat messages; it must not be exposed to untrusted networks.
     #[clap(
         short = 'C',
         long,
@@ -8,15 +8,31 @@
 // You should have received a copy of the MIT License along with this software.
 // If not, see <https://opensource.org/licenses/MIT>.
 
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::PathBuf;
+
+use internet2::addr::{NodeId, ServiceAddr};
+use storm::StormApp;
+use storm_rpc::{QuotaScope, QuotaUsage, StorageUsage};
+
 #[cfg(feature = "server")]
-use super::Opts;
+use super::{Opts, QuotaOpts};
+use crate::downpourd;
 #[cfg(feature = "server")]
 use crate::opts::Options;
 
-#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug)]
+#[derive(Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug)]
 pub struct Config {
     pub run_chat: bool,
     pub run_downpour: bool,
+    /// Policy for the downpour daemon
+    pub downpour: downpourd::Config,
+    /// Storage quotas checked for the containers received by the transfer daemon
+    pub quotas: QuotaConfig,
+    /// ZMQ socket for LNP node RPC API, used to manage connections to remote peers
+    pub lnp_endpoint: ServiceAddr,
+    /// File with the node private key used to check encrypted containers
+    pub node_key: Option<PathBuf>,
     /// Indicates whether deamons should be spawned as threads (true) or as child processes (false)
     pub threaded: bool,
 }
@@ -31,11 +47,101 @@ impl Options for Opts {
         Config {
             run_chat: self.chat,
             run_downpour: self.downpour,
+            downpour: self.downpour_policy.clone().into(),
+            quotas: self.quotas.clone().into(),
+            lnp_endpoint: self.lnp_endpoint.clone(),
+            node_key: self.node_key.clone(),
             threaded: self.threaded_daemons,
         }
     }
 }
 
+/// Storage quotas for the containers received from remote peers
+#[derive(Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, Default)]
+pub struct QuotaConfig {
+    /// Maximal total size of the containers (in bytes) which can be stored per app
+    pub app_quotas: BTreeMap<StormApp, u64>,
+
+    /// Maximal total size of the containers (in bytes) which can be received from a single peer,
+    /// unless the peer has its own quota in `peer_quotas`. `None` does not limit peers.
+    pub peer_quota: Option<u64>,
+
+    /// Quotas of the individual peers, overriding `peer_quota`
+    pub peer_quotas: BTreeMap<NodeId, u64>,
+}
+
+impl QuotaConfig {
+    /// Checks whether none of the quotas is set.
+    pub fn is_unlimited(&self) -> bool {
+        self.app_quotas.is_empty() && self.peer_quota.is_none() && self.peer_quotas.is_empty()
+    }
+
+    /// Returns quota of the scope, if any.
+    pub fn limit(&self, scope: QuotaScope) -> Option<u64> {
+        match scope {
+            QuotaScope::App(app) => self.app_quotas.get(&app).copied(),
+            QuotaScope::Peer(remote_id) => {
+                self.peer_quotas.get(&remote_id).copied().or(self.peer_quota)
+            }
+        }
+    }
+
+    /// Checks whether a container of `size` bytes requested by `storm_app` from `remote_id` peer
+    /// fits the quotas, given the current storage usage. Returns usage of the exceeded quota.
+    pub fn check(
+        &self,
+        usage: &StorageUsage,
+        storm_app: StormApp,
+        remote_id: NodeId,
+        size: u64,
+    ) -> Result<(), QuotaUsage> {
+        for scope in [QuotaScope::App(storm_app), QuotaScope::Peer(remote_id)] {
+            let used = usage.used(scope);
+            if let Some(limit) = self.limit(scope) {
+                if used.saturating_add(size) > limit {
+                    return Err(QuotaUsage {
+                        scope,
+                        used,
+                        limit: Some(limit),
+                    });
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Reports usage for all apps and peers which either store some data or have a quota.
+    pub fn report(&self, usage: &StorageUsage) -> Vec<QuotaUsage> {
+        let apps = usage.apps.keys().chain(self.app_quotas.keys()).copied().map(QuotaScope::App);
+        let peers =
+            usage.peers.keys().chain(self.peer_quotas.keys()).copied().map(QuotaScope::Peer);
+        apps.chain(peers)
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .map(|scope| QuotaUsage {
+                scope,
+                used: usage.used(scope),
+                limit: self.limit(scope),
+            })
+            .collect()
+    }
+}
+
+#[cfg(feature = "server")]
+impl From<QuotaOpts> for QuotaConfig {
+    fn from(opts: QuotaOpts) -> Self {
+        QuotaConfig {
+            app_quotas: opts.app_quotas.into_iter().map(|quota| (quota.app, quota.limit)).collect(),
+            peer_quota: opts.peer_quota,
+            peer_quotas: opts
+                .peer_quotas
+                .into_iter()
+                .map(|quota| (quota.remote_id, quota.limit))
+                .collect(),
+        }
+    }
+}
+
 impl From<crate::Config<Config>> for crate::Config<()> {
     fn from(config: crate::Config<Config>) -> Self {
         crate::Config {
@@ -50,3 +156,98 @@ impl From<crate::Config<Config>> for crate::Config<()> {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use internet2::addr::LocalNode;
+    use secp256k1::SecretKey;
+
+    use super::*;
+
+    fn node_id(byte: u8) -> NodeId {
+        LocalNode::with(secp256k1::SECP256K1, SecretKey::from_slice(&[byte; 32]).unwrap()).node_id()
+    }
+
+    fn config() -> QuotaConfig {
+        QuotaConfig {
+            app_quotas: bmap! { StormApp::FileTransfer => 100 },
+            peer_quota: Some(50),
+            peer_quotas: bmap! { node_id(1) => 200 },
+        }
+    }
+
+    #[test]
+    fn limits() {
+        let config = config();
+        assert!(!config.is_unlimited());
+        assert!(QuotaConfig::default().is_unlimited());
+        assert_eq!(config.limit(QuotaScope::App(StormApp::FileTransfer)), Some(100));
+        assert_eq!(config.limit(QuotaScope::App(StormApp::Chat)), None);
+        assert_eq!(config.limit(QuotaScope::Peer(node_id(1))), Some(200));
+        assert_eq!(config.limit(QuotaScope::Peer(node_id(2))), Some(50));
+        assert_eq!(QuotaConfig::default().limit(QuotaScope::Peer(node_id(2))), None);
+    }
+
+    #[test]
+    fn check() {
+        let config = config();
+        let mut usage = StorageUsage::default();
+        usage.add(Some(StormApp::FileTransfer), Some(node_id(1)), 90);
+
+        assert_eq!(config.check(&usage, StormApp::FileTransfer, node_id(1), 10), Ok(()));
+        assert_eq!(
+            config.check(&usage, StormApp::FileTransfer, node_id(1), 11),
+            Err(QuotaUsage {
+                scope: QuotaScope::App(StormApp::FileTransfer),
+                used: 90,
+                limit: Some(100)
+            })
+        );
+        assert_eq!(config.check(&usage, StormApp::Chat, node_id(1), 110), Ok(()));
+        assert_eq!(
+            config.check(&usage, StormApp::Chat, node_id(1), 111),
+            Err(QuotaUsage {
+                scope: QuotaScope::Peer(node_id(1)),
+                used: 90,
+                limit: Some(200)
+            })
+        );
+        assert_eq!(
+            config.check(&usage, StormApp::Chat, node_id(2), 51),
+            Err(QuotaUsage {
+                scope: QuotaScope::Peer(node_id(2)),
+                used: 0,
+                limit: Some(50)
+            })
+        );
+        assert_eq!(
+            QuotaConfig::default().check(&usage, StormApp::Chat, node_id(2), u64::MAX),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn report() {
+        let config = config();
+        let mut usage = StorageUsage::default();
+        usage.add(Some(StormApp::Chat), Some(node_id(2)), 10);
+        usage.add(Some(StormApp::Chat), None, 5);
+
+        let report = config.report(&usage);
+        let expected = [
+            (QuotaScope::App(StormApp::Chat), 15, None),
+            (QuotaScope::App(StormApp::FileTransfer), 0, Some(100)),
+            (QuotaScope::Peer(node_id(1)), 0, Some(200)),
+            (QuotaScope::Peer(node_id(2)), 10, Some(50)),
+        ];
+        assert_eq!(report.len(), expected.len());
+        for (scope, used, limit) in expected {
+            assert!(
+                report.contains(&QuotaUsage { scope, used, limit }),
+                "{} is not reported",
+                scope
+            );
+        }
+        assert!(QuotaConfig::default().report(&StorageUsage::default()).is_empty());
+    }
+}
@@ -31,7 +31,7 @@ pub enum Daemon {
 
 impl Launcher for Daemon {
     type RunError = BootstrapError<LaunchError>;
-    type Config = crate::Config;
+    type Config = crate::Config<stormd::Config>;
 
     fn bin_name(&self) -> &'static str {
         match self {
@@ -42,22 +42,40 @@ impl Launcher for Daemon {
     }
 
     fn cmd_args(&self, cmd: &mut Command) -> Result<(), LauncherError<Self>> {
-        cmd.args(std::env::args().skip(1).filter(|arg| {
-            !["--threaded", "--chat", "--downpour"].iter().any(|pat| arg.starts_with(pat))
-        }));
+        cmd.args(std::env::args().skip(1).filter(|arg| self.accepts_arg(arg)));
 
         Ok(())
     }
 
-    fn run_impl(self, config: crate::Config) -> Result<(), Self::RunError> {
+    fn run_impl(self, config: crate::Config<stormd::Config>) -> Result<(), Self::RunError> {
         match self {
-            Daemon::Transferd => transferd::run(config),
-            Daemon::Chatd => chatd::run(config),
-            Daemon::Downpourd => downpourd::run(config),
+            Daemon::Transferd => transferd::run(config.into()),
+            Daemon::Chatd => chatd::run(config.into()),
+            Daemon::Downpourd => {
+                let policy = config.ext.downpour.clone();
+                downpourd::run(crate::Config::with(config, policy))
+            }
         }
     }
 }
 
+impl Daemon {
+    /// Detects whether stormd command-line argument must be passed to the daemon.
+    fn accepts_arg(&self, arg: &str) -> bool {
+        // Downpour policy options are used only by downpourd
+        if *self == Daemon::Downpourd && arg.starts_with("--downpour-") {
+            return true;
+        }
+        // Storage quotas are checked by stormd itself
+        if arg.starts_with("--quota-") {
+            return false;
+        }
+        !["--threaded", "--chat", "--downpour", "--lnp", "--node-key"]
+            .iter()
+            .any(|pat| arg.starts_with(pat))
+    }
+}
+
 impl Runtime {
     pub(super) fn launch_daemon(
         &self,
@@ -65,9 +83,46 @@ impl Runtime {
         config: crate::Config<stormd::Config>,
     ) -> Result<DaemonHandle<Daemon>, LauncherError<Daemon>> {
         if self.config.ext.threaded {
-            daemon.thread_daemon(config.into())
+            daemon.thread_daemon(config)
         } else {
             daemon.exec_daemon()
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use clap::Parser;
+
+    use super::*;
+
+    fn child_args(daemon: Daemon, args: &[&str]) -> Vec<String> {
+        args.iter().filter(|arg| daemon.accepts_arg(arg)).map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn node_key() {
+        let args = ["stormd", "--msg", "/tmp/msg.rpc", "--node-key=/tmp/node.key", "-v"];
+        assert!(stormd::Opts::try_parse_from(args).is_ok());
+        let split = ["stormd", "--msg", "/tmp/msg.rpc", "--node-key", "/tmp/node.key"];
+        assert!(stormd::Opts::try_parse_from(split).is_err());
+
+        let args = child_args(Daemon::Transferd, &args[1..]);
+        assert_eq!(args, vec![s!("--msg"), s!("/tmp/msg.rpc"), s!("-v")]);
+        let args = std::iter::once("transferd").chain(args.iter().map(String::as_str));
+        assert!(transferd::Opts::try_parse_from(args).is_ok());
+    }
+
+    #[test]
+    fn lnp_endpoint() {
+        let args = ["stormd", "--msg", "/tmp/msg.rpc", "--lnp=/tmp/lnp.rpc", "--chat"];
+        assert!(stormd::Opts::try_parse_from(args).is_ok());
+        let split = ["stormd", "--msg", "/tmp/msg.rpc", "--lnp", "/tmp/lnp.rpc"];
+        assert!(stormd::Opts::try_parse_from(split).is_err());
+
+        let args = child_args(Daemon::Chatd, &args[1..]);
+        assert_eq!(args, vec![s!("--msg"), s!("/tmp/msg.rpc")]);
+        let args = std::iter::once("chatd").chain(args.iter().map(String::as_str));
+        assert!(chatd::Opts::try_parse_from(args).is_ok());
+    }
+}
@@ -0,0 +1,261 @@
+// Storm node providing distributed storage & messaging for lightning network.
+//
+// Written in 2022 by
+//     Dr. Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2022 by LNP/BP Standards Association, Switzerland.
+//
+// You should have received a copy of the MIT License along with this software.
+// If not, see <https://opensource.org/licenses/MIT>.
+
+//! Proxy forwarding peer management requests from stormd to LNP node.
+//!
+//! LNP node may take a while to establish a connection, so the proxy runs in a separate thread
+//! and reports LNP node replies back to stormd over the CTL bus, such that stormd is not blocked
+//! while waiting for them.
+//!
+//! LNP node client blocks until the node replies, so it runs in yet another worker thread, which
+//! allows the proxy to fail requests not replied within [`LNP_TIMEOUTS`]. After a timeout the
+//! worker is abandoned and a new one is connected to LNP node on the next request.
+
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use internet2::addr::{NodeAddr, ServiceAddr};
+use internet2::ZmqSocketType;
+use lnp::addr::LnpAddr;
+use lnp2p::Protocol;
+use microservices::esb;
+use storm_rpc::{ClientRequest, ServiceId, Timeouts};
+
+use crate::bus::{BusMsg, CtlMsg, Endpoints, LnpReply, PeerReply, PeerRequest, ServiceBus};
+use crate::{DaemonError, LaunchError};
+
+/// Timeouts for LNP node replies. Connecting to a peer takes a while, so requests are not failed
+/// as long as LNP node keeps reporting progress within the idle timeout.
+const LNP_TIMEOUTS: Timeouts = Timeouts {
+    request: Some(Duration::from_secs(120)),
+    idle: Some(Duration::from_secs(30)),
+};
+
+pub(super) struct LnpProxy {
+    requests: mpsc::Sender<(ClientRequest, PeerRequest)>,
+}
+
+impl LnpProxy {
+    pub fn spawn(
+        lnp_endpoint: ServiceAddr,
+        ctl_endpoint: ServiceAddr,
+    ) -> Result<LnpProxy, LaunchError> {
+        let (requests, queue) = mpsc::channel::<(ClientRequest, PeerRequest)>();
+        thread::Builder::new()
+            .name(s!("lnp-proxy"))
+            .spawn(move || {
+                let mut esb = match esb::Controller::<_, BusMsg, _>::with(
+                    map! {
+                        ServiceBus::Ctl => esb::BusConfig::with_addr(
+                            ctl_endpoint,
+                            ZmqSocketType::RouterConnect,
+                            Some(ServiceId::stormd())
+                        )
+                    },
+                    Handler,
+                ) {
+                    Ok(esb) => esb,
+                    Err(err) => {
+                        error!("LNP proxy is unable to connect CTL bus: {}", err);
+                        return;
+                    }
+                };
+                let mut worker = None;
+                for (client, request) in queue {
+                    let reply = exec(&mut worker, &lnp_endpoint, request);
+                    let msg = CtlMsg::LnpReply(LnpReply {
+                        client,
+                        request,
+                        reply,
+                    });
+                    if let Err(err) = esb.send_to(ServiceBus::Ctl, ServiceId::stormd(), msg.into())
+                    {
+                        error!("LNP proxy is unable to report to stormd: {}", err);
+                    }
+                }
+            })
+            .map_err(|_| LaunchError::BusSetupFailure)?;
+        Ok(LnpProxy { requests })
+    }
+
+    pub fn request(&self, client: ClientRequest, request: PeerRequest) -> Result<(), DaemonError> {
+        self.requests.send((client, request)).map_err(|_| DaemonError::LnpProxyTerminated)
+    }
+}
+
+/// Thread running blocking LNP node client
+struct Worker {
+    requests: mpsc::Sender<(lnp_rpc::ServiceId, lnp_rpc::RpcMsg)>,
+    replies: mpsc::Receiver<Result<lnp_rpc::RpcMsg, String>>,
+}
+
+impl Worker {
+    /// Spawns the worker, which connects to LNP node on the first request.
+    fn spawn(lnp_endpoint: ServiceAddr) -> Result<Worker, String> {
+        let (requests, queue) = mpsc::channel::<(lnp_rpc::ServiceId, lnp_rpc::RpcMsg)>();
+        let (replies_tx, replies) = mpsc::channel();
+        thread::Builder::new()
+            .name(s!("lnp-client"))
+            .spawn(move || {
+                let mut lnp = None;
+                for (service_id, msg) in queue {
+                    let client = match lnp {
+                        Some(ref mut client) => client,
+                        None => match lnp_rpc::Client::with(lnp_endpoint.clone()) {
+                            Ok(client) => lnp.insert(client),
+                            Err(err) => {
+                                let _ = replies_tx.send(Err(err.to_string()));
+                                continue;
+                            }
+                        },
+                    };
+                    if let Err(err) = client.request(service_id, msg) {
+                        let _ = replies_tx.send(Err(err.to_string()));
+                        continue;
+                    }
+                    loop {
+                        let reply = client.response().map_err(|err| err.to_string());
+                        let progress = matches!(reply, Ok(lnp_rpc::RpcMsg::Progress(_)));
+                        // Proxy has abandoned the worker after a timeout
+                        if replies_tx.send(reply).is_err() {
+                            return;
+                        }
+                        if !progress {
+                            break;
+                        }
+                    }
+                }
+            })
+            .map_err(|err| err.to_string())?;
+        Ok(Worker { requests, replies })
+    }
+}
+
+/// Sends request to LNP node and waits for its final reply, logging progress reports.
+fn call(
+    worker: &mut Option<Worker>,
+    lnp_endpoint: &ServiceAddr,
+    service_id: lnp_rpc::ServiceId,
+    msg: lnp_rpc::RpcMsg,
+) -> Result<lnp_rpc::RpcMsg, String> {
+    let current = match worker {
+        Some(current) => current,
+        None => worker.insert(Worker::spawn(lnp_endpoint.clone())?),
+    };
+    if current.requests.send((service_id, msg)).is_err() {
+        *worker = None;
+        return Err(s!("LNP node client has terminated"));
+    }
+    let started = Instant::now();
+    let request_deadline = LNP_TIMEOUTS.request.map(|timeout| started + timeout);
+    loop {
+        let idle_deadline = LNP_TIMEOUTS.idle.map(|timeout| Instant::now() + timeout);
+        let deadline = match (request_deadline, idle_deadline) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        };
+        let reply = match deadline {
+            None => current.replies.recv().map_err(|_| RecvTimeoutError::Disconnected),
+            Some(deadline) => {
+                current.replies.recv_timeout(deadline.saturating_duration_since(Instant::now()))
+            }
+        };
+        match reply {
+            Ok(Ok(lnp_rpc::RpcMsg::Progress(info))) => debug!("LNP node reports: {}", info),
+            Ok(reply) => return reply,
+            Err(RecvTimeoutError::Timeout) => {
+                *worker = None;
+                return Err(format!("LNP node has not replied in {:?}", started.elapsed()));
+            }
+            Err(RecvTimeoutError::Disconnected) => {
+                *worker = None;
+                return Err(s!("LNP node client has terminated"));
+            }
+        }
+    }
+}
+
+/// Executes request to LNP node, connecting to it on the first request.
+fn exec(
+    worker: &mut Option<Worker>,
+    lnp_endpoint: &ServiceAddr,
+    request: PeerRequest,
+) -> PeerReply {
+    let msg = match request {
+        PeerRequest::Connect(node_addr) => lnp_rpc::RpcMsg::ConnectPeer(LnpAddr {
+            protocol: Protocol::Bifrost,
+            node_addr,
+        }),
+        PeerRequest::Disconnect(remote_id) => {
+            // LNP node requires full peer address, which is known to the peer daemon only, since
+            // the peer might have connected to us
+            let peer_service = lnp_rpc::ServiceId::PeerBifrost(remote_id);
+            let addr = match call(worker, lnp_endpoint, peer_service, lnp_rpc::RpcMsg::GetInfo) {
+                Ok(lnp_rpc::RpcMsg::PeerInfo(info)) => match info.remote_socket.first() {
+                    Some(addr) => *addr,
+                    None => {
+                        return PeerReply::Rejected(format!("address of {} is unknown", remote_id))
+                    }
+                },
+                Ok(lnp_rpc::RpcMsg::Failure(failure)) => return PeerReply::Rejected(failure.info),
+                Ok(other) => {
+                    return PeerReply::Failure(format!("unexpected LNP node reply {}", other))
+                }
+                Err(err) => return PeerReply::Failure(err),
+            };
+            lnp_rpc::RpcMsg::DisconnectPeer(LnpAddr {
+                protocol: Protocol::Bifrost,
+                node_addr: NodeAddr {
+                    id: remote_id,
+                    addr,
+                },
+            })
+        }
+        PeerRequest::ListPeers => lnp_rpc::RpcMsg::ListPeers,
+    };
+    match call(worker, lnp_endpoint, lnp_rpc::ServiceId::LnpBroker, msg) {
+        Ok(lnp_rpc::RpcMsg::Success(_)) => PeerReply::Success,
+        Ok(lnp_rpc::RpcMsg::PeerList(peers)) => {
+            PeerReply::Peers(peers.bifrost.into_iter().collect())
+        }
+        Ok(lnp_rpc::RpcMsg::Failure(failure)) => PeerReply::Rejected(failure.info),
+        Ok(other) => PeerReply::Failure(format!("unexpected LNP node reply {}", other)),
+        Err(err) => PeerReply::Failure(err),
+    }
+}
+
+struct Handler;
+
+// Proxy only sends replies to stormd and does not receive anything
+impl esb::Handler<ServiceBus> for Handler {
+    type Request = BusMsg;
+    type Error = DaemonError;
+
+    fn identity(&self) -> ServiceId { ServiceId::Lnp }
+
+    fn handle(
+        &mut self,
+        _: &mut Endpoints,
+        _: ServiceBus,
+        _: ServiceId,
+        _: BusMsg,
+    ) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    fn handle_err(
+        &mut self,
+        _: &mut Endpoints,
+        err: esb::Error<ServiceId>,
+    ) -> Result<(), Self::Error> {
+        Err(err.into())
+    }
+}
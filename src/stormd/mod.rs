@@ -9,13 +9,14 @@
 // If not, see <https://opensource.org/licenses/MIT>.
 
 mod service;
+mod lnp;
 pub(self) mod daemons;
 #[cfg(feature = "server")]
 mod opts;
 mod config;
 
-pub use config::Config;
+pub use config::{Config, QuotaConfig};
 pub use daemons::Daemon;
 #[cfg(feature = "server")]
-pub use opts::Opts;
+pub use opts::{AppQuota, Opts, PeerQuota, QuotaOpts};
 pub use service::{run, Runtime};
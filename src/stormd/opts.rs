@@ -8,7 +8,13 @@
 // You should have received a copy of the MIT License along with this software.
 // If not, see <https://opensource.org/licenses/MIT>.
 
-use clap::Parser;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use clap::{Args, Parser, ValueHint};
+use internet2::addr::{NodeId, ServiceAddr};
+use lnp_rpc::LNP_NODE_RPC_ENDPOINT;
+use storm::StormApp;
 
 /// Lightning storm daemon; part of Storm Node.
 ///
@@ -29,12 +35,177 @@ pub struct Opts {
     #[clap(long)]
     pub downpour: bool,
 
+    #[clap(flatten)]
+    pub downpour_policy: crate::downpourd::PolicyOpts,
+
+    #[clap(flatten)]
+    pub quotas: QuotaOpts,
+
+    /// ZMQ socket for connecting LNP node RPC interface, used to manage connections to remote
+    /// peers.
+    ///
+    /// Socket can be either TCP address in form of `<ipv4 | ipv6>:<port>` – or a path
+    /// to an IPC file.
+    ///
+    /// Requires `=` sign between the option name and its value, since the option is used by
+    /// stormd only and is filtered out from the arguments of the other daemons.
+    #[clap(
+        long = "lnp",
+        require_equals = true,
+        env = "LNP_NODE_RPC_ENDPOINT",
+        default_value = LNP_NODE_RPC_ENDPOINT,
+        value_hint = ValueHint::FilePath
+    )]
+    pub lnp_endpoint: ServiceAddr,
+
+    /// File with the node private key, used by the integrity check to decrypt data of the
+    /// containers encrypted to the node. Without the key only presence and hashes of their chunks
+    /// are checked.
+    ///
+    /// Requires `=` sign between the option name and its value, since the option is used by
+    /// stormd only and is filtered out from the arguments of the other daemons.
+    #[clap(long, require_equals = true, value_name = "FILE", value_hint = ValueHint::FilePath)]
+    pub node_key: Option<PathBuf>,
+
     /// Spawn daemons as threads and not processes
     #[clap(short = 'T', long = "threaded")]
     pub threaded_daemons: bool,
 }
 
+/// Storage quotas for the containers received from remote peers.
+///
+/// All options require `=` sign between the option name and its value, so stormd, which checks
+/// the quotas, can filter them out from the arguments of the other daemons.
+#[derive(Args)]
+#[derive(Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug)]
+pub struct QuotaOpts {
+    /// Quota on the total size of containers requested by an app, in form of `APP:BYTES`.
+    ///
+    /// Can be used multiple times. Apps without a quota are not limited.
+    #[clap(long = "quota-app", require_equals = true, value_name = "APP:BYTES")]
+    pub app_quotas: Vec<AppQuota>,
+
+    /// Quota on the total size of containers received from a single peer.
+    ///
+    /// If not provided, peers are not limited, unless they have own quota.
+    #[clap(long = "quota-peer-default", require_equals = true, value_name = "BYTES")]
+    pub peer_quota: Option<u64>,
+
+    /// Quota on the total size of containers received from a specific peer, in form of
+    /// `NODE_ID:BYTES`.
+    ///
+    /// Can be used multiple times.
+    #[clap(long = "quota-peer", require_equals = true, value_name = "NODE_ID:BYTES")]
+    pub peer_quotas: Vec<PeerQuota>,
+}
+
+/// Quota on the total size of containers requested by an app
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug)]
+pub struct AppQuota {
+    pub app: StormApp,
+    pub limit: u64,
+}
+
+impl FromStr for AppQuota {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (app, limit) = s
+            .rsplit_once(':')
+            .ok_or_else(|| format!("app quota `{}` must have form of `APP:BYTES`", s))?;
+        let app = [
+            StormApp::System,
+            StormApp::Chat,
+            StormApp::FileTransfer,
+            StormApp::Storage,
+            StormApp::Search,
+            StormApp::RgbContracts,
+            StormApp::RgbTransfers,
+        ]
+        .into_iter()
+        .find(|known| known.to_string() == app)
+        .or_else(|| app.parse::<u16>().ok().map(StormApp::from))
+        .ok_or_else(|| format!("unknown storm app `{}`", app))?;
+        let limit = limit.parse().map_err(|_| format!("invalid quota size `{}`", limit))?;
+        Ok(AppQuota { app, limit })
+    }
+}
+
+/// Quota on the total size of containers received from a peer
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug)]
+pub struct PeerQuota {
+    pub remote_id: NodeId,
+    pub limit: u64,
+}
+
+impl FromStr for PeerQuota {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (remote_id, limit) = s
+            .rsplit_once(':')
+            .ok_or_else(|| format!("peer quota `{}` must have form of `NODE_ID:BYTES`", s))?;
+        let remote_id =
+            remote_id.parse().map_err(|_| format!("invalid peer node id `{}`", remote_id))?;
+        let limit = limit.parse().map_err(|_| format!("invalid quota size `{}`", limit))?;
+        Ok(PeerQuota { remote_id, limit })
+    }
+}
+
 #[cfg(feature = "server")]
 impl Opts {
     pub fn process(&mut self) { self.shared.process() }
 }
+
+#[cfg(test)]
+mod test {
+    use internet2::addr::LocalNode;
+    use secp256k1::SecretKey;
+
+    use super::*;
+
+    #[test]
+    fn app_quota() {
+        assert_eq!(
+            "file-transfer:1000".parse(),
+            Ok(AppQuota {
+                app: StormApp::FileTransfer,
+                limit: 1000
+            })
+        );
+        assert_eq!(
+            "rgb-contracts:0".parse(),
+            Ok(AppQuota {
+                app: StormApp::RgbContracts,
+                limit: 0
+            })
+        );
+        assert_eq!(
+            "4000:1".parse(),
+            Ok(AppQuota {
+                app: StormApp::from(4000u16),
+                limit: 1
+            })
+        );
+        assert!("file-transfer".parse::<AppQuota>().is_err());
+        assert!("unknown:1000".parse::<AppQuota>().is_err());
+        assert!("chat:-1".parse::<AppQuota>().is_err());
+        assert!("chat:1kb".parse::<AppQuota>().is_err());
+    }
+
+    #[test]
+    fn peer_quota() {
+        let node = LocalNode::with(secp256k1::SECP256K1, SecretKey::from_slice(&[1; 32]).unwrap());
+        let remote_id = node.node_id();
+        assert_eq!(
+            format!("{}:1000", remote_id).parse(),
+            Ok(PeerQuota {
+                remote_id,
+                limit: 1000
+            })
+        );
+        assert!(remote_id.to_string().parse::<PeerQuota>().is_err());
+        assert!(format!("{}:", remote_id).parse::<PeerQuota>().is_err());
+        assert!("02aa:1000".parse::<PeerQuota>().is_err());
+    }
+}
@@ -8,30 +8,32 @@
 // You should have received a copy of the MIT License along with this software.
 // If not, see <https://opensource.org/licenses/MIT>.
 
-use std::collections::{BTreeSet, HashMap, HashSet, VecDeque};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque};
 use std::ops::Deref;
 
-use internet2::addr::NodeId;
+use internet2::addr::{LocalNode, NodeId};
 use internet2::{Unmarshall, ZmqSocketType};
 use lnp2p::bifrost;
 use lnp2p::bifrost::{BifrostApp, Messages as LnMsg};
 use microservices::cli::LogStyle;
 use microservices::error::BootstrapError;
-use microservices::esb::{self, ClientId, EndpointList, Error};
+use microservices::esb::{self, EndpointList, Error};
 use microservices::node::TryService;
 use storm::p2p::{AppMsg, ChunkPull, ChunkPush, Messages, STORM_P2P_UNMARSHALLER};
-use storm::{ContainerId, StormApp};
+use storm::{Container, ContainerFullId, ContainerId, StormApp};
 use storm_ext::{ExtMsg, StormExtMsg};
 use storm_rpc::{
-    AddressedMsg, AppContainer, RpcMsg, ServiceId, DB_TABLE_CHUNKS, DB_TABLE_CONTAINERS,
-    DB_TABLE_CONTAINER_HEADERS,
+    fsck, gc, inventory, AddressedMsg, AppContainer, ClientRequest, DamagedContainer, GcError,
+    PeerInfo, ProgressEvent, RpcMsg, ServiceId, StorageUsage, StormSupport, DB_TABLE_CONTAINERS,
 };
 
+use super::lnp::LnpProxy;
 use crate::bus::{
-    AddressedClientMsg, BusMsg, ChunkSend, CtlMsg, DaemonId, Endpoints, Responder, ServiceBus,
+    AddressedClientMsg, BusMsg, ChunkSend, CtlMsg, DaemonId, Endpoints, LnpReply, PeerReply,
+    PeerRequest, QuotaRejection, Responder, ServiceBus,
 };
 use crate::stormd::Daemon;
-use crate::{Config, DaemonError, LaunchError};
+use crate::{Config, DaemonError, LaunchError, RpcAuth};
 
 pub fn run(config: Config<super::Config>) -> Result<(), BootstrapError<LaunchError>> {
     let msg_endpoint = config.msg_endpoint.clone();
@@ -87,7 +89,24 @@ pub struct Runtime {
     /// Tracks known apps which must be notified on complete container downloads
     pub(crate) container_apps: HashMap<ContainerId, StormApp>,
     pub(crate) container_transfers: HashMap<ContainerId, DaemonId>,
+    /// Apps and remote peers of the containers being retrieved, to which their size is accounted
+    pub(crate) container_requests: HashMap<ContainerId, (StormApp, NodeId)>,
+    /// Storage used by the received containers. It is read from the store once and then updated
+    /// with each accepted container, so that the quotas are checked one container at a time;
+    /// `None` if it must be read again.
+    pub(crate) usage: Option<StorageUsage>,
     pub(crate) ctl_queue: VecDeque<CtlMsg>,
+
+    /// Forwards peer management requests to LNP node
+    lnp_proxy: LnpProxy,
+    /// Remote Bifrost peers known to be connected
+    pub(crate) peers: BTreeMap<NodeId, PeerInfo>,
+
+    /// Access tokens of RPC clients
+    rpc_auth: RpcAuth,
+
+    /// Node key used to check encrypted containers
+    node: Option<LocalNode>,
 }
 
 impl Runtime {
@@ -97,10 +116,23 @@ impl Runtime {
         let mut store =
             store_rpc::Client::with(&config.store_endpoint).map_err(LaunchError::from)?;
 
-        for table in [DB_TABLE_CONTAINER_HEADERS, DB_TABLE_CONTAINERS, DB_TABLE_CHUNKS] {
+        for table in gc::GC_TABLES {
             store.use_table(table.to_owned()).map_err(LaunchError::from)?;
         }
 
+        let lnp_proxy =
+            LnpProxy::spawn(config.ext.lnp_endpoint.clone(), config.ctl_endpoint.clone())?;
+
+        let rpc_auth = RpcAuth::load(&config.data_dir)?;
+
+        let node = config
+            .ext
+            .node_key
+            .as_ref()
+            .map(storm_rpc::read_node_key)
+            .transpose()
+            .map_err(LaunchError::from)?;
+
         info!("Stormd runtime started successfully");
 
         Ok(Self {
@@ -111,7 +143,13 @@ impl Runtime {
             transferd_busy: empty!(),
             container_apps: empty!(),
             container_transfers: empty!(),
+            container_requests: empty!(),
+            usage: None,
             ctl_queue: empty!(),
+            lnp_proxy,
+            peers: empty!(),
+            rpc_auth,
+            node,
         })
     }
 }
@@ -151,8 +189,22 @@ impl esb::Handler<ServiceBus> for Runtime {
             (ServiceBus::Storm, BusMsg::Storm(msg), ServiceId::StormApp(app_id)) => {
                 self.handle_app(endpoints, app_id, msg)
             }
-            (ServiceBus::Rpc, BusMsg::Rpc(msg), ServiceId::Client(client_id)) => {
-                self.handle_rpc(endpoints, client_id, msg)
+            (ServiceBus::Rpc, BusMsg::Rpc(envelope), ServiceId::Client(client_id)) => {
+                let client = ClientRequest {
+                    client_id,
+                    request_id: envelope.request_id,
+                };
+                match self.rpc_auth.check(&envelope) {
+                    Ok(role) => {
+                        trace!("Client {} is authenticated as {}", client_id, role);
+                        self.handle_rpc(endpoints, client, envelope.msg)
+                    }
+                    Err(err) => {
+                        warn!("Rejecting request from client {}: {}", client, err);
+                        self.send_rpc(endpoints, client, err)?;
+                        Ok(())
+                    }
+                }
             }
             (ServiceBus::Rpc, BusMsg::Storm(msg), other_source) => {
                 self.handle_others(endpoints, other_source, msg)
@@ -232,8 +284,12 @@ impl Runtime {
                     _ => unreachable!(),
                 };
 
-                if let Some(daemon_id) = self.container_transfers.get(&container_id) {
-                    self.send_ctl(endpoints, ServiceId::Transfer(*daemon_id), instr)?;
+                if let Some(daemon_id) = self.container_transfers.get(&container_id).copied() {
+                    let instr = match instr {
+                        CtlMsg::ProcessContainer(container) => self.check_quotas(container)?,
+                        instr => instr,
+                    };
+                    self.send_ctl(endpoints, ServiceId::Transfer(daemon_id), instr)?;
                 } else if matches!(instr, CtlMsg::ProcessContainer(_)) {
                     self.ctl_queue.push_back(instr);
                     self.pick_or_start(endpoints, None)?;
@@ -259,8 +315,18 @@ impl Runtime {
                     )?;
                 }
 
-                // A remote peer described list of apps. We need to report that to a client.
-                Err(Messages::ActiveApps(_)) => {}
+                // A remote peer described list of apps, confirming that it supports Storm
+                Err(Messages::ActiveApps(apps)) => {
+                    debug!("Peer {} runs Storm apps {:?}", remote_id, apps);
+                    self.peers
+                        .entry(remote_id)
+                        .or_insert(PeerInfo {
+                            remote_id,
+                            addr: None,
+                            storm: StormSupport::Unconfirmed,
+                        })
+                        .storm = StormSupport::Apps(apps);
+                }
 
                 _ => {}
             }
@@ -274,26 +340,119 @@ impl Runtime {
     fn handle_rpc(
         &mut self,
         endpoints: &mut Endpoints,
-        client_id: ClientId,
+        client: ClientRequest,
         message: RpcMsg,
     ) -> Result<(), DaemonError> {
         match message {
             RpcMsg::SendContainer(container) => {
                 self.ctl_queue.push_back(CtlMsg::AnnounceContainer(AddressedClientMsg {
                     remote_id: container.remote_id,
-                    client_id: Some(client_id),
+                    client: Some(client),
                     data: container.data,
                 }));
-                self.pick_or_start(endpoints, Some(client_id))
+                self.pick_or_start(endpoints, Some(client))
             }
 
             RpcMsg::GetContainer(container) => {
                 self.ctl_queue.push_back(CtlMsg::GetContainer(AddressedClientMsg {
                     remote_id: container.remote_id,
-                    client_id: Some(client_id),
+                    client: Some(client),
                     data: container.data,
                 }));
-                self.pick_or_start(endpoints, Some(client_id))
+                self.pick_or_start(endpoints, Some(client))
+            }
+
+            RpcMsg::DeleteContainer(container_id) => {
+                let reply = match gc::delete_container(&mut self.store, container_id) {
+                    Ok(()) => {
+                        self.usage = None;
+                        RpcMsg::Success(
+                            Some(format!("Container {} is deleted", container_id)).into(),
+                        )
+                    }
+                    Err(err) => DaemonError::from(err).into(),
+                };
+                self.send_rpc(endpoints, client, reply)?;
+                Ok(())
+            }
+
+            RpcMsg::PinContainer(container_id) | RpcMsg::UnpinContainer(container_id) => {
+                let pin = matches!(message, RpcMsg::PinContainer(_));
+                let reply = match gc::set_pinned(&mut self.store, container_id, pin) {
+                    Ok(()) => RpcMsg::Success(None.into()),
+                    Err(err) => DaemonError::from(err).into(),
+                };
+                self.send_rpc(endpoints, client, reply)?;
+                Ok(())
+            }
+
+            RpcMsg::CollectGarbage(dry_run) => {
+                // Chunks of containers being received are not yet referenced
+                let transfers = self.container_transfers.len();
+                let reply = if !dry_run && transfers > 0 {
+                    DaemonError::from(GcError::TransfersInProgress(transfers)).into()
+                } else {
+                    match gc::collect_garbage(&mut self.store, dry_run, |msg| debug!("{}", msg)) {
+                        Ok(report) => RpcMsg::GcReport(report),
+                        Err(err) => DaemonError::from(err).into(),
+                    }
+                };
+                self.send_rpc(endpoints, client, reply)?;
+                Ok(())
+            }
+
+            RpcMsg::Fsck(repair_from) => {
+                let mut report = match fsck::fsck(&mut self.store, self.node.as_ref(), |msg| {
+                    debug!("{}", msg)
+                }) {
+                    Ok(report) => report,
+                    Err(err) => {
+                        self.send_rpc(endpoints, client, DaemonError::from(err))?;
+                        return Ok(());
+                    }
+                };
+                if let Some(remote_id) = repair_from {
+                    report.refetching = self.refetch(endpoints, remote_id, &report.damaged)?;
+                }
+                self.send_rpc(endpoints, client, RpcMsg::FsckReport(report))?;
+                Ok(())
+            }
+
+            RpcMsg::ListContainers(filter) => {
+                let reply = match inventory::list_containers(&mut self.store, &filter) {
+                    Ok(containers) => RpcMsg::Containers(containers),
+                    Err(err) => DaemonError::from(err).into(),
+                };
+                self.send_rpc(endpoints, client, reply)?;
+                Ok(())
+            }
+
+            RpcMsg::ContainerInfo(container_id) => {
+                let reply = match inventory::container_summary(&mut self.store, container_id) {
+                    Ok(summary) => RpcMsg::ContainerSummary(summary),
+                    Err(err) => DaemonError::from(err).into(),
+                };
+                self.send_rpc(endpoints, client, reply)?;
+                Ok(())
+            }
+
+            RpcMsg::Connect(node_addr) => {
+                self.lnp_proxy.request(client, PeerRequest::Connect(node_addr))
+            }
+
+            RpcMsg::Disconnect(remote_id) => {
+                self.lnp_proxy.request(client, PeerRequest::Disconnect(remote_id))
+            }
+
+            RpcMsg::ListPeers => self.lnp_proxy.request(client, PeerRequest::ListPeers),
+
+            RpcMsg::ListQuotas => {
+                let reply = match self.storage_usage().cloned() {
+                    Ok(usage) => RpcMsg::Quotas(self.config.ext.quotas.report(&usage)),
+                    Err(err) => DaemonError::from(err).into(),
+                };
+                self.send_rpc(endpoints, client, reply)?;
+                Ok(())
             }
 
             wrong_msg => {
@@ -318,6 +477,14 @@ impl Runtime {
                 // TODO: Register other daemons
             }
 
+            CtlMsg::LnpReply(LnpReply {
+                client,
+                request,
+                reply,
+            }) if source == ServiceId::Lnp => {
+                self.handle_lnp_reply(endpoints, *client, *request, reply.clone())?;
+            }
+
             CtlMsg::ProcessingFailed | CtlMsg::ProcessingComplete => {
                 if let ServiceId::Transfer(daemon_id) = source {
                     if let Some(container_id) = self
@@ -328,13 +495,20 @@ impl Runtime {
                         .copied()
                     {
                         self.container_transfers.remove(&container_id);
-                        if let Some(app) = self.container_apps.get(&container_id) {
-                            // Notify client on complete process
-                            let _ = self.send_ext(
-                                endpoints,
-                                Some(*app),
-                                ExtMsg::ContainerRetrieved(container_id),
-                            );
+                        self.container_requests.remove(&container_id);
+                        // Failed transfer may have not stored the accounted container
+                        if matches!(message, CtlMsg::ProcessingFailed) {
+                            self.usage = None;
+                        }
+                        // Notify the app which has requested the container retrieval
+                        if let Some(app) = self.container_apps.remove(&container_id) {
+                            let report = match message {
+                                CtlMsg::ProcessingComplete => {
+                                    ExtMsg::ContainerRetrieved(container_id)
+                                }
+                                _ => ExtMsg::ContainerRetrievalFailed(container_id),
+                            };
+                            let _ = self.send_ext(endpoints, Some(app), report);
                         }
                     }
                     self.transferd_busy.remove(&daemon_id);
@@ -382,7 +556,7 @@ impl Runtime {
                 self.container_apps.insert(container.data.container_id, app);
                 self.ctl_queue.push_back(CtlMsg::GetContainer(AddressedClientMsg {
                     remote_id: container.remote_id,
-                    client_id: None,
+                    client: None,
                     data: AppContainer {
                         storm_app: app,
                         container_id: container.data,
@@ -394,7 +568,7 @@ impl Runtime {
             ExtMsg::SendContainer(container) => {
                 self.ctl_queue.push_back(CtlMsg::SendContainer(AddressedClientMsg {
                     remote_id: container.remote_id,
-                    client_id: None,
+                    client: None,
                     data: AppContainer {
                         storm_app: app,
                         container_id: container.data,
@@ -423,7 +597,7 @@ impl Runtime {
                 info!("Receive a container announcement from {}", source);
                 self.ctl_queue.push_back(CtlMsg::AnnounceContainer(AddressedClientMsg {
                     remote_id: container.remote_id,
-                    client_id: None,
+                    client: None,
                     data: AppContainer {
                         storm_app: StormApp::FileTransfer,
                         container_id: container.data.id,
@@ -485,10 +659,18 @@ impl Runtime {
 
         let container_id = match msg {
             CtlMsg::GetContainer(AddressedClientMsg {
-                data: AppContainer { container_id, .. },
+                remote_id,
+                data:
+                    AppContainer {
+                        storm_app,
+                        container_id,
+                    },
                 ..
-            })
-            | CtlMsg::SendContainer(AddressedClientMsg {
+            }) => {
+                self.container_requests.insert(container_id.container_id, (storm_app, remote_id));
+                Some(container_id.container_id)
+            }
+            CtlMsg::SendContainer(AddressedClientMsg {
                 data: AppContainer { container_id, .. },
                 ..
             }) => Some(container_id.container_id),
@@ -505,33 +687,186 @@ impl Runtime {
         Ok(true)
     }
 
+    /// Returns storage used by the received containers, reading it from the store if needed.
+    fn storage_usage(&mut self) -> Result<&mut StorageUsage, GcError> {
+        if self.usage.is_none() {
+            self.usage = Some(inventory::storage_usage(&mut self.store, None)?);
+        }
+        Ok(self.usage.as_mut().expect("storage usage is just read"))
+    }
+
+    /// Checks whether a container received from a remote peer fits the storage quotas and
+    /// accounts its size. Returns instruction for the transfer daemon, which either processes
+    /// the container or rejects it.
+    ///
+    /// Checks are done by stormd and not by the transfer daemons, so concurrent transfers can't
+    /// exceed the quotas together.
+    fn check_quotas(&mut self, container: Container) -> Result<CtlMsg, DaemonError> {
+        let container_id = container.container_id();
+        let (storm_app, remote_id) = match self.container_requests.get(&container_id) {
+            Some(request) if !self.config.ext.quotas.is_unlimited() => *request,
+            _ => return Ok(CtlMsg::ProcessContainer(container)),
+        };
+        let size = container.header.size;
+
+        // Re-fetched containers are already stored and accounted, possibly with other origin
+        let refetch =
+            gc::retrieve_live(&mut self.store, DB_TABLE_CONTAINERS, container_id)?.is_some();
+        let checked = if refetch {
+            self.usage = None;
+            let usage = inventory::storage_usage(&mut self.store, Some(container_id))?;
+            self.config.ext.quotas.check(&usage, storm_app, remote_id, size)
+        } else {
+            self.storage_usage()?;
+            let usage = self.usage.as_mut().expect("storage usage is just read");
+            let checked = self.config.ext.quotas.check(usage, storm_app, remote_id, size);
+            if checked.is_ok() {
+                usage.add(Some(storm_app), Some(remote_id), size);
+            }
+            checked
+        };
+
+        Ok(match checked {
+            Ok(()) => CtlMsg::ProcessContainer(container),
+            Err(quota) => {
+                warn!("Container {} of {} bytes exceeds quota: {}", container_id, size, quota);
+                CtlMsg::RejectContainer(QuotaRejection { size, quota })
+            }
+        })
+    }
+
     fn pick_or_start(
         &mut self,
         endpoints: &mut Endpoints,
-        client_id: Option<ClientId>,
+        client: Option<ClientRequest>,
     ) -> Result<(), DaemonError> {
         if self.pick_task(endpoints)? {
-            if let Some(client_id) = client_id {
-                let _ = self.send_rpc(
-                    endpoints,
-                    client_id,
-                    RpcMsg::Progress(s!("Container send request is forwarded to transfer service")),
-                );
+            if let Some(client) = client {
+                let _ = self.send_rpc(endpoints, client, ProgressEvent::Assigned);
             }
             return Ok(());
         }
 
-        let config = self.config.clone().into();
-        let _handle = self.launch_daemon(Daemon::Transferd, config)?;
-        if let Some(client_id) = client_id {
-            let _ = self.send_rpc(
-                endpoints,
-                client_id,
-                RpcMsg::Progress(s!("A new transfer service instance is started")),
-            );
+        let _handle = self.launch_daemon(Daemon::Transferd, self.config.clone())?;
+        if let Some(client) = client {
+            let _ = self.send_rpc(endpoints, client, ProgressEvent::Queued);
         }
 
         // TODO: Store daemon handlers
         Ok(())
     }
+
+    fn handle_lnp_reply(
+        &mut self,
+        endpoints: &mut Endpoints,
+        client: ClientRequest,
+        request: PeerRequest,
+        reply: PeerReply,
+    ) -> Result<(), DaemonError> {
+        let reply = match (request, reply) {
+            (PeerRequest::Connect(node_addr), PeerReply::Rejected(details)) => {
+                DaemonError::PeerUnreachable(node_addr, details).into()
+            }
+            (PeerRequest::Disconnect(remote_id), PeerReply::Rejected(_))
+                if !self.peers.contains_key(&remote_id) =>
+            {
+                DaemonError::UnknownPeer(remote_id).into()
+            }
+            (_, PeerReply::Rejected(info) | PeerReply::Failure(info)) => {
+                DaemonError::Lnp(info).into()
+            }
+
+            (PeerRequest::Connect(node_addr), PeerReply::Success) => {
+                info!("Connected to {}", node_addr);
+                self.peers.insert(node_addr.id, PeerInfo {
+                    remote_id: node_addr.id,
+                    addr: Some(node_addr.addr),
+                    storm: StormSupport::Unconfirmed,
+                });
+                self.request_apps(endpoints, node_addr.id);
+                RpcMsg::Success(Some(format!("Connected to {}", node_addr)).into())
+            }
+
+            (PeerRequest::Disconnect(remote_id), PeerReply::Success) => {
+                info!("Disconnected from {}", remote_id);
+                self.peers.remove(&remote_id);
+                RpcMsg::Success(Some(format!("Disconnected from {}", remote_id)).into())
+            }
+
+            (PeerRequest::ListPeers, PeerReply::Peers(remote_ids)) => {
+                self.peers.retain(|remote_id, _| remote_ids.contains(remote_id));
+                for remote_id in remote_ids {
+                    if self.peers.contains_key(&remote_id) {
+                        continue;
+                    }
+                    self.peers.insert(remote_id, PeerInfo {
+                        remote_id,
+                        addr: None,
+                        storm: StormSupport::Unconfirmed,
+                    });
+                }
+                let unconfirmed = self
+                    .peers
+                    .values()
+                    .filter(|peer| peer.storm == StormSupport::Unconfirmed)
+                    .map(|peer| peer.remote_id)
+                    .collect::<Vec<_>>();
+                for remote_id in unconfirmed {
+                    self.request_apps(endpoints, remote_id);
+                }
+                RpcMsg::Peers(self.peers.values().cloned().collect())
+            }
+
+            (request, reply) => {
+                DaemonError::Lnp(format!("unexpected reply {} to {}", reply, request)).into()
+            }
+        };
+        self.send_rpc(endpoints, client, reply)?;
+        Ok(())
+    }
+
+    /// Asks remote peer for the list of its Storm apps; the peer replies only if it supports
+    /// Storm protocol.
+    fn request_apps(&self, endpoints: &mut Endpoints, remote_id: NodeId) {
+        if let Err(err) = self.send_p2p(endpoints, remote_id, Messages::ListApps) {
+            warn!("Unable to request Storm apps from {}: {}", remote_id, err);
+        }
+    }
+
+    /// Requests repairable damaged containers from the remote peer, returning the number of
+    /// requested containers.
+    fn refetch(
+        &mut self,
+        endpoints: &mut Endpoints,
+        remote_id: NodeId,
+        damaged: &[DamagedContainer],
+    ) -> Result<u64, DaemonError> {
+        let mut count = 0u64;
+        for container in damaged.iter().filter(|container| container.is_repairable()) {
+            // Containers created locally are re-fetched as files
+            let storm_app = inventory::origin(&mut self.store, container.container_id)?
+                .and_then(|origin| origin.storm_app)
+                .unwrap_or(StormApp::FileTransfer);
+            info!(
+                "Re-fetching damaged container {} from {} for {}",
+                container.container_id, remote_id, storm_app
+            );
+            self.ctl_queue.push_back(CtlMsg::GetContainer(AddressedClientMsg {
+                remote_id,
+                client: None,
+                data: AppContainer {
+                    storm_app,
+                    container_id: ContainerFullId {
+                        message_id: zero!(),
+                        container_id: container.container_id,
+                    },
+                },
+            }));
+            count += 1;
+        }
+        if count > 0 {
+            self.pick_or_start(endpoints, None)?;
+        }
+        Ok(count)
+    }
 }
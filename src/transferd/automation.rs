@@ -12,19 +12,19 @@ use std::collections::BTreeSet;
 use std::fmt::Debug;
 
 use internet2::addr::NodeId;
-use microservices::esb::ClientId;
 use storm::p2p::{ChunkPull, ChunkPush};
 use storm::{
     p2p, Chunk, ChunkId, Container, ContainerFullId, ContainerHeader, ContainerId, ContainerInfo,
     StormApp,
 };
 use storm_rpc::{
-    RpcMsg, ServiceId, DB_TABLE_CHUNKS, DB_TABLE_CONTAINERS, DB_TABLE_CONTAINER_HEADERS,
+    fsck, gc, inventory, ClientRequest, ProgressEvent, RpcMsg, ServiceId, DB_TABLE_CHUNKS,
+    DB_TABLE_CONTAINERS, DB_TABLE_CONTAINER_HEADERS,
 };
 use strict_encoding::{StrictDecode, StrictEncode};
 
 use super::Runtime;
-use crate::bus::{CtlMsg, Endpoints, Responder};
+use crate::bus::{CtlMsg, Endpoints, QuotaRejection, Responder};
 use crate::DaemonError;
 
 #[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, Display, Error)]
@@ -65,13 +65,15 @@ pub enum ReceiveState {
         info: Info,
         total: usize,
         pending: BTreeSet<ChunkId>,
+        /// Size of the chunks received so far
+        bytes: u64,
     },
 }
 
 #[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug)]
 pub struct Info {
     pub app_id: StormApp,
-    pub client_id: Option<ClientId>,
+    pub client: Option<ClientRequest>,
     pub remote_id: NodeId,
     pub id: ContainerFullId,
 }
@@ -125,7 +127,7 @@ impl Runtime {
     pub(super) fn handle_receive(
         &mut self,
         endpoints: &mut Endpoints,
-        client_id: Option<ClientId>,
+        client: Option<ClientRequest>,
         storm_app: StormApp,
         remote_id: NodeId,
         id: ContainerFullId,
@@ -138,7 +140,7 @@ impl Runtime {
         self.state = State::Receive(ReceiveState::AwaitingContainer {
             info: Info {
                 app_id: storm_app,
-                client_id,
+                client,
                 remote_id,
                 id,
             },
@@ -151,8 +153,8 @@ impl Runtime {
         };
         self.send_p2p_reporting_client(
             endpoints,
-            client_id,
-            Some("Requested container"),
+            client,
+            &[ProgressEvent::ContainerRequested(id.container_id).into()],
             remote_id,
             p2p::Messages::PullContainer(msg),
         );
@@ -170,28 +172,41 @@ impl Runtime {
 
         debug!("Processing container info for {}", info.id.container_id);
 
-        if let Some(client_id) = info.client_id {
-            self.send_rpc(endpoints, client_id, RpcMsg::Progress("Container received".into()))?;
-        }
+        let id = container.container_id();
+
+        // Known containers are received again when they are re-fetched after a failed integrity
+        // check; their corrupted chunks are requested together with the missing ones
+        let refetch = gc::retrieve_live(&mut self.store, DB_TABLE_CONTAINERS, id)?.is_some();
 
         let header_chunk = Chunk::try_from(container.header.strict_serialize()?)?;
         let container_chunk = Chunk::try_from(container.strict_serialize()?)?;
 
-        let id = container.container_id();
         self.store.store(DB_TABLE_CONTAINER_HEADERS, id, &header_chunk)?;
         self.store.store(DB_TABLE_CONTAINERS, id, &container_chunk)?;
+        inventory::record_origin::<DaemonError>(
+            &mut self.store,
+            id,
+            Some(info.app_id),
+            Some(info.remote_id),
+        )?;
 
         // Prepare list of missed chunks
-        let chunk_ids = self
-            .store
-            .filter_unknown(DB_TABLE_CHUNKS, container.chunks.iter().copied().collect())?;
+        let chunk_ids = container.chunks.iter().copied().collect();
+        let chunk_ids = if refetch {
+            fsck::damaged_chunks(&mut self.store, chunk_ids)?
+        } else {
+            gc::missing_chunks(&mut self.store, chunk_ids)?
+        };
         let unknown_count = chunk_ids.len();
-        if let Some(client_id) = info.client_id {
-            self.send_rpc(
-                endpoints,
-                client_id,
-                RpcMsg::Progress(format!("Retrieving {} new chunks", unknown_count).into()),
-            )?;
+        if let Some(client) = info.client {
+            self.send_rpc(endpoints, client, ProgressEvent::ContainerReceived {
+                container_id: id,
+                missing_chunks: unknown_count as u64,
+            })?;
+        }
+
+        if chunk_ids.is_empty() {
+            return self.complete_receive(endpoints, info);
         }
 
         debug!("Requesting {} chunks", chunk_ids.len());
@@ -202,6 +217,7 @@ impl Runtime {
             info,
             total: unknown_count,
             pending: chunk_ids.clone(),
+            bytes: 0,
         });
 
         self.send_p2p(
@@ -218,6 +234,28 @@ impl Runtime {
         Ok(())
     }
 
+    /// Aborts receiving of a container which exceeds storage quota, as it was checked by stormd.
+    pub(super) fn handle_rejection(
+        &mut self,
+        endpoints: &mut Endpoints,
+        rejection: QuotaRejection,
+    ) -> Result<(), DaemonError> {
+        self.state.require_state(StateName::Receive(ReceiveStateName::AwaitingContainer))?;
+        let info = self.state.info().expect("receive state always have metadata");
+
+        let err = DaemonError::QuotaExceeded {
+            size: rejection.size,
+            quota: rejection.quota,
+        };
+        warn!("Rejecting container {}: {}", info.id.container_id, err);
+        self.state = StateTy::Free;
+        if let Some(client) = info.client {
+            self.send_rpc(endpoints, client, err)?;
+        }
+        self.send_ctl(endpoints, ServiceId::stormd(), CtlMsg::ProcessingFailed)?;
+        Ok(())
+    }
+
     pub(super) fn handle_chunk(
         &mut self,
         endpoints: &mut Endpoints,
@@ -229,36 +267,56 @@ impl Runtime {
         let chunk_id = chunk.chunk_id();
         debug!("Processing chunk {}", chunk_id);
 
-        if let Some(client_id) = info.client_id {
-            self.send_rpc(
-                endpoints,
-                client_id,
-                RpcMsg::Progress(format!("Received chunk {}", chunk_id).into()),
-            )?;
-        }
-
         self.store.store(DB_TABLE_CHUNKS, chunk_id, &chunk)?;
 
         // Switching the state
-        match &mut self.state {
-            State::Receive(ReceiveState::ReceivingChunks { pending, .. }) => {
+        let (done, total, bytes) = match &mut self.state {
+            State::Receive(ReceiveState::ReceivingChunks {
+                pending,
+                total,
+                bytes,
+                ..
+            }) => {
                 pending.remove(&chunk_id);
-                if pending.is_empty() {
-                    info!("Transfer service completed its work");
-                    self.state = StateTy::Free;
-                    self.send_ctl(endpoints, ServiceId::stormd(), CtlMsg::ProcessingComplete)?;
-                }
+                *bytes += chunk.len() as u64;
+                (*total - pending.len(), *total, *bytes)
             }
             _ => unreachable!(),
+        };
+
+        if let Some(client) = info.client {
+            self.send_rpc(endpoints, client, ProgressEvent::ChunkReceived {
+                done: done as u64,
+                total: total as u64,
+                bytes,
+            })?;
+        }
+
+        if done == total {
+            self.complete_receive(endpoints, info)?;
         }
 
         Ok(())
     }
 
+    fn complete_receive(
+        &mut self,
+        endpoints: &mut Endpoints,
+        info: Info,
+    ) -> Result<(), DaemonError> {
+        info!("Transfer service completed its work");
+        self.state = StateTy::Free;
+        if let Some(client) = info.client {
+            self.send_rpc(endpoints, client, RpcMsg::Success(None.into()))?;
+        }
+        self.send_ctl(endpoints, ServiceId::stormd(), CtlMsg::ProcessingComplete)?;
+        Ok(())
+    }
+
     pub(super) fn handle_announce(
         &mut self,
         endpoints: &mut Endpoints,
-        client_id: Option<ClientId>,
+        client: Option<ClientRequest>,
         storm_app: StormApp,
         remote_id: NodeId,
         id: ContainerFullId,
@@ -267,10 +325,9 @@ impl Runtime {
 
         debug!("Sending announcement for {}", id.container_id);
 
-        let header_chunk = self
-            .store
-            .retrieve_chunk(DB_TABLE_CONTAINER_HEADERS, id.container_id)?
-            .ok_or(DaemonError::UnknownContainer(id.container_id))?;
+        let header_chunk =
+            gc::retrieve_live(&mut self.store, DB_TABLE_CONTAINER_HEADERS, id.container_id)?
+                .ok_or(DaemonError::UnknownContainer(id.container_id))?;
         let header = ContainerHeader::strict_deserialize(header_chunk)?;
         let info = ContainerInfo { header, id };
         let msg = p2p::AppMsg {
@@ -279,8 +336,8 @@ impl Runtime {
         };
         self.send_p2p_reporting_client(
             endpoints,
-            client_id,
-            None,
+            client,
+            &[ProgressEvent::UploadSent.into(), RpcMsg::Success(None.into())],
             remote_id,
             p2p::Messages::AnnounceContainer(msg),
         );
@@ -292,7 +349,7 @@ impl Runtime {
     pub(super) fn handle_send_container(
         &mut self,
         endpoints: &mut Endpoints,
-        client_id: Option<ClientId>,
+        client: Option<ClientRequest>,
         storm_app: StormApp,
         remote_id: NodeId,
         id: ContainerFullId,
@@ -301,10 +358,9 @@ impl Runtime {
 
         debug!("Got container {}, saving to storage", id.container_id);
 
-        let container_chunk = self
-            .store
-            .retrieve_chunk(DB_TABLE_CONTAINERS, id.container_id)?
-            .ok_or(DaemonError::UnknownContainer(id.container_id))?;
+        let container_chunk =
+            gc::retrieve_live(&mut self.store, DB_TABLE_CONTAINERS, id.container_id)?
+                .ok_or(DaemonError::UnknownContainer(id.container_id))?;
         let container = Container::strict_deserialize(container_chunk)?;
         let msg = p2p::AppMsg {
             app: storm_app,
@@ -312,8 +368,8 @@ impl Runtime {
         };
         self.send_p2p_reporting_client(
             endpoints,
-            client_id,
-            None,
+            client,
+            &[ProgressEvent::UploadSent.into(), RpcMsg::Success(None.into())],
             remote_id,
             p2p::Messages::PushContainer(msg),
         );
@@ -337,7 +393,7 @@ impl Runtime {
 
         for chunk_id in chunk_ids {
             // We ignore failed chunks
-            if let Ok(Some(chunk)) = self.store.retrieve_chunk(DB_TABLE_CHUNKS, chunk_id) {
+            if let Ok(Some(chunk)) = gc::retrieve_live(&mut self.store, DB_TABLE_CHUNKS, chunk_id) {
                 let _ = self.send_p2p(
                     endpoints,
                     remote_id,
@@ -130,52 +130,50 @@ impl Runtime {
         match message {
             CtlMsg::GetContainer(AddressedClientMsg {
                 remote_id,
-                client_id,
+                client,
                 data:
                     AppContainer {
                         storm_app,
                         container_id,
                     },
             }) => {
-                self.handle_receive(endpoints, client_id, storm_app, remote_id, container_id)?;
+                self.handle_receive(endpoints, client, storm_app, remote_id, container_id)?;
             }
 
             CtlMsg::ProcessContainer(container) => {
                 self.handle_container(endpoints, container)?;
             }
 
+            CtlMsg::RejectContainer(rejection) => {
+                self.handle_rejection(endpoints, rejection)?;
+            }
+
             CtlMsg::ProcessChunk(chunk) => {
                 self.handle_chunk(endpoints, chunk)?;
             }
 
             CtlMsg::AnnounceContainer(AddressedClientMsg {
                 remote_id,
-                client_id,
+                client,
                 data:
                     AppContainer {
                         storm_app,
                         container_id,
                     },
             }) => {
-                self.handle_announce(endpoints, client_id, storm_app, remote_id, container_id)?;
+                self.handle_announce(endpoints, client, storm_app, remote_id, container_id)?;
             }
 
             CtlMsg::SendContainer(AddressedClientMsg {
                 remote_id,
-                client_id,
+                client,
                 data:
                     AppContainer {
                         storm_app,
                         container_id,
                     },
             }) => {
-                self.handle_send_container(
-                    endpoints,
-                    client_id,
-                    storm_app,
-                    remote_id,
-                    container_id,
-                )?;
+                self.handle_send_container(endpoints, client, storm_app, remote_id, container_id)?;
             }
 
             CtlMsg::SendChunks(AddressedMsg { remote_id, data }) => {